@@ -1,10 +1,16 @@
 use crate::error::AppError;
-use crate::fs::{self, FileEntry, watcher};
+use crate::fs::events::{as_window, WatchOptions};
+use crate::fs::merge::{merge3, MergeResult};
+use crate::fs::sandbox::{vault_root, ApprovedVaults, VaultSandbox};
+use crate::fs::search::{FileMatches, SearchOptions, SearchResults};
+use crate::fs::watcher::WatcherState;
+use crate::fs::{self, DirListing, FileEntry, VersionedContent};
 use tauri::{AppHandle, Manager, WebviewWindowBuilder, WebviewBuilder, LogicalPosition, LogicalSize, Position, Size};
 use tauri::WebviewUrl;
 use tauri::webview::NewWindowResponse;
-use tauri::Emitter;
+use tauri::{Emitter, State};
 use std::io::Read;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
 
 // Browser / WebView 调试日志，写入与前端相同的 debug-logs 目录，方便统一排查
 fn browser_debug_log(app: &AppHandle, message: String) {
@@ -15,31 +21,186 @@ fn browser_debug_log(app: &AppHandle, message: String) {
     });
 }
 
-/// Read file content
+/// Set the vault opened in the calling window; file commands are confined to open vaults
+/// A folder that wasn't approved before is confirmed with the user in a native dialog.
 #[tauri::command]
-pub async fn read_file(path: String) -> Result<String, AppError> {
-    fs::read_file_content(&path)
+pub async fn set_vault_root(
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    path: String,
+) -> Result<(), AppError> {
+    let root = vault_root(&path)?;
+    let approved = approved_vaults(&app)?;
+    if !approved.contains(&root) {
+        let message = format!(
+            "Open \"{}\" as a vault?\n\nLumina will be able to read and change every file in this folder.",
+            root.display()
+        );
+        let parent = window.clone();
+        let confirmed = tauri::async_runtime::spawn_blocking(move || {
+            app.dialog()
+                .message(message)
+                .title("Open vault")
+                .kind(MessageDialogKind::Warning)
+                .buttons(MessageDialogButtons::OkCancel)
+                .parent(&parent)
+                .blocking_show()
+        })
+        .await
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?;
+        if !confirmed {
+            return Err(AppError::OutsideVault(path));
+        }
+        approved.add(&root)?;
+    }
+    sandbox.set_vault(window.label(), &path)
+}
+
+/// Let the user pick a vault folder in a native dialog and open it in the calling window
+/// Returns the folder, `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn pick_vault_root(
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    title: Option<String>,
+) -> Result<Option<String>, AppError> {
+    let Some(picked) = pick_path(&app, &window, true, None, title).await? else {
+        return Ok(None);
+    };
+    let path = picked.to_string_lossy().to_string();
+    let root = vault_root(&path)?;
+    approved_vaults(&app)?.add(&root)?;
+    sandbox.set_vault(window.label(), &path)?;
+    Ok(Some(path))
 }
 
-/// Save file content
+/// Let the user pick a file or folder outside the vault in a native dialog and
+/// allow file commands on it (and everything below it)
+/// Returns the picked path, `None` if the dialog was cancelled.
 #[tauri::command]
-pub async fn save_file(path: String, content: String) -> Result<(), AppError> {
-    fs::write_file_content(&path, &content)
+pub async fn allow_external_path(
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    directory: Option<bool>,
+    default_path: Option<String>,
+    title: Option<String>,
+) -> Result<Option<String>, AppError> {
+    let picked = pick_path(&app, &window, directory.unwrap_or(false), default_path, title).await?;
+    let Some(picked) = picked else {
+        return Ok(None);
+    };
+    let path = picked.to_string_lossy().to_string();
+    sandbox.allow_external(&path)?;
+    Ok(Some(path))
+}
+
+/// Vault roots the user approved, stored in the app data dir
+fn approved_vaults(app: &AppHandle) -> Result<ApprovedVaults, AppError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::InvalidPath(format!("Failed to resolve app data dir: {}", e)))?;
+    Ok(ApprovedVaults::new(&dir))
+}
+
+/// Show a native file or folder picker attached to `window`
+async fn pick_path(
+    app: &AppHandle,
+    window: &tauri::WebviewWindow,
+    directory: bool,
+    default_path: Option<String>,
+    title: Option<String>,
+) -> Result<Option<std::path::PathBuf>, AppError> {
+    let app = app.clone();
+    let parent = window.clone();
+    let picked = tauri::async_runtime::spawn_blocking(move || {
+        let mut dialog = app.dialog().file().set_parent(&parent);
+        if let Some(path) = default_path {
+            dialog = dialog.set_directory(path);
+        }
+        if let Some(title) = title {
+            dialog = dialog.set_title(title);
+        }
+        if directory {
+            dialog.blocking_pick_folder()
+        } else {
+            dialog.blocking_pick_file()
+        }
+    })
+    .await
+    .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?;
+    picked
+        .map(|path| path.into_path().map_err(|e| AppError::InvalidPath(e.to_string())))
+        .transpose()
+}
+
+/// Read file content with its version token
+#[tauri::command]
+pub async fn read_file(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    path: String,
+) -> Result<VersionedContent, AppError> {
+    sandbox.check(window.label(), &path)?;
+    fs::read_file_versioned(&path)
+}
+
+/// Save file content (atomically; `backup` keeps the previous content as a hidden `.bak`)
+///
+/// With `expected_version`, fails with a conflict if the file changed on disk since
+/// it was read. Returns the new version token. Like the other write commands, the
+/// change is reported to every window on the vault except the calling one.
+#[tauri::command]
+pub async fn save_file(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    path: String,
+    content: String,
+    backup: Option<bool>,
+    expected_version: Option<String>,
+) -> Result<String, AppError> {
+    sandbox.check(window.label(), &path)?;
+    as_window(window.label(), || {
+        fs::write_file_checked(&path, &content, backup.unwrap_or(false), expected_version.as_deref())
+    })
+}
+
+/// Three-way merge of the editor's content (`local`) with the version on disk
+/// (`remote`), based on the content the editor loaded (`base`); for resolving
+/// save conflicts
+#[tauri::command]
+pub async fn merge_text(
+    base: String,
+    local: String,
+    remote: String,
+) -> Result<MergeResult, AppError> {
+    Ok(merge3(&base, &local, &remote))
 }
 
 /// Write binary file (for images, etc.)
 #[tauri::command]
-pub async fn write_binary_file(path: String, data: Vec<u8>) -> Result<(), AppError> {
-    let path = std::path::Path::new(&path);
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    std::fs::write(path, &data).map_err(AppError::from)
+pub async fn write_binary_file(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    path: String,
+    data: Vec<u8>,
+    backup: Option<bool>,
+) -> Result<(), AppError> {
+    sandbox.check(window.label(), &path)?;
+    as_window(window.label(), || fs::write_binary_content(&path, &data, backup.unwrap_or(false)))
 }
 
 /// Read binary file and return as base64
 #[tauri::command]
-pub async fn read_binary_file_base64(path: String) -> Result<String, AppError> {
+pub async fn read_binary_file_base64(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    path: String,
+) -> Result<String, AppError> {
+    sandbox.check(window.label(), &path)?;
     use base64::{Engine as _, engine::general_purpose::STANDARD};
     let data = std::fs::read(&path)?;
     Ok(STANDARD.encode(&data))
@@ -47,32 +208,141 @@ pub async fn read_binary_file_base64(path: String) -> Result<String, AppError> {
 
 /// List directory with file tree
 #[tauri::command]
-pub async fn list_directory(path: String) -> Result<Vec<FileEntry>, AppError> {
+pub async fn list_directory(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    path: String,
+) -> Result<Vec<FileEntry>, AppError> {
+    sandbox.check(window.label(), &path)?;
     fs::list_dir_recursive(&path)
 }
 
+/// List a single directory level (paginated), for lazily expanded trees
+/// Ignore rules are read from `vault_path` when given
+#[tauri::command]
+pub async fn list_directory_page(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    path: String,
+    vault_path: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<DirListing, AppError> {
+    sandbox.check(window.label(), &path)?;
+    if let Some(vault) = &vault_path {
+        sandbox.check(window.label(), vault)?;
+    }
+    fs::list_dir(&path, vault_path.as_deref(), offset.unwrap_or(0), limit)
+}
+
+/// Get metadata for a single file or directory
+#[tauri::command]
+pub async fn stat_file(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    path: String,
+    vault_path: Option<String>,
+) -> Result<FileEntry, AppError> {
+    sandbox.check(window.label(), &path)?;
+    if let Some(vault) = &vault_path {
+        sandbox.check(window.label(), vault)?;
+    }
+    fs::stat_entry(&path, vault_path.as_deref())
+}
+
 /// Create a new file
 #[tauri::command]
-pub async fn create_file(path: String) -> Result<(), AppError> {
-    fs::create_new_file(&path)
+pub async fn create_file(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    path: String,
+) -> Result<(), AppError> {
+    sandbox.check(window.label(), &path)?;
+    as_window(window.label(), || fs::create_new_file(&path))
 }
 
 /// Create a new directory
 #[tauri::command]
-pub async fn create_dir(path: String) -> Result<(), AppError> {
-    fs::create_new_dir(&path)
+pub async fn create_dir(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    path: String,
+) -> Result<(), AppError> {
+    sandbox.check(window.label(), &path)?;
+    as_window(window.label(), || fs::create_new_dir(&path))
 }
 
 /// Delete a file or directory
 #[tauri::command]
-pub async fn delete_file(path: String) -> Result<(), AppError> {
-    fs::delete_entry(&path)
+pub async fn delete_file(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    path: String,
+) -> Result<(), AppError> {
+    sandbox.check_entry(window.label(), &path)?;
+    as_window(window.label(), || fs::delete_entry(&path))
 }
 
 /// Rename/move a file
 #[tauri::command]
-pub async fn rename_file(old_path: String, new_path: String) -> Result<(), AppError> {
-    fs::rename_entry(&old_path, &new_path)
+pub async fn rename_file(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    old_path: String,
+    new_path: String,
+) -> Result<(), AppError> {
+    sandbox.check_entry(window.label(), &old_path)?;
+    sandbox.check_entry(window.label(), &new_path)?;
+    as_window(window.label(), || fs::rename_entry(&old_path, &new_path))
+}
+
+/// A file with matches, streamed while `search_vault` runs
+#[derive(serde::Serialize, Clone)]
+pub struct SearchMatchEventPayload {
+    pub search_id: String,
+    pub file: FileMatches,
+}
+
+/// Search file contents in the vault
+/// Uses the vault's ignore rules and skips binary files. With `stream_id`, each
+/// file with matches is emitted to the calling window as "search:match" while
+/// the search runs, and the returned `files` is empty (totals only).
+#[tauri::command]
+pub async fn search_vault(
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+    query: String,
+    options: Option<SearchOptions>,
+    stream_id: Option<String>,
+) -> Result<SearchResults, AppError> {
+    sandbox.check(window.label(), &vault_path)?;
+    let options = options.unwrap_or_default();
+    if let Some(directory) = &options.directory {
+        let directory = std::path::Path::new(&vault_path).join(directory);
+        sandbox.check(window.label(), &directory.to_string_lossy())?;
+    }
+
+    let target = tauri::EventTarget::webview_window(window.label());
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut files = Vec::new();
+        let stats = fs::search::search_vault(
+            std::path::Path::new(&vault_path),
+            &query,
+            &options,
+            &mut |file| match &stream_id {
+                Some(search_id) => {
+                    let payload = SearchMatchEventPayload { search_id: search_id.clone(), file };
+                    let _ = app.emit_to(target.clone(), "search:match", payload);
+                }
+                None => files.push(file),
+            },
+        )?;
+        Ok::<_, AppError>(SearchResults { files, stats })
+    })
+    .await
+    .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?
 }
 
 /// Show file/folder in system file explorer
@@ -515,14 +785,34 @@ pub struct VideoTimeInfo {
     pub paused: bool,
 }
 
-/// Start file system watcher
-/// Emits "fs:change" events when files are created, modified, or deleted
+/// Start file system watcher for the calling window
+/// Emits debounced "fs:change" batches when files are created, modified, renamed or deleted
+/// (except for the window's own writes).
+/// Starting again for the same window and path replaces the previous watcher.
 #[tauri::command]
-pub async fn start_file_watcher(app: AppHandle, watch_path: String) -> Result<(), AppError> {
-    watcher::start_watcher(app, watch_path)
+pub async fn start_file_watcher(
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    state: State<'_, WatcherState>,
+    watch_path: String,
+    options: Option<WatchOptions>,
+) -> Result<(), AppError> {
+    state
+        .start(app, window.label(), watch_path, options.unwrap_or_default())
         .map_err(|e| AppError::InvalidPath(e))
 }
 
+/// Stop the calling window's watcher for `watch_path` (all of its watchers when omitted)
+#[tauri::command]
+pub async fn stop_file_watcher(
+    window: tauri::WebviewWindow,
+    state: State<'_, WatcherState>,
+    watch_path: Option<String>,
+) -> Result<(), AppError> {
+    state.stop(window.label(), watch_path.as_deref());
+    Ok(())
+}
+
 #[derive(serde::Serialize, Clone)]
 pub struct BrowserNewTabEventPayload {
     pub parent_tab_id: String,
@@ -1,3 +1,4 @@
+use serde::ser::SerializeStruct;
 use serde::Serialize;
 use thiserror::Error;
 
@@ -23,6 +24,29 @@ pub enum AppError {
 
     #[error("WebDAV error: {0}")]
     WebDAV(String),
+
+    #[error("WebDAV authentication failed: {0}")]
+    WebDAVAuth(String),
+
+    #[error("WebDAV permission denied: {0}")]
+    WebDAVForbidden(String),
+
+    #[error("WebDAV resource not found: {0}")]
+    WebDAVNotFound(String),
+
+    #[error("Path is outside the vault: {0}")]
+    OutsideVault(String),
+
+    #[error("Invalid search: {0}")]
+    InvalidQuery(String),
+
+    /// The file changed on disk since it was read; carries the current content for merging
+    #[error("File changed on disk: {path}")]
+    Conflict {
+        path: String,
+        version: String,
+        content: String,
+    },
 }
 
 impl Serialize for AppError {
@@ -30,6 +54,18 @@ impl Serialize for AppError {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        match self {
+            // Structured so the frontend can offer a merge
+            AppError::Conflict { path, version, content } => {
+                let mut state = serializer.serialize_struct("AppError", 5)?;
+                state.serialize_field("kind", "conflict")?;
+                state.serialize_field("message", &self.to_string())?;
+                state.serialize_field("path", path)?;
+                state.serialize_field("version", version)?;
+                state.serialize_field("content", content)?;
+                state.end()
+            }
+            _ => serializer.serialize_str(&self.to_string()),
+        }
     }
 }
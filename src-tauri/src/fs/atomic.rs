@@ -0,0 +1,181 @@
+//! Crash-safe file writes
+//!
+//! Content is written to a temporary file in the same directory, flushed to
+//! disk and then renamed over the target, so a crash or power loss leaves either
+//! the old or the new content, never a truncated file.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::events::record_own_write;
+use crate::error::AppError;
+
+/// Distinguishes temp files of concurrent writes from the same process
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Backup of the previous content, kept next to the file (hidden from the tree)
+pub fn backup_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.bak", name))
+}
+
+/// Atomically replace `path` with `data`
+///
+/// Existing permissions are preserved and symlinks are written through.
+/// With `backup`, the previous content is first copied to [`backup_path`],
+/// replacing any older backup.
+pub fn write_atomic(path: &Path, data: &[u8], backup: bool) -> Result<(), AppError> {
+    write_atomic_checked(path, data, backup, || Ok(()))
+}
+
+/// Like [`write_atomic`], but runs `check` right before the rename and leaves
+/// the target untouched if it fails (e.g. because the file changed meanwhile)
+pub fn write_atomic_checked(
+    path: &Path,
+    data: &[u8],
+    backup: bool,
+    check: impl FnOnce() -> Result<(), AppError>,
+) -> Result<(), AppError> {
+    // Replace the link target, not the link itself
+    let target = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_symlink() => fs::canonicalize(path)?,
+        _ => path.to_path_buf(),
+    };
+    let name = target
+        .file_name()
+        .ok_or_else(|| AppError::InvalidPath(path.display().to_string()))?
+        .to_string_lossy()
+        .to_string();
+    let existing = fs::metadata(&target).ok();
+    if let Some(meta) = &existing {
+        if meta.is_dir() {
+            return Err(AppError::InvalidPath(path.display().to_string()));
+        }
+        // A read-only file must stay untouched, as with a plain write
+        if meta.permissions().readonly() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("File is read-only: {}", path.display()),
+            )
+            .into());
+        }
+    }
+
+    let temp = target.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = write_temp(&temp, data, existing.as_ref())
+        .map_err(AppError::from)
+        .and_then(|_| {
+            if backup && existing.is_some() {
+                fs::copy(&target, backup_path(&target))?;
+            }
+            check()?;
+            fs::rename(&temp, &target)?;
+            Ok(())
+        });
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    record_own_write(&temp);
+    record_own_write(&target);
+    record_own_write(path);
+
+    sync_parent(&target);
+    Ok(())
+}
+
+fn write_temp(temp: &Path, data: &[u8], existing: Option<&fs::Metadata>) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(temp)?;
+    file.write_all(data)?;
+    if let Some(meta) = existing {
+        file.set_permissions(meta.permissions())?;
+    }
+    file.sync_all()
+}
+
+/// Flush the directory entry so the rename itself survives a crash
+fn sync_parent(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_replaces_and_backs_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.md");
+
+        write_atomic(&path, b"first", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+        assert!(!backup_path(&path).exists());
+
+        write_atomic(&path, b"second", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "first");
+
+        write_atomic(&path, b"third", false).unwrap();
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "first");
+
+        // No temp files left behind
+        let names: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names.len(), 2, "{:?}", names);
+    }
+
+    #[test]
+    fn test_failed_check_leaves_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.md");
+        fs::write(&path, "original").unwrap();
+
+        let result = write_atomic_checked(&path, b"new", false, || {
+            Err(AppError::InvalidPath(path.display().to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_permissions_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("script.sh");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+
+        write_atomic(&path, b"new", false).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+
+        let link = dir.path().join("link.sh");
+        std::os::unix::fs::symlink(&path, &link).unwrap();
+        write_atomic(&link, b"through link", false).unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "through link");
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o444)).unwrap();
+        assert!(write_atomic(&path, b"denied", false).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "through link");
+    }
+}
@@ -0,0 +1,728 @@
+//! Watcher event processing
+//!
+//! Turns raw `notify` events into the `FsEvent` batches sent to the frontend:
+//! rename halves are paired, paths hidden by the vault's ignore rules or the
+//! extension filter are dropped, repeated changes to one path are coalesced,
+//! and changes caused by the app's own writes are suppressed in the window that
+//! made them (as long as the path still looks as the write left it). Backend features subscribe to a vault's raw changes through
+//! [`ChangeListeners`].
+
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use super::VaultIgnore;
+
+/// How long a write made by the app can suppress watcher events for its path
+const OWN_WRITE_WINDOW: Duration = Duration::from_secs(2);
+
+/// File extensions reported by default: notes, databases, PDFs, images and canvases
+const DEFAULT_EXTENSIONS: &[&str] = &[
+    "md", "db.json", "pdf", "png", "jpg", "jpeg", "gif", "webp", "svg", "canvas", "excalidraw",
+];
+
+/// File system event types
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum FsEvent {
+    Created { path: String },
+    Modified { path: String },
+    Deleted { path: String },
+    Renamed { old_path: String, new_path: String },
+}
+
+/// Watcher configuration from the frontend
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WatchOptions {
+    /// Extensions to report (without the dot, e.g. `pdf` or `db.json`); empty reports every file
+    pub extensions: Vec<String>,
+    /// Quiet period before a batch is emitted
+    pub debounce_ms: u64,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            extensions: DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            debounce_ms: 200,
+        }
+    }
+}
+
+/// What a path looked like right after a write
+#[derive(Debug, PartialEq)]
+enum Snapshot {
+    Missing,
+    Dir,
+    File { modified: Option<SystemTime>, len: u64 },
+}
+
+impl Snapshot {
+    fn of(path: &Path) -> Self {
+        match fs::metadata(path) {
+            Err(_) => Snapshot::Missing,
+            Ok(meta) if meta.is_dir() => Snapshot::Dir,
+            Ok(meta) => Snapshot::File {
+                modified: meta.modified().ok(),
+                len: meta.len(),
+            },
+        }
+    }
+}
+
+/// A recent write by the app
+struct OwnWrite {
+    at: Instant,
+    /// Window that asked for it (None: backend work, suppressed everywhere)
+    writer: Option<String>,
+    after: Snapshot,
+}
+
+type OwnWrites = HashMap<PathBuf, OwnWrite>;
+
+fn own_writes() -> &'static Mutex<OwnWrites> {
+    static OWN_WRITES: OnceLock<Mutex<OwnWrites>> = OnceLock::new();
+    OWN_WRITES.get_or_init(Default::default)
+}
+
+thread_local! {
+    /// Window the current command writes for (see [`as_window`])
+    static WRITER: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with the app's writes attributed to `window`
+///
+/// Only that window's watchers suppress the resulting events, so other windows
+/// on the same vault still see the change. Writes made outside `as_window`
+/// (backend work such as sync) are suppressed in every window.
+pub fn as_window<T>(window: &str, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<String>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            WRITER.with(|writer| *writer.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(WRITER.with(|writer| writer.replace(Some(window.to_string()))));
+    f()
+}
+
+/// Record that the app itself just changed `path`, so the watcher doesn't echo
+/// the change back to the window that made it
+///
+/// Call it after the change: events are only suppressed while the path still
+/// looks as the write left it, so an external edit right afterwards is reported.
+pub fn record_own_write(path: &Path) {
+    let writer = WRITER.with(|writer| writer.borrow().clone());
+    let after = Snapshot::of(path);
+    if let Ok(mut writes) = own_writes().lock() {
+        let now = Instant::now();
+        writes.retain(|_, write| now.duration_since(write.at) < OWN_WRITE_WINDOW);
+        writes.insert(path.to_path_buf(), OwnWrite { at: now, writer, after });
+    }
+}
+
+fn is_own_write(path: &str, window: &str) -> bool {
+    matches_own_write(Path::new(path), |writer| writer.is_none_or(|w| w == window))
+}
+
+/// Whether `path` still looks as a recent write attributed to `writer` (see
+/// [`as_window`]) left it, so backend features can skip changes they made
+pub fn is_own_write_by(path: &Path, writer: &str) -> bool {
+    matches_own_write(path, |w| w == Some(writer))
+}
+
+fn matches_own_write(path: &Path, writer_matches: impl Fn(Option<&str>) -> bool) -> bool {
+    let Ok(writes) = own_writes().lock() else {
+        return false;
+    };
+    writes.get(path).is_some_and(|write| {
+        write.at.elapsed() < OWN_WRITE_WINDOW
+            && writer_matches(write.writer.as_deref())
+            && write.after == Snapshot::of(path)
+    })
+}
+
+type ChangeCallback = Arc<dyn Fn(&[&Path]) + Send + Sync>;
+
+#[derive(Default)]
+struct Listeners {
+    next_id: u64,
+    entries: Vec<(u64, PathBuf, ChangeCallback)>,
+}
+
+/// Callbacks for changed paths under a vault, fed by the vault's watchers,
+/// so backend features don't need watchers of their own
+#[derive(Clone, Default)]
+pub struct ChangeListeners(Arc<Mutex<Listeners>>);
+
+impl ChangeListeners {
+    /// Call `callback` with every batch of changed paths under `vault` until
+    /// the returned subscription is dropped
+    pub fn subscribe(
+        &self,
+        vault: &Path,
+        callback: impl Fn(&[&Path]) + Send + Sync + 'static,
+    ) -> ChangeSubscription {
+        let mut listeners = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        listeners.next_id += 1;
+        let id = listeners.next_id;
+        listeners
+            .entries
+            .push((id, vault.to_path_buf(), Arc::new(callback)));
+        ChangeSubscription {
+            listeners: self.clone(),
+            id,
+        }
+    }
+
+    /// Pass a batch of changed paths under `vault` to its subscribers
+    pub fn notify(&self, vault: &Path, paths: &[&Path]) {
+        let callbacks: Vec<ChangeCallback> = match self.0.lock() {
+            Ok(listeners) => listeners
+                .entries
+                .iter()
+                .filter(|(_, root, _)| root == vault)
+                .map(|(_, _, callback)| callback.clone())
+                .collect(),
+            Err(_) => return,
+        };
+        for callback in callbacks {
+            callback(paths);
+        }
+    }
+
+    /// Feed the subscribers of `vault` from a watcher of their own, for when no
+    /// window watches the vault; stops when the returned feed is dropped
+    ///
+    /// Like the window watchers, changes are passed on in batches once the
+    /// stream has been quiet briefly, so the writer has recorded its own writes.
+    pub fn watch(&self, vault: &Path) -> notify::Result<ChangeFeed> {
+        let (tx, rx) = std::sync::mpsc::channel::<Event>();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: notify::Result<Event>| {
+                if let Ok(event) = res {
+                    let _ = tx.send(event);
+                }
+            },
+            Config::default(),
+        )?;
+        watcher.watch(vault, RecursiveMode::Recursive)?;
+
+        // Ends once the watcher is dropped
+        let listeners = self.clone();
+        let root = vault.to_path_buf();
+        let quiet = Duration::from_millis(WatchOptions::default().debounce_ms);
+        std::thread::spawn(move || {
+            while let Ok(first) = rx.recv() {
+                let mut batch = vec![first];
+                let deadline = Instant::now() + quiet * 10;
+                while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                    match rx.recv_timeout(quiet.min(remaining)) {
+                        Ok(event) => batch.push(event),
+                        Err(_) => break,
+                    }
+                }
+                let paths: Vec<&Path> = batch
+                    .iter()
+                    .filter(|e| !matches!(e.kind, EventKind::Access(_)))
+                    .flat_map(|e| e.paths.iter().map(|p| p.as_path()))
+                    .collect();
+                listeners.notify(&root, &paths);
+            }
+        });
+        Ok(ChangeFeed { _watcher: watcher })
+    }
+}
+
+/// Watcher started by [`ChangeListeners::watch`]
+pub struct ChangeFeed {
+    _watcher: RecommendedWatcher,
+}
+
+/// Unsubscribes from [`ChangeListeners`] when dropped
+pub struct ChangeSubscription {
+    listeners: ChangeListeners,
+    id: u64,
+}
+
+impl Drop for ChangeSubscription {
+    fn drop(&mut self) {
+        if let Ok(mut listeners) = self.listeners.0.lock() {
+            listeners.entries.retain(|(id, _, _)| *id != self.id);
+        }
+    }
+}
+
+/// Converts batches of raw events for one vault and window into `FsEvent`s
+pub struct EventProcessor {
+    root: PathBuf,
+    window: String,
+    rules: VaultIgnore,
+    extensions: Vec<String>,
+}
+
+impl EventProcessor {
+    pub fn new(root: &Path, window: &str, options: &WatchOptions) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            window: window.to_string(),
+            rules: VaultIgnore::load(root),
+            extensions: options
+                .extensions
+                .iter()
+                .map(|e| e.trim_start_matches('.').to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn rules(&self) -> &VaultIgnore {
+        &self.rules
+    }
+
+    /// Reload the vault's ignore rules (after `.luminaignore` changed)
+    pub fn reload_rules(&mut self) {
+        self.rules = VaultIgnore::load(&self.root);
+    }
+
+    /// Pair, filter, coalesce and de-echo one batch of raw events
+    pub fn process(&self, events: &[Event]) -> Vec<FsEvent> {
+        let mut coalescer = Coalescer::default();
+        for change in pair_renames(events) {
+            if let Some(change) = self.visible(change) {
+                coalescer.push(change);
+            }
+        }
+        coalescer
+            .finish()
+            .into_iter()
+            .filter(|event| match event {
+                FsEvent::Renamed { old_path, new_path } => {
+                    !(is_own_write(old_path, &self.window) && is_own_write(new_path, &self.window))
+                }
+                FsEvent::Created { path } | FsEvent::Modified { path } | FsEvent::Deleted { path } => {
+                    !is_own_write(path, &self.window)
+                }
+            })
+            .collect()
+    }
+
+    /// Drop hidden paths; a rename across the visibility boundary becomes a
+    /// deletion or a modification (an editor's atomic save through a hidden temp file)
+    fn visible(&self, change: FsEvent) -> Option<FsEvent> {
+        match change {
+            FsEvent::Renamed { old_path, new_path } => {
+                match (self.is_visible(&old_path), self.is_visible(&new_path)) {
+                    (true, true) => Some(FsEvent::Renamed { old_path, new_path }),
+                    (true, false) => Some(FsEvent::Deleted { path: old_path }),
+                    (false, true) => Some(FsEvent::Modified { path: new_path }),
+                    (false, false) => None,
+                }
+            }
+            FsEvent::Created { ref path } | FsEvent::Modified { ref path } | FsEvent::Deleted { ref path } => {
+                self.is_visible(path).then_some(change)
+            }
+        }
+    }
+
+    /// Whether a path belongs to the vault and passes the extension filter
+    pub fn is_visible(&self, path: &str) -> bool {
+        let path = Path::new(path);
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        if relative.as_os_str().is_empty() {
+            return false;
+        }
+
+        let is_dir = path.is_dir();
+        // A deleted path may have been a directory; only hide it if hidden both ways
+        let ignored = if is_dir || path.exists() {
+            self.rules.is_ignored(relative, is_dir)
+        } else {
+            self.rules.is_ignored(relative, false) && self.rules.is_ignored(relative, true)
+        };
+        if ignored {
+            return false;
+        }
+
+        if is_dir || self.extensions.is_empty() {
+            return true;
+        }
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        // Without an extension a deleted path is most likely a directory
+        (!path.exists() && !name.contains('.'))
+            || self.extensions.iter().any(|ext| name.ends_with(&format!(".{}", ext)))
+    }
+}
+
+/// Flatten raw events into changes, pairing rename halves
+///
+/// Backends report renames differently: inotify sends `From`, `To` and then
+/// `Both` sharing a tracker, Windows sends untracked `From`/`To` pairs and
+/// FSEvents sends `Any` for each side. Unpaired halves become deletions or
+/// creations (moved out of / into the vault).
+fn pair_renames(events: &[Event]) -> Vec<FsEvent> {
+    let mut changes = Vec::new();
+    let mut pending_from: Vec<(PathBuf, Option<usize>)> = Vec::new();
+    let mut paired_trackers = Vec::new();
+    let path_string = |p: &Path| p.to_string_lossy().to_string();
+
+    for event in events {
+        let tracker = event.attrs.tracker();
+        match &event.kind {
+            EventKind::Create(_) => {
+                changes.extend(event.paths.iter().map(|p| FsEvent::Created { path: path_string(p) }));
+            }
+            EventKind::Remove(_) => {
+                changes.extend(event.paths.iter().map(|p| FsEvent::Deleted { path: path_string(p) }));
+            }
+            EventKind::Modify(ModifyKind::Name(mode)) => {
+                let mode = match mode {
+                    // FSEvents doesn't say which side this is
+                    RenameMode::Any | RenameMode::Other => match event.paths.first() {
+                        Some(p) if p.exists() => RenameMode::To,
+                        _ => RenameMode::From,
+                    },
+                    mode => *mode,
+                };
+                match (mode, event.paths.as_slice()) {
+                    // Skip the `Both` that repeats an already paired From/To
+                    (RenameMode::Both, [from, to])
+                        if tracker.is_none() || !paired_trackers.contains(&tracker) =>
+                    {
+                        changes.push(FsEvent::Renamed {
+                            old_path: path_string(from),
+                            new_path: path_string(to),
+                        });
+                    }
+                    (RenameMode::From, [from]) => pending_from.push((from.clone(), tracker)),
+                    (RenameMode::To, [to]) => {
+                        let matching = pending_from
+                            .iter()
+                            .rposition(|(_, t)| tracker.is_none() || *t == tracker);
+                        match matching {
+                            Some(index) => {
+                                let (from, _) = pending_from.remove(index);
+                                paired_trackers.push(tracker);
+                                changes.push(FsEvent::Renamed {
+                                    old_path: path_string(&from),
+                                    new_path: path_string(to),
+                                });
+                            }
+                            None => changes.push(FsEvent::Created { path: path_string(to) }),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            // Metadata-only changes (permissions, access times) are not content changes
+            EventKind::Modify(ModifyKind::Metadata(_)) => {}
+            EventKind::Modify(_) => {
+                changes.extend(event.paths.iter().map(|p| FsEvent::Modified { path: path_string(p) }));
+            }
+            EventKind::Access(_) | EventKind::Any | EventKind::Other => {}
+        }
+    }
+
+    changes.extend(
+        pending_from
+            .into_iter()
+            .map(|(from, _)| FsEvent::Deleted { path: path_string(&from) }),
+    );
+    changes
+}
+
+/// Merges successive changes to the same path (e.g. create + modify = create)
+#[derive(Default)]
+struct Coalescer {
+    events: Vec<Option<FsEvent>>,
+    /// Current path -> index of the last event that left something at that path
+    latest: HashMap<String, usize>,
+}
+
+impl Coalescer {
+    fn push(&mut self, event: FsEvent) {
+        let source = match &event {
+            FsEvent::Renamed { old_path, .. } => old_path,
+            FsEvent::Created { path } | FsEvent::Modified { path } | FsEvent::Deleted { path } => path,
+        };
+        let prior = self.latest.get(source).copied();
+        let previous = prior.and_then(|i| self.events[i].clone());
+
+        match (previous, event) {
+            (Some(FsEvent::Deleted { .. }), FsEvent::Created { path } | FsEvent::Modified { path }) => {
+                self.replace(prior, FsEvent::Modified { path });
+            }
+            (
+                Some(FsEvent::Created { .. } | FsEvent::Modified { .. }),
+                FsEvent::Created { .. } | FsEvent::Modified { .. },
+            ) => {}
+            (Some(FsEvent::Created { path }), FsEvent::Deleted { .. }) => {
+                self.events[prior.unwrap()] = None;
+                self.latest.remove(&path);
+            }
+            (Some(FsEvent::Modified { .. }), FsEvent::Deleted { path }) => {
+                self.replace(prior, FsEvent::Deleted { path });
+            }
+            (Some(FsEvent::Renamed { old_path, new_path }), FsEvent::Deleted { .. }) => {
+                self.latest.remove(&new_path);
+                self.replace(prior, FsEvent::Deleted { path: old_path });
+            }
+            (Some(FsEvent::Created { path }), FsEvent::Renamed { new_path, .. }) => {
+                self.latest.remove(&path);
+                self.replace(prior, FsEvent::Created { path: new_path });
+            }
+            (Some(FsEvent::Renamed { old_path, new_path: via }), FsEvent::Renamed { new_path, .. }) => {
+                self.latest.remove(&via);
+                let merged = if old_path == new_path {
+                    FsEvent::Modified { path: new_path }
+                } else {
+                    FsEvent::Renamed { old_path, new_path }
+                };
+                self.replace(prior, merged);
+            }
+            (_, event) => {
+                if let FsEvent::Renamed { old_path, .. } = &event {
+                    self.latest.remove(old_path);
+                }
+                self.events.push(None);
+                self.replace(Some(self.events.len() - 1), event);
+            }
+        }
+    }
+
+    fn replace(&mut self, index: Option<usize>, event: FsEvent) {
+        let index = index.expect("replace needs an existing event");
+        let path = match &event {
+            FsEvent::Renamed { new_path, .. } => new_path,
+            FsEvent::Created { path } | FsEvent::Modified { path } | FsEvent::Deleted { path } => path,
+        };
+        self.latest.insert(path.clone(), index);
+        self.events[index] = Some(event);
+    }
+
+    fn finish(self) -> Vec<FsEvent> {
+        self.events.into_iter().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, RemoveKind};
+    use std::fs;
+
+    fn event(kind: EventKind, paths: &[&Path]) -> Event {
+        paths
+            .iter()
+            .fold(Event::new(kind), |event, path| event.add_path(path.to_path_buf()))
+    }
+
+    fn rename(mode: RenameMode, paths: &[&Path], tracker: Option<usize>) -> Event {
+        let event = event(EventKind::Modify(ModifyKind::Name(mode)), paths);
+        match tracker {
+            Some(tracker) => event.set_tracker(tracker),
+            None => event,
+        }
+    }
+
+    fn modify(path: &Path) -> Event {
+        event(EventKind::Modify(ModifyKind::Data(DataChange::Any)), &[path])
+    }
+
+    fn s(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_renames_are_paired_across_backends() {
+        let vault = tempfile::tempdir().unwrap();
+        let root = vault.path();
+        let (a, b, c) = (root.join("a.md"), root.join("b.md"), root.join("c.md"));
+        fs::write(&b, "").unwrap();
+        fs::write(&c, "").unwrap();
+        let processor = EventProcessor::new(root, "main", &WatchOptions::default());
+        let renamed = |old: &Path, new: &Path| FsEvent::Renamed { old_path: s(old), new_path: s(new) };
+
+        // inotify: From, To and Both with the same tracker
+        let inotify = [
+            rename(RenameMode::From, &[&a], Some(7)),
+            rename(RenameMode::To, &[&b], Some(7)),
+            rename(RenameMode::Both, &[&a, &b], Some(7)),
+        ];
+        assert_eq!(processor.process(&inotify), vec![renamed(&a, &b)]);
+
+        // Windows: untracked From/To
+        let windows = [rename(RenameMode::From, &[&a], None), rename(RenameMode::To, &[&c], None)];
+        assert_eq!(processor.process(&windows), vec![renamed(&a, &c)]);
+
+        // FSEvents: Any for both sides, told apart by existence
+        let fsevents = [rename(RenameMode::Any, &[&a], None), rename(RenameMode::Any, &[&b], None)];
+        assert_eq!(processor.process(&fsevents), vec![renamed(&a, &b)]);
+
+        // Moved out of and into the vault
+        let outside = PathBuf::from("/elsewhere/x.md");
+        let moved_out = [rename(RenameMode::From, &[&a], Some(1))];
+        assert_eq!(processor.process(&moved_out), vec![FsEvent::Deleted { path: s(&a) }]);
+        let moved_in = [rename(RenameMode::Both, &[&outside, &b], Some(2))];
+        assert_eq!(processor.process(&moved_in), vec![FsEvent::Modified { path: s(&b) }]);
+    }
+
+    #[test]
+    fn test_batches_are_coalesced_and_filtered() {
+        let vault = tempfile::tempdir().unwrap();
+        let root = vault.path();
+        let note = root.join("note.md");
+        let image = root.join("img.png");
+        let log = root.join("debug.log");
+        let temp = root.join(".note.md.tmp");
+        for path in [&note, &image, &log] {
+            fs::write(path, "").unwrap();
+        }
+        let processor = EventProcessor::new(root, "main", &WatchOptions::default());
+
+        let batch = [
+            event(EventKind::Create(CreateKind::File), &[&note]),
+            modify(&note),
+            modify(&note),
+            modify(&image),
+            modify(&log),
+            modify(&root.join(".git/index")),
+            event(EventKind::Create(CreateKind::File), &[&root.join("gone.md")]),
+            event(EventKind::Remove(RemoveKind::File), &[&root.join("gone.md")]),
+        ];
+        assert_eq!(
+            processor.process(&batch),
+            vec![FsEvent::Created { path: s(&note) }, FsEvent::Modified { path: s(&image) }]
+        );
+
+        // An editor's atomic save through a hidden temp file is a modification
+        let atomic_save = [
+            event(EventKind::Create(CreateKind::File), &[&temp]),
+            modify(&temp),
+            rename(RenameMode::Both, &[&temp, &note], Some(3)),
+        ];
+        assert_eq!(processor.process(&atomic_save), vec![FsEvent::Modified { path: s(&note) }]);
+
+        // Empty extension list reports every file
+        let all = EventProcessor::new(root, "main", &WatchOptions { extensions: Vec::new(), debounce_ms: 0 });
+        assert_eq!(all.process(&[modify(&log)]), vec![FsEvent::Modified { path: s(&log) }]);
+    }
+
+    #[test]
+    fn test_coalescing_rules() {
+        let path = |name: &str| name.to_string();
+        let run = |events: Vec<FsEvent>| {
+            let mut coalescer = Coalescer::default();
+            events.into_iter().for_each(|e| coalescer.push(e));
+            coalescer.finish()
+        };
+        let renamed = |a: &str, b: &str| FsEvent::Renamed { old_path: path(a), new_path: path(b) };
+
+        assert_eq!(
+            run(vec![FsEvent::Deleted { path: path("a") }, FsEvent::Created { path: path("a") }]),
+            vec![FsEvent::Modified { path: path("a") }]
+        );
+        assert_eq!(
+            run(vec![FsEvent::Created { path: path("a") }, renamed("a", "b")]),
+            vec![FsEvent::Created { path: path("b") }]
+        );
+        assert_eq!(run(vec![renamed("a", "b"), renamed("b", "c")]), vec![renamed("a", "c")]);
+        assert_eq!(
+            run(vec![renamed("a", "b"), FsEvent::Deleted { path: path("b") }]),
+            vec![FsEvent::Deleted { path: path("a") }]
+        );
+        // A new file at the vacated path is a separate change
+        assert_eq!(
+            run(vec![renamed("a", "b"), FsEvent::Created { path: path("a") }]),
+            vec![renamed("a", "b"), FsEvent::Created { path: path("a") }]
+        );
+    }
+
+    #[test]
+    fn test_own_writes_are_suppressed() {
+        let vault = tempfile::tempdir().unwrap();
+        let note = vault.path().join("own.md");
+        let other = vault.path().join("other.md");
+        fs::write(&note, "").unwrap();
+        fs::write(&other, "").unwrap();
+        let processor = EventProcessor::new(vault.path(), "main", &WatchOptions::default());
+        let other_window = EventProcessor::new(vault.path(), "second", &WatchOptions::default());
+
+        record_own_write(&note);
+        assert_eq!(
+            processor.process(&[modify(&note), modify(&other)]),
+            vec![FsEvent::Modified { path: s(&other) }]
+        );
+        assert_eq!(other_window.process(&[modify(&note)]), Vec::new());
+
+        // An external edit after the app's write is still reported
+        fs::write(&note, "edited elsewhere").unwrap();
+        assert_eq!(
+            processor.process(&[modify(&note)]),
+            vec![FsEvent::Modified { path: s(&note) }]
+        );
+
+        // A window's own write still reaches the other windows on the vault
+        as_window("main", || record_own_write(&other));
+        assert!(is_own_write_by(&other, "main"));
+        assert!(!is_own_write_by(&other, "second"));
+        assert_eq!(processor.process(&[modify(&other)]), Vec::new());
+        assert_eq!(
+            other_window.process(&[modify(&other)]),
+            vec![FsEvent::Modified { path: s(&other) }]
+        );
+    }
+
+    #[test]
+    fn test_change_listeners() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let listeners = ChangeListeners::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let subscription = listeners.subscribe(Path::new("/vault"), move |paths| {
+            counter.fetch_add(paths.len(), Ordering::SeqCst);
+        });
+
+        let changed = Path::new("/vault/a.md");
+        listeners.notify(Path::new("/vault/"), &[changed, changed]);
+        listeners.notify(Path::new("/other"), &[changed]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        drop(subscription);
+        listeners.notify(Path::new("/vault"), &[changed]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_change_feed_without_window_watcher() {
+        let vault = tempfile::tempdir().unwrap();
+        let root = vault.path().canonicalize().unwrap();
+        let listeners = ChangeListeners::default();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let _subscription = listeners.subscribe(&root, move |paths| {
+            let _ = tx.send(paths.iter().map(|p| p.to_path_buf()).collect::<Vec<_>>());
+        });
+        let feed = listeners.watch(&root).unwrap();
+
+        fs::write(root.join("a.md"), "alpha").unwrap();
+        let paths = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(paths.contains(&root.join("a.md")));
+
+        drop(feed);
+    }
+}
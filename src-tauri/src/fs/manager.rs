@@ -1,83 +1,226 @@
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use serde::Serialize;
+use sha2::{Digest, Sha256};
 
+use super::atomic::{write_atomic, write_atomic_checked};
+use super::events::record_own_write;
+use super::vault_ignore::IGNORE_FILE;
+use super::VaultIgnore;
 use crate::error::AppError;
 
-#[derive(Debug, Serialize, Clone)]
+/// How long a directory's sorted listing is reused for further pages
+const LISTING_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
 pub struct FileEntry {
     pub name: String,
     pub path: String,
     pub is_dir: bool,
+    /// Size in bytes (0 for directories)
+    pub size: u64,
+    /// Last modification time in Unix milliseconds
+    pub modified: Option<u64>,
+    /// Creation time in Unix milliseconds (not every filesystem records it)
+    pub created: Option<u64>,
+    /// Number of visible children, for directories
+    pub child_count: Option<usize>,
     pub children: Option<Vec<FileEntry>>,
 }
 
+/// One page of a single-level directory listing
+#[derive(Debug, Serialize, Clone)]
+pub struct DirListing {
+    pub entries: Vec<FileEntry>,
+    /// Number of visible entries in the directory, across all pages
+    pub total: usize,
+}
+
+/// Incremental file tree update derived from a watcher event
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum TreeChange {
+    /// The entry was created or changed; insert or replace it under its parent
+    Upsert { entry: FileEntry },
+    /// The entry (and everything below it) no longer exists
+    Removed { path: String },
+    /// The vault's ignore rules changed; the whole tree must be relisted
+    Reset,
+}
+
+/// File content together with the version it was read at
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VersionedContent {
+    pub content: String,
+    /// Token to pass back when saving (see [`write_file_checked`])
+    pub version: String,
+}
+
 /// Read file content as UTF-8 string
 pub fn read_file_content(path: &str) -> Result<String, AppError> {
+    read_file_versioned(path).map(|file| file.content)
+}
+
+/// Read file content along with its version token
+pub fn read_file_versioned(path: &str) -> Result<VersionedContent, AppError> {
     let path = Path::new(path);
     if !path.exists() {
         return Err(AppError::FileNotFound(path.display().to_string()));
     }
-    fs::read_to_string(path).map_err(AppError::from)
+    let content = fs::read_to_string(path)?;
+    let modified = fs::metadata(path).ok().and_then(|m| unix_millis(m.modified()));
+    Ok(VersionedContent {
+        version: version_token(modified, content.as_bytes()),
+        content,
+    })
+}
+
+/// Version token: modification time plus content hash (`<mtime ms>-<sha256>`)
+fn version_token(modified: Option<u64>, data: &[u8]) -> String {
+    format!("{}-{:x}", modified.unwrap_or(0), Sha256::digest(data))
+}
+
+/// Whether two tokens describe the same content
+///
+/// Only the hash is compared: a touch or a sync download of identical content
+/// changes the mtime but is not a conflict.
+fn same_version(a: &str, b: &str) -> bool {
+    let hash = |token: &str| token.split_once('-').map(|(_, h)| h.to_string());
+    hash(a).is_some() && hash(a) == hash(b)
 }
 
 /// Write content to file, creating parent directories if needed
-pub fn write_file_content(path: &str, content: &str) -> Result<(), AppError> {
+///
+/// The write is atomic; with `backup` the previous content is kept as a hidden `.bak`.
+pub fn write_file_content(path: &str, content: &str, backup: bool) -> Result<(), AppError> {
+    write_binary_content(path, content.as_bytes(), backup)
+}
+
+/// Write content only if the file still matches `expected` (optimistic concurrency)
+///
+/// Fails with [`AppError::Conflict`] carrying the current content when the file
+/// changed on disk since it was read. A file that no longer exists is recreated.
+/// The version is compared right before the new content is renamed into place,
+/// so an external write during the save isn't overwritten.
+/// Returns the version of the written content.
+pub fn write_file_checked(
+    path: &str,
+    content: &str,
+    backup: bool,
+    expected: Option<&str>,
+) -> Result<String, AppError> {
+    let check = || {
+        let Some(expected) = expected else {
+            return Ok(());
+        };
+        match read_file_versioned(path) {
+            Ok(current) if !same_version(&current.version, expected) => Err(AppError::Conflict {
+                path: path.to_string(),
+                version: current.version,
+                content: current.content,
+            }),
+            Ok(_) | Err(AppError::FileNotFound(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    };
+
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic_checked(Path::new(path), content.as_bytes(), backup, check)?;
+    let modified = fs::metadata(path).ok().and_then(|m| unix_millis(m.modified()));
+    Ok(version_token(modified, content.as_bytes()))
+}
+
+/// Write bytes to file atomically, creating parent directories if needed
+pub fn write_binary_content(path: &str, data: &[u8], backup: bool) -> Result<(), AppError> {
     let path = Path::new(path);
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
-    fs::write(path, content).map_err(AppError::from)
+    write_atomic(path, data, backup)
 }
 
-/// List directory contents recursively (all files)
-pub fn list_dir_recursive(path: &str) -> Result<Vec<FileEntry>, AppError> {
-    let root = Path::new(path);
-    if !root.exists() {
-        return Err(AppError::FileNotFound(path.to_string()));
-    }
-    if !root.is_dir() {
-        return Err(AppError::InvalidPath("Path is not a directory".to_string()));
+fn unix_millis(time: std::io::Result<SystemTime>) -> Option<u64> {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+}
+
+fn entry_from_metadata(path: &Path, metadata: &fs::Metadata) -> FileEntry {
+    let is_dir = metadata.is_dir();
+    FileEntry {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        is_dir,
+        size: if is_dir { 0 } else { metadata.len() },
+        modified: unix_millis(metadata.modified()),
+        created: unix_millis(metadata.created()),
+        child_count: None,
+        children: None,
     }
+}
 
+/// Visible entries of a directory inside `vault`, unsorted and without children
+fn read_visible_entries(
+    vault: &Path,
+    dir: &Path,
+    rules: &VaultIgnore,
+) -> Result<Vec<FileEntry>, AppError> {
     let mut entries = Vec::new();
-
-    for entry in fs::read_dir(root)? {
+    for entry in fs::read_dir(dir)? {
         let entry = entry?;
+        // Follow symlinks like the rest of the app; skip broken ones
         let path = entry.path();
-        let name = entry.file_name().to_string_lossy().to_string();
-
-        // Skip hidden files and directories
-        if name.starts_with('.') {
+        let Ok(metadata) = fs::metadata(&path) else {
             continue;
-        }
-        
-        // Skip node_modules and other common non-user directories
-        if name == "node_modules" || name == "target" || name == ".git" {
+        };
+        let relative = path.strip_prefix(vault).unwrap_or(&path);
+        if rules.is_entry_ignored(relative, metadata.is_dir()) {
             continue;
         }
+        entries.push(entry_from_metadata(&path, &metadata));
+    }
+    Ok(entries)
+}
 
-        if path.is_dir() {
-            let children = list_dir_recursive(&path.to_string_lossy())?;
-            // Include all directories (including empty ones)
-            entries.push(FileEntry {
-                name,
-                path: path.to_string_lossy().to_string(),
-                is_dir: true,
-                children: Some(children),
-            });
+/// Visible children of a directory as `(path, is_dir)`, unsorted
+///
+/// Cheaper than [`read_visible_entries`]: only symlinks need an extra stat.
+fn read_visible_children(
+    vault: &Path,
+    dir: &Path,
+    rules: &VaultIgnore,
+) -> Result<Vec<(PathBuf, bool)>, AppError> {
+    let mut children = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        let is_dir = if file_type.is_symlink() {
+            match fs::metadata(&path) {
+                Ok(metadata) => metadata.is_dir(),
+                Err(_) => continue,
+            }
         } else {
-            // Include all files
-            entries.push(FileEntry {
-                name,
-                path: path.to_string_lossy().to_string(),
-                is_dir: false,
-                children: None,
-            });
+            file_type.is_dir()
+        };
+        let relative = path.strip_prefix(vault).unwrap_or(&path);
+        if !rules.is_entry_ignored(relative, is_dir) {
+            children.push((path, is_dir));
         }
     }
+    Ok(children)
+}
 
-    // Sort: directories first, then files, alphabetically
+/// Sort: directories first, then files, alphabetically
+fn sort_entries(entries: &mut [FileEntry]) {
     entries.sort_by(|a, b| {
         match (a.is_dir, b.is_dir) {
             (true, false) => std::cmp::Ordering::Less,
@@ -85,10 +228,222 @@ pub fn list_dir_recursive(path: &str) -> Result<Vec<FileEntry>, AppError> {
             _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
         }
     });
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Sorted visible children of a directory, kept between page requests
+struct CachedListing {
+    at: Instant,
+    dir_modified: Option<SystemTime>,
+    rules_modified: Option<SystemTime>,
+    children: Arc<Vec<(PathBuf, bool)>>,
+}
+
+/// Listings by (directory, vault)
+type ListingCache = HashMap<(PathBuf, PathBuf), CachedListing>;
+
+fn listing_cache() -> &'static Mutex<ListingCache> {
+    static LISTINGS: OnceLock<Mutex<ListingCache>> = OnceLock::new();
+    LISTINGS.get_or_init(Default::default)
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).ok().and_then(|m| m.modified().ok())
+}
+
+/// Sorted visible children of `dir`
+///
+/// The first page (`fresh`) always reads the directory; later pages reuse that
+/// listing while neither the directory nor the ignore file changed, so paging
+/// through a large folder doesn't re-read and re-sort it for every page.
+fn sorted_children(
+    vault: &Path,
+    dir: &Path,
+    rules: &VaultIgnore,
+    fresh: bool,
+) -> Result<Arc<Vec<(PathBuf, bool)>>, AppError> {
+    let key = (dir.to_path_buf(), vault.to_path_buf());
+    let dir_modified = modified_time(dir);
+    let rules_modified = modified_time(&vault.join(IGNORE_FILE));
+    if !fresh {
+        let cache = listing_cache().lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = cache.get(&key).filter(|cached| {
+            cached.at.elapsed() < LISTING_TTL
+                && cached.dir_modified == dir_modified
+                && cached.rules_modified == rules_modified
+        }) {
+            return Ok(cached.children.clone());
+        }
+    }
+
+    let mut children = read_visible_children(vault, dir, rules)?;
+    // Same order as `sort_entries`
+    children.sort_by_cached_key(|(path, is_dir)| (!is_dir, file_name(path).to_lowercase()));
+    let children = Arc::new(children);
+
+    let mut cache = listing_cache().lock().unwrap_or_else(|e| e.into_inner());
+    cache.retain(|_, cached| cached.at.elapsed() < LISTING_TTL);
+    cache.insert(
+        key,
+        CachedListing {
+            at: Instant::now(),
+            dir_modified,
+            rules_modified,
+            children: children.clone(),
+        },
+    );
+    Ok(children)
+}
+
+fn check_dir(root: &Path) -> Result<(), AppError> {
+    if !root.exists() {
+        return Err(AppError::FileNotFound(root.display().to_string()));
+    }
+    if !root.is_dir() {
+        return Err(AppError::InvalidPath("Path is not a directory".to_string()));
+    }
+    Ok(())
+}
+
+/// List directory contents recursively (all files not hidden by the vault's ignore rules)
+pub fn list_dir_recursive(path: &str) -> Result<Vec<FileEntry>, AppError> {
+    let root = Path::new(path);
+    check_dir(root)?;
+    walk_dir(root, root, &VaultIgnore::load(root))
+}
+
+fn walk_dir(vault: &Path, dir: &Path, rules: &VaultIgnore) -> Result<Vec<FileEntry>, AppError> {
+    let mut entries = read_visible_entries(vault, dir, rules)?;
+    for entry in entries.iter_mut().filter(|e| e.is_dir) {
+        // Include all directories (including empty ones)
+        let children = walk_dir(vault, Path::new(&entry.path), rules)?;
+        entry.child_count = Some(children.len());
+        entry.children = Some(children);
+    }
+    sort_entries(&mut entries);
 
     Ok(entries)
 }
 
+/// Visit every file under `dir` that the vault's ignore rules don't hide, in tree order
+///
+/// This is the walk behind `list_dir_recursive`, without building the tree.
+/// Returns `false` if `visit` stopped the walk early by returning `false`.
+pub fn visit_files(
+    vault: &Path,
+    dir: &Path,
+    rules: &VaultIgnore,
+    visit: &mut dyn FnMut(&FileEntry) -> bool,
+) -> Result<bool, AppError> {
+    let mut entries = read_visible_entries(vault, dir, rules)?;
+    sort_entries(&mut entries);
+    for entry in &entries {
+        let keep_going = if entry.is_dir {
+            visit_files(vault, Path::new(&entry.path), rules, visit)?
+        } else {
+            visit(entry)
+        };
+        if !keep_going {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// List a single directory level, one page at a time
+///
+/// Directories carry `child_count` instead of `children`, so the tree can be
+/// expanded lazily. `limit` of `None` returns everything after `offset`.
+/// Ignore rules come from `vault_path` (defaults to `path` itself).
+pub fn list_dir(
+    path: &str,
+    vault_path: Option<&str>,
+    offset: usize,
+    limit: Option<usize>,
+) -> Result<DirListing, AppError> {
+    let root = Path::new(path);
+    check_dir(root)?;
+    let vault = Path::new(vault_path.unwrap_or(path));
+    let rules = VaultIgnore::load(vault);
+
+    // An ignored directory has no visible contents
+    let relative = root.strip_prefix(vault).unwrap_or(Path::new(""));
+    if rules.is_ignored(relative, true) {
+        return Ok(DirListing { entries: Vec::new(), total: 0 });
+    }
+
+    let children = sorted_children(vault, root, &rules, offset == 0)?;
+
+    // Metadata and child counts only for the returned page
+    let page = children
+        .iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .filter_map(|(path, _)| {
+            let metadata = fs::metadata(path).ok()?;
+            let mut entry = entry_from_metadata(path, &metadata);
+            if entry.is_dir {
+                entry.child_count = Some(count_visible_children(vault, path, &rules));
+            }
+            Some(entry)
+        })
+        .collect();
+
+    Ok(DirListing { entries: page, total: children.len() })
+}
+
+fn count_visible_children(vault: &Path, dir: &Path, rules: &VaultIgnore) -> usize {
+    read_visible_children(vault, dir, rules)
+        .map(|children| children.len())
+        .unwrap_or(0)
+}
+
+/// Metadata for a single file or directory (directories include `child_count`)
+///
+/// Ignore rules for the child count come from `vault_path` (defaults to `path`).
+pub fn stat_entry(path: &str, vault_path: Option<&str>) -> Result<FileEntry, AppError> {
+    let vault = Path::new(vault_path.unwrap_or(path));
+    stat_with_rules(vault, Path::new(path), &VaultIgnore::load(vault))
+}
+
+fn stat_with_rules(vault: &Path, path: &Path, rules: &VaultIgnore) -> Result<FileEntry, AppError> {
+    let metadata = fs::metadata(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::FileNotFound(path.display().to_string()),
+        _ => e.into(),
+    })?;
+    let mut entry = entry_from_metadata(path, &metadata);
+    if entry.is_dir {
+        entry.child_count = Some(count_visible_children(vault, path, rules));
+    }
+    Ok(entry)
+}
+
+/// Translate a changed path under `root` into a tree update
+///
+/// Returns `None` for paths outside the root or hidden by the vault's ignore rules.
+pub fn tree_change(root: &Path, rules: &VaultIgnore, path: &Path) -> Option<TreeChange> {
+    let relative = path.strip_prefix(root).ok()?;
+    if relative.as_os_str().is_empty() {
+        return None;
+    }
+
+    match stat_with_rules(root, path, rules) {
+        Ok(entry) => {
+            (!rules.is_ignored(relative, entry.is_dir)).then_some(TreeChange::Upsert { entry })
+        }
+        // A removed path may have been a directory; check both ways
+        Err(_) => {
+            let visible = !rules.is_ignored(relative, false) || !rules.is_ignored(relative, true);
+            visible.then(|| TreeChange::Removed {
+                path: path.to_string_lossy().to_string(),
+            })
+        }
+    }
+}
+
 /// Create a new .md file
 pub fn create_new_file(path: &str) -> Result<(), AppError> {
     let path = Path::new(path);
@@ -98,7 +453,9 @@ pub fn create_new_file(path: &str) -> Result<(), AppError> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
-    fs::write(path, "").map_err(AppError::from)
+    fs::write(path, "")?;
+    record_own_write(path);
+    Ok(())
 }
 
 /// Delete a file or directory (move to trash/recycle bin)
@@ -109,6 +466,7 @@ pub fn delete_entry(path: &str) -> Result<(), AppError> {
     }
     // 移动到回收站而非永久删除
     trash::delete(path)?;
+    record_own_write(path);
     Ok(())
 }
 
@@ -118,7 +476,9 @@ pub fn create_new_dir(path: &str) -> Result<(), AppError> {
     if path.exists() {
         return Err(AppError::FileExists(path.display().to_string()));
     }
-    fs::create_dir_all(path).map_err(AppError::from)
+    fs::create_dir_all(path)?;
+    record_own_write(path);
+    Ok(())
 }
 
 /// Rename/move a file or directory
@@ -134,5 +494,137 @@ pub fn rename_entry(old_path: &str, new_path: &str) -> Result<(), AppError> {
     if let Some(parent) = new.parent() {
         fs::create_dir_all(parent)?;
     }
-    fs::rename(old, new).map_err(AppError::from)
+    fs::rename(old, new)?;
+    record_own_write(old);
+    record_own_write(new);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(entries: &[FileEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_list_dir_pages_one_level() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("notes/deep")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join("notes/a.md"), "alpha").unwrap();
+        fs::write(root.join("notes/.hidden.md"), "").unwrap();
+        fs::write(root.join("b.md"), "beta!").unwrap();
+        fs::write(root.join("C.md"), "").unwrap();
+
+        let root_str = root.to_string_lossy();
+        let listing = list_dir(&root_str, None, 0, None).unwrap();
+        assert_eq!(listing.total, 3);
+        assert_eq!(names(&listing.entries), vec!["notes", "b.md", "C.md"]);
+        // Directories carry a child count, not their children
+        assert_eq!(listing.entries[0].child_count, Some(2));
+        assert!(listing.entries[0].children.is_none());
+        assert_eq!(listing.entries[1].size, 5);
+        assert!(listing.entries[1].modified.is_some());
+
+        let page = list_dir(&root_str, None, 1, Some(1)).unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(names(&page.entries), vec!["b.md"]);
+        // Later pages reuse the listing only while the directory is unchanged
+        fs::write(root.join("a.md"), "").unwrap();
+        let page = list_dir(&root_str, None, 1, Some(2)).unwrap();
+        assert_eq!(page.total, 4);
+        assert_eq!(names(&page.entries), vec!["a.md", "b.md"]);
+        fs::remove_file(root.join("a.md")).unwrap();
+        assert!(list_dir(&root.join("b.md").to_string_lossy(), None, 0, None).is_err());
+
+        let tree = list_dir_recursive(&root_str).unwrap();
+        assert_eq!(names(tree[0].children.as_ref().unwrap()), vec!["deep", "a.md"]);
+    }
+
+    #[test]
+    fn test_ignore_file_applies_to_listings() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("attachments")).unwrap();
+        fs::create_dir_all(root.join(".obsidian")).unwrap();
+        fs::create_dir_all(root.join("notes")).unwrap();
+        fs::write(root.join("attachments/img.png"), "").unwrap();
+        fs::write(root.join(".obsidian/app.json"), "{}").unwrap();
+        fs::write(root.join("notes/a.md"), "").unwrap();
+        fs::write(root.join("notes/draft.tmp"), "").unwrap();
+        fs::write(root.join(".luminaignore"), "attachments/\n*.tmp\n!.obsidian/\n").unwrap();
+
+        let root_str = root.to_string_lossy();
+        let tree = list_dir_recursive(&root_str).unwrap();
+        assert_eq!(names(&tree), vec![".obsidian", "notes"]);
+        assert_eq!(names(tree[1].children.as_ref().unwrap()), vec!["a.md"]);
+
+        let notes = root.join("notes");
+        let listing = list_dir(&notes.to_string_lossy(), Some(&root_str), 0, None).unwrap();
+        assert_eq!(names(&listing.entries), vec!["a.md"]);
+        let attachments = root.join("attachments");
+        let hidden = list_dir(&attachments.to_string_lossy(), Some(&root_str), 0, None).unwrap();
+        assert_eq!(hidden.total, 0);
+        let stat = stat_entry(&notes.to_string_lossy(), Some(&root_str)).unwrap();
+        assert_eq!(stat.child_count, Some(1));
+    }
+
+    #[test]
+    fn test_tree_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.md"), "alpha").unwrap();
+        let rules = VaultIgnore::load(root);
+        let tree_change = |path: &Path| tree_change(root, &rules, path);
+
+        match tree_change(&root.join("a.md")) {
+            Some(TreeChange::Upsert { entry }) => assert_eq!(entry.size, 5),
+            other => panic!("unexpected change: {:?}", other),
+        }
+        assert_eq!(
+            tree_change(&root.join("gone.md")),
+            Some(TreeChange::Removed {
+                path: root.join("gone.md").to_string_lossy().to_string()
+            })
+        );
+        assert_eq!(tree_change(&root.join(".git/index")), None);
+        assert_eq!(tree_change(root), None);
+        assert_eq!(super::tree_change(&root.join("sub"), &rules, &root.join("a.md")), None);
+    }
+
+    #[test]
+    fn test_write_file_checked_detects_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.md");
+        let path = path.to_string_lossy();
+        fs::write(&*path, "original").unwrap();
+
+        let opened = read_file_versioned(&path).unwrap();
+        assert_eq!(opened.content, "original");
+        let version = write_file_checked(&path, "mine", false, Some(&opened.version)).unwrap();
+        assert_eq!(read_file_versioned(&path).unwrap().version, version);
+
+        // Another editor changes the file; saving with the old token fails
+        fs::write(&*path, "theirs").unwrap();
+        match write_file_checked(&path, "mine again", false, Some(&version)) {
+            Err(AppError::Conflict { content, version: current, .. }) => {
+                assert_eq!(content, "theirs");
+                // Saving against the current version resolves the conflict
+                write_file_checked(&path, "merged", false, Some(&current)).unwrap();
+            }
+            other => panic!("expected conflict, got {:?}", other),
+        }
+        assert_eq!(fs::read_to_string(&*path).unwrap(), "merged");
+
+        // Identical content with a new mtime is not a conflict
+        let stale = format!("0-{}", version_token(None, b"merged").split_once('-').unwrap().1);
+        write_file_checked(&path, "again", false, Some(&stale)).unwrap();
+        // Without a token the save is unconditional
+        fs::write(&*path, "external").unwrap();
+        write_file_checked(&path, "forced", false, None).unwrap();
+        assert_eq!(fs::read_to_string(&*path).unwrap(), "forced");
+    }
 }
@@ -0,0 +1,249 @@
+//! Three-way text merge
+//!
+//! A line-based diff3 against a common base: edits on both sides that don't
+//! overlap are combined, and conflict markers are written only where both sides
+//! changed the same region. Used by WebDAV sync (base = last synced content) and
+//! by editor save conflicts (base = content the editor loaded).
+
+use serde::Serialize;
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+/// Conflict markers
+pub const MARKER_LOCAL: &str = "<<<<<<< local";
+pub const MARKER_SEPARATOR: &str = "=======";
+pub const MARKER_REMOTE: &str = ">>>>>>> remote";
+
+/// Result of a merge
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MergeResult {
+    /// Merged content (with conflict markers if there were conflicts)
+    pub content: String,
+    /// Number of conflicting blocks
+    pub conflicts: usize,
+}
+
+impl MergeResult {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts == 0
+    }
+}
+
+/// One edit: replace base lines `[start, end)` with `lines`
+#[derive(Debug)]
+struct Hunk<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a str>,
+}
+
+/// Merge `local` and `remote`, both derived from `base`
+pub fn merge3(base: &str, local: &str, remote: &str) -> MergeResult {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let local_lines: Vec<&str> = local.split_inclusive('\n').collect();
+    let remote_lines: Vec<&str> = remote.split_inclusive('\n').collect();
+
+    let local_hunks = diff_hunks(&base_lines, &local_lines);
+    let remote_hunks = diff_hunks(&base_lines, &remote_lines);
+
+    let mut out: Vec<String> = Vec::new();
+    let mut conflicts = 0;
+    let mut pos = 0;
+    let (mut i, mut j) = (0, 0);
+
+    loop {
+        // The edit that starts first in the base opens a new group
+        let group_start = match (local_hunks.get(i), remote_hunks.get(j)) {
+            (None, None) => break,
+            (Some(l), None) => l.start,
+            (None, Some(r)) => r.start,
+            (Some(l), Some(r)) => l.start.min(r.start),
+        };
+        let mut group_end = group_start;
+        let (local_from, remote_from) = (i, j);
+
+        // Absorb every edit overlapping or touching the group
+        loop {
+            if let Some(h) = local_hunks.get(i).filter(|h| h.start <= group_end) {
+                group_end = group_end.max(h.end);
+                i += 1;
+                continue;
+            }
+            if let Some(h) = remote_hunks.get(j).filter(|h| h.start <= group_end) {
+                group_end = group_end.max(h.end);
+                j += 1;
+                continue;
+            }
+            break;
+        }
+
+        out.extend(base_lines[pos..group_start].iter().map(|s| s.to_string()));
+        pos = group_end;
+
+        let base_part = &base_lines[group_start..group_end];
+        let local_part = apply_hunks(base_part, group_start, &local_hunks[local_from..i]);
+        let remote_part = apply_hunks(base_part, group_start, &remote_hunks[remote_from..j]);
+
+        if local_from == i {
+            out.extend(remote_part);
+        } else if remote_from == j || local_part == remote_part {
+            out.extend(local_part);
+        } else {
+            conflicts += 1;
+            out.push(format!("{}\n", MARKER_LOCAL));
+            push_block(&mut out, local_part);
+            out.push(format!("{}\n", MARKER_SEPARATOR));
+            push_block(&mut out, remote_part);
+            out.push(format!("{}\n", MARKER_REMOTE));
+        }
+    }
+
+    out.extend(base_lines[pos..].iter().map(|s| s.to_string()));
+
+    MergeResult {
+        content: out.concat(),
+        conflicts,
+    }
+}
+
+/// Edits turning `base` into `other`, ordered by base position and never adjacent
+fn diff_hunks<'a>(base: &[&str], other: &[&'a str]) -> Vec<Hunk<'a>> {
+    let mut hunks: Vec<Hunk<'a>> = Vec::new();
+
+    for op in capture_diff_slices(Algorithm::Myers, base, other) {
+        let (old_range, new_range) = match op {
+            DiffOp::Equal { .. } => continue,
+            DiffOp::Delete { old_index, old_len, new_index } => {
+                (old_index..old_index + old_len, new_index..new_index)
+            }
+            DiffOp::Insert { old_index, new_index, new_len } => {
+                (old_index..old_index, new_index..new_index + new_len)
+            }
+            DiffOp::Replace { old_index, old_len, new_index, new_len } => {
+                (old_index..old_index + old_len, new_index..new_index + new_len)
+            }
+        };
+
+        match hunks.last_mut() {
+            // Consecutive deletes/inserts form a single edit
+            Some(last) if last.end == old_range.start => {
+                last.end = old_range.end;
+                last.lines.extend_from_slice(&other[new_range]);
+            }
+            _ => hunks.push(Hunk {
+                start: old_range.start,
+                end: old_range.end,
+                lines: other[new_range].to_vec(),
+            }),
+        }
+    }
+
+    hunks
+}
+
+/// Apply a group of edits to a slice of the base
+fn apply_hunks(base_part: &[&str], offset: usize, hunks: &[Hunk]) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut pos = offset;
+
+    for hunk in hunks {
+        result.extend(base_part[pos - offset..hunk.start - offset].iter().map(|s| s.to_string()));
+        result.extend(hunk.lines.iter().map(|s| s.to_string()));
+        pos = hunk.end;
+    }
+    result.extend(base_part[pos - offset..].iter().map(|s| s.to_string()));
+
+    result
+}
+
+/// Write a conflict block so that each marker is on its own line
+fn push_block(out: &mut Vec<String>, mut lines: Vec<String>) {
+    if let Some(last) = lines.last_mut() {
+        if !last.ends_with('\n') {
+            last.push('\n');
+        }
+    }
+    out.extend(lines);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "# Title\n\nintro\n\n## A\nalpha\n\n## B\nbeta\n";
+
+    #[test]
+    fn test_non_overlapping_edits() {
+        let local = "# Title\n\nintro (edited locally)\n\n## A\nalpha\n\n## B\nbeta\n";
+        let remote = "# Title\n\nintro\n\n## A\nalpha\n\n## B\nbeta\nmore beta\n";
+
+        let result = merge3(BASE, local, remote);
+        assert!(result.is_clean());
+        assert_eq!(
+            result.content,
+            "# Title\n\nintro (edited locally)\n\n## A\nalpha\n\n## B\nbeta\nmore beta\n"
+        );
+    }
+
+    #[test]
+    fn test_identical_edits() {
+        let edited = "# Title\n\nintro\n\n## A\nALPHA\n\n## B\nbeta\n";
+        let result = merge3(BASE, edited, edited);
+        assert!(result.is_clean());
+        assert_eq!(result.content, edited);
+    }
+
+    #[test]
+    fn test_one_side_unchanged() {
+        let remote = "# New title\n\nintro\n";
+        let result = merge3(BASE, BASE, remote);
+        assert!(result.is_clean());
+        assert_eq!(result.content, remote);
+    }
+
+    #[test]
+    fn test_overlapping_edits_conflict() {
+        let local = "# Title\n\nintro\n\n## A\nalpha local\n\n## B\nbeta\n";
+        let remote = "# Title\n\nintro\n\n## A\nalpha remote\n\n## B\nbeta remote\n";
+
+        let result = merge3(BASE, local, remote);
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            result.content,
+            "# Title\n\nintro\n\n## A\n<<<<<<< local\nalpha local\n=======\nalpha remote\n>>>>>>> remote\n\n## B\nbeta remote\n"
+        );
+    }
+
+    #[test]
+    fn test_insertions_at_same_point_conflict() {
+        let local = format!("{}local tail\n", BASE);
+        let remote = format!("{}remote tail\n", BASE);
+
+        let result = merge3(BASE, &local, &remote);
+        assert_eq!(result.conflicts, 1);
+        assert!(result.content.ends_with("<<<<<<< local\nlocal tail\n=======\nremote tail\n>>>>>>> remote\n"));
+    }
+
+    #[test]
+    fn test_delete_vs_edit_conflict() {
+        let local = "# Title\n\nintro\n\n## B\nbeta\n";
+        let remote = "# Title\n\nintro\n\n## A\nalpha!\n\n## B\nbeta\n";
+
+        let result = merge3(BASE, local, remote);
+        assert_eq!(result.conflicts, 1);
+        assert!(result.content.contains("=======\n## A\nalpha!\n\n>>>>>>> remote\n"));
+    }
+
+    #[test]
+    fn test_missing_trailing_newline() {
+        let base = "a\nb";
+        let local = "a\nb local";
+        let remote = "a\nb remote";
+
+        let result = merge3(base, local, remote);
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            result.content,
+            "a\n<<<<<<< local\nb local\n=======\nb remote\n>>>>>>> remote\n"
+        );
+    }
+}
@@ -1,4 +1,11 @@
+pub mod atomic;
+pub mod events;
 mod manager;
+pub mod merge;
+pub mod sandbox;
+pub mod search;
+pub mod vault_ignore;
 pub mod watcher;
 
 pub use manager::*;
+pub use vault_ignore::VaultIgnore;
@@ -0,0 +1,280 @@
+//! Vault path sandboxing
+//!
+//! File commands only touch paths inside an open vault, so a prompt-injected
+//! agent tool call can't read `~/.ssh` or overwrite system files. Paths are
+//! resolved before the check (`..` and symlinks included). Vault roots and paths
+//! outside every vault are only granted after the user approved them in a native
+//! dialog, and never a filesystem root or the home directory.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::error::AppError;
+
+/// Open vault roots (per window) and user-approved external paths
+#[derive(Default)]
+pub struct VaultSandbox {
+    vaults: RwLock<HashMap<String, PathBuf>>,
+    allowed: RwLock<Vec<PathBuf>>,
+}
+
+impl VaultSandbox {
+    /// Set the vault opened in `window`, replacing its previous one
+    pub fn set_vault(&self, window: &str, path: &str) -> Result<(), AppError> {
+        let root = vault_root(path)?;
+        self.vaults
+            .write()
+            .map_err(|_| AppError::InvalidPath(path.to_string()))?
+            .insert(window.to_string(), root);
+        Ok(())
+    }
+
+    /// Forget the vault of a closed window
+    pub fn remove_window(&self, window: &str) {
+        if let Ok(mut vaults) = self.vaults.write() {
+            vaults.remove(window);
+        }
+    }
+
+    /// Allow a path outside the vaults (and everything below it)
+    pub fn allow_external(&self, path: &str) -> Result<(), AppError> {
+        let resolved = resolve(Path::new(path))?;
+        if is_too_broad(&resolved) {
+            return Err(AppError::InvalidPath(path.to_string()));
+        }
+        let mut allowed = self
+            .allowed
+            .write()
+            .map_err(|_| AppError::InvalidPath(path.to_string()))?;
+        if !allowed.contains(&resolved) {
+            allowed.push(resolved);
+        }
+        Ok(())
+    }
+
+    /// Check that `path` lies inside the vault opened in `window` or an allowed
+    /// path; another window's vault doesn't count
+    pub fn check(&self, window: &str, path: &str) -> Result<(), AppError> {
+        let resolved = resolve(Path::new(path))?;
+        let in_vault = self
+            .vaults
+            .read()
+            .map(|vaults| vaults.get(window).is_some_and(|root| resolved.starts_with(root)))
+            .unwrap_or(false);
+        let allowed = self
+            .allowed
+            .read()
+            .map(|allowed| allowed.iter().any(|root| resolved.starts_with(root)))
+            .unwrap_or(false);
+        if in_vault || allowed {
+            Ok(())
+        } else {
+            Err(AppError::OutsideVault(path.to_string()))
+        }
+    }
+
+    /// Like [`check`](Self::check), but also rejects a vault root itself
+    /// (for deleting or renaming entries)
+    pub fn check_entry(&self, window: &str, path: &str) -> Result<(), AppError> {
+        self.check(window, path)?;
+        let resolved = resolve(Path::new(path))?;
+        let is_root = self
+            .vaults
+            .read()
+            .map(|vaults| vaults.values().any(|root| *root == resolved))
+            .unwrap_or(false);
+        if is_root {
+            return Err(AppError::OutsideVault(path.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Resolve a directory to open as a vault
+pub fn vault_root(path: &str) -> Result<PathBuf, AppError> {
+    Path::new(path)
+        .canonicalize()
+        .ok()
+        .filter(|p| p.is_dir() && !is_too_broad(p))
+        .ok_or_else(|| AppError::InvalidPath(path.to_string()))
+}
+
+/// A filesystem root or the home directory (or one of its ancestors) would
+/// expose far more than a vault
+fn is_too_broad(path: &Path) -> bool {
+    let home = std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" })
+        .and_then(|home| Path::new(&home).canonicalize().ok());
+    path.parent().is_none() || home.is_some_and(|home| home.starts_with(path))
+}
+
+const APPROVED_VAULTS_FILE: &str = "approved-vaults.json";
+
+/// Vault roots the user approved, kept in the app data dir so reopening one
+/// (e.g. on startup) doesn't ask again
+pub struct ApprovedVaults {
+    path: PathBuf,
+}
+
+impl ApprovedVaults {
+    pub fn new(app_data_dir: &Path) -> Self {
+        Self {
+            path: app_data_dir.join(APPROVED_VAULTS_FILE),
+        }
+    }
+
+    fn load(&self) -> Vec<PathBuf> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, root: &Path) -> bool {
+        self.load().iter().any(|approved| approved == root)
+    }
+
+    pub fn add(&self, root: &Path) -> Result<(), AppError> {
+        let mut roots = self.load();
+        if roots.iter().any(|approved| approved == root) {
+            return Ok(());
+        }
+        roots.push(root.to_path_buf());
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(&roots)
+            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+        crate::fs::atomic::write_atomic(&self.path, content.as_bytes(), false)
+    }
+}
+
+/// Resolve a path that may not exist yet
+///
+/// The deepest existing ancestor is canonicalized (following symlinks and `..`);
+/// the missing remainder can't contain symlinks, so it is normalized lexically.
+fn resolve(path: &Path) -> Result<PathBuf, AppError> {
+    if !path.is_absolute() {
+        return Err(AppError::InvalidPath(path.display().to_string()));
+    }
+
+    let mut existing = path;
+    let mut missing = Vec::new();
+    let mut resolved = loop {
+        match existing.canonicalize() {
+            Ok(resolved) => break resolved,
+            // An entry that exists but can't be resolved is a dangling symlink;
+            // writing through it could create a file anywhere
+            Err(_) if existing.symlink_metadata().is_ok() => {
+                return Err(AppError::InvalidPath(path.display().to_string()));
+            }
+            Err(_) => {
+                let name = existing.components().next_back();
+                let (Some(parent), Some(name)) = (existing.parent(), name) else {
+                    return Err(AppError::InvalidPath(path.display().to_string()));
+                };
+                missing.push(name);
+                existing = parent;
+            }
+        }
+    };
+
+    for component in missing.into_iter().rev() {
+        match component {
+            Component::Normal(name) => resolved.push(name),
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(AppError::InvalidPath(path.display().to_string()));
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn s(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_paths_must_stay_in_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("vault");
+        let outside = dir.path().join("secrets");
+        fs::create_dir_all(vault.join("notes")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("key"), "secret").unwrap();
+
+        let sandbox = VaultSandbox::default();
+        assert!(sandbox.check("main", &s(&vault.join("notes/a.md"))).is_err());
+        sandbox.set_vault("main", &s(&vault)).unwrap();
+
+        assert!(sandbox.check("main", &s(&vault.join("notes/a.md"))).is_ok());
+        // Files that don't exist yet, with `..` that stays inside
+        assert!(sandbox.check("main", &s(&vault.join("new/dir/../b.md"))).is_ok());
+        assert!(sandbox.check("main", &s(&vault.join("notes/../../secrets/key"))).is_err());
+        assert!(sandbox.check("main", &s(&vault.join("new/../../secrets/key"))).is_err());
+        assert!(sandbox.check("main", &s(&outside.join("key"))).is_err());
+        assert!(sandbox.check("main", "relative/a.md").is_err());
+        // Only the calling window's vault
+        assert!(sandbox.check("other", &s(&vault.join("notes/a.md"))).is_err());
+
+        // The root itself can be listed but not deleted or renamed
+        assert!(sandbox.check("main", &s(&vault)).is_ok());
+        assert!(sandbox.check_entry("main", &s(&vault)).is_err());
+        assert!(sandbox.check_entry("main", &s(&vault.join("notes"))).is_ok());
+
+        // Approved external paths, but never a root or the home directory
+        sandbox.allow_external(&s(&outside)).unwrap();
+        assert!(sandbox.check("main", &s(&outside.join("key"))).is_ok());
+        let root = vault.ancestors().last().unwrap();
+        assert!(sandbox.allow_external(&s(root)).is_err());
+        assert!(sandbox.set_vault("other", &s(root)).is_err());
+        #[cfg(unix)]
+        if let Some(home) = std::env::var_os("HOME") {
+            assert!(sandbox.allow_external(&home.to_string_lossy()).is_err());
+        }
+
+        let approved = ApprovedVaults::new(&dir.path().join("app"));
+        let root = vault_root(&s(&vault)).unwrap();
+        assert!(!approved.contains(&root));
+        approved.add(&root).unwrap();
+        approved.add(&root).unwrap();
+        assert!(approved.contains(&root));
+        assert_eq!(approved.load().len(), 1);
+
+        sandbox.remove_window("main");
+        assert!(sandbox.check("main", &s(&vault.join("notes/a.md"))).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_cannot_escape() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("vault");
+        let outside = dir.path().join("outside");
+        fs::create_dir_all(&vault).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("passwd"), "").unwrap();
+        std::os::unix::fs::symlink(&outside, vault.join("link")).unwrap();
+        fs::write(vault.join("real.md"), "").unwrap();
+        std::os::unix::fs::symlink(vault.join("real.md"), vault.join("alias.md")).unwrap();
+
+        let sandbox = VaultSandbox::default();
+        sandbox.set_vault("main", &s(&vault)).unwrap();
+        assert!(sandbox.check("main", &s(&vault.join("link/passwd"))).is_err());
+        assert!(sandbox.check("main", &s(&vault.join("link/new.md"))).is_err());
+        assert!(sandbox.check("main", &s(&vault.join("alias.md"))).is_ok());
+
+        std::os::unix::fs::symlink(outside.join("missing"), vault.join("dangling.md")).unwrap();
+        assert!(sandbox.check("main", &s(&vault.join("dangling.md"))).is_err());
+    }
+}
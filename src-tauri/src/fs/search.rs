@@ -0,0 +1,285 @@
+//! Full-text search over the vault
+//!
+//! Walks the vault with the same walker and ignore rules as the file tree and
+//! matches each text file line by line, so searching doesn't require sending
+//! every note across IPC.
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::{visit_files, VaultIgnore};
+use crate::error::AppError;
+
+/// Files larger than this are skipped
+const MAX_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Search options from the frontend
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// Treat the query as a regular expression instead of literal text
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// Globs (relative to the vault) a file must match; empty matches every file
+    pub include: Vec<String>,
+    /// Globs (relative to the vault) that exclude a file
+    pub exclude: Vec<String>,
+    /// Only search below this directory (relative to the vault)
+    pub directory: Option<String>,
+    /// Lines of context before and after each matching line
+    pub context_lines: usize,
+    /// Stop after this many matching lines (0 for no limit)
+    pub max_results: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            regex: false,
+            case_sensitive: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            directory: None,
+            context_lines: 0,
+            max_results: 500,
+        }
+    }
+}
+
+/// A matching line
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LineMatch {
+    /// 1-based line number
+    pub line: usize,
+    pub text: String,
+    /// `[start, end)` of each match in `text`, in UTF-16 code units (JavaScript string indices)
+    pub ranges: Vec<[usize; 2]>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// Matches within one file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileMatches {
+    pub path: String,
+    /// Path relative to the vault, with `/` separators
+    pub relative_path: String,
+    pub matches: Vec<LineMatch>,
+}
+
+/// Search totals
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SearchStats {
+    pub total_matches: usize,
+    pub searched_files: usize,
+    /// The result limit was reached before the whole vault was searched
+    pub truncated: bool,
+}
+
+/// Search result returned by the command (`files` is empty when results are streamed)
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResults {
+    pub files: Vec<FileMatches>,
+    #[serde(flatten)]
+    pub stats: SearchStats,
+}
+
+/// Search the vault, handing each file with matches to `on_file` as soon as it's searched
+pub fn search_vault(
+    vault: &Path,
+    query: &str,
+    options: &SearchOptions,
+    on_file: &mut dyn FnMut(FileMatches),
+) -> Result<SearchStats, AppError> {
+    if query.is_empty() {
+        return Err(AppError::InvalidQuery("empty query".to_string()));
+    }
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| AppError::InvalidQuery(e.to_string()))?;
+    let include = build_globset(&options.include)?;
+    let exclude = build_globset(&options.exclude)?;
+
+    let rules = VaultIgnore::load(vault);
+    let start = match options.directory.as_deref().map(|d| d.trim_matches(['/', '\\'])) {
+        Some(dir) if !dir.is_empty() => {
+            let relative = Path::new(dir);
+            if rules.is_ignored(relative, true) {
+                return Ok(SearchStats::default());
+            }
+            vault.join(relative)
+        }
+        _ => vault.to_path_buf(),
+    };
+
+    let mut stats = SearchStats::default();
+    visit_files(vault, &start, &rules, &mut |entry| {
+        let path = Path::new(&entry.path);
+        let relative = path
+            .strip_prefix(vault)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        if (!options.include.is_empty() && !include.is_match(&relative)) || exclude.is_match(&relative) {
+            return true;
+        }
+        let Some(content) = read_text(path, entry.size) else {
+            return true;
+        };
+        stats.searched_files += 1;
+
+        let remaining = match options.max_results {
+            0 => usize::MAX,
+            max => max - stats.total_matches,
+        };
+        let (matches, more) = match_lines(&content, &regex, options.context_lines, remaining);
+        if !matches.is_empty() {
+            stats.total_matches += matches.len();
+            on_file(FileMatches {
+                path: entry.path.clone(),
+                relative_path: relative,
+                matches,
+            });
+        }
+        if more {
+            stats.truncated = true;
+            return false;
+        }
+        true
+    })?;
+    Ok(stats)
+}
+
+/// Read a file as text, skipping large and binary files
+fn read_text(path: &Path, size: u64) -> Option<String> {
+    if size > MAX_FILE_SIZE {
+        return None;
+    }
+    let data = fs::read(path).ok()?;
+    if data[..data.len().min(8192)].contains(&0) {
+        return None;
+    }
+    String::from_utf8(data).ok()
+}
+
+/// Matching lines of `content`, at most `limit`; the flag is set when more were left
+fn match_lines(content: &str, regex: &Regex, context: usize, limit: usize) -> (Vec<LineMatch>, bool) {
+    let lines: Vec<&str> = content
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+    let mut matches = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let ranges: Vec<[usize; 2]> = regex
+            .find_iter(line)
+            .filter(|m| !m.is_empty())
+            .map(|m| {
+                let start = utf16_len(&line[..m.start()]);
+                [start, start + utf16_len(m.as_str())]
+            })
+            .collect();
+        if ranges.is_empty() {
+            continue;
+        }
+        if matches.len() == limit {
+            return (matches, true);
+        }
+        let owned = |slice: &[&str]| slice.iter().map(|l| l.to_string()).collect();
+        matches.push(LineMatch {
+            line: index + 1,
+            text: line.to_string(),
+            ranges,
+            before: owned(&lines[index.saturating_sub(context)..index]),
+            after: owned(&lines[index + 1..(index + 1 + context).min(lines.len())]),
+        });
+    }
+    (matches, false)
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet, AppError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| AppError::InvalidQuery(format!("invalid glob '{}': {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| AppError::InvalidQuery(format!("invalid globs: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(vault: &Path, query: &str, options: &SearchOptions) -> (Vec<FileMatches>, SearchStats) {
+        let mut files = Vec::new();
+        let stats = search_vault(vault, query, options, &mut |f| files.push(f)).unwrap();
+        (files, stats)
+    }
+
+    #[test]
+    fn test_search_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("notes")).unwrap();
+        fs::create_dir_all(vault.join("archive")).unwrap();
+        fs::write(vault.join("notes/a.md"), "intro\r\nTODO: 写测试 todo\nend\n").unwrap();
+        fs::write(vault.join("notes/b.txt"), "nothing todo here").unwrap();
+        fs::write(vault.join("archive/old.md"), "TODO old").unwrap();
+        fs::write(vault.join(".hidden.md"), "TODO hidden").unwrap();
+        fs::write(vault.join("image.png"), b"TODO\0binary").unwrap();
+
+        // Case-insensitive literal search, ranges in UTF-16 units, context lines
+        let options = SearchOptions { context_lines: 1, ..Default::default() };
+        let (files, stats) = search(vault, "todo", &options);
+        let paths: Vec<_> = files.iter().map(|f| f.relative_path.as_str()).collect();
+        assert_eq!(paths, vec!["archive/old.md", "notes/a.md", "notes/b.txt"]);
+        assert_eq!(stats.total_matches, 3);
+        assert!(!stats.truncated);
+        let hit = &files[1].matches[0];
+        assert_eq!(hit.line, 2);
+        assert_eq!(hit.ranges, vec![[0, 4], [10, 14]]);
+        assert_eq!(hit.before, vec!["intro"]);
+        assert_eq!(hit.after, vec!["end"]);
+
+        // Case-sensitive regex, glob filters, directory scope
+        let options = SearchOptions {
+            regex: true,
+            case_sensitive: true,
+            include: vec!["*.md".to_string()],
+            exclude: vec!["archive/**".to_string()],
+            ..Default::default()
+        };
+        let (files, _) = search(vault, "^TODO", &options);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].relative_path, "notes/a.md");
+
+        let options = SearchOptions { directory: Some("archive".to_string()), ..Default::default() };
+        assert_eq!(search(vault, "todo", &options).0.len(), 1);
+
+        // Limits stop the walk
+        let options = SearchOptions { max_results: 2, ..Default::default() };
+        let (files, stats) = search(vault, "todo", &options);
+        assert_eq!(stats.total_matches, 2);
+        assert!(stats.truncated);
+        assert_eq!(files.len(), 2);
+
+        let invalid = SearchOptions { regex: true, ..Default::default() };
+        assert!(search_vault(vault, "(", &invalid, &mut |_| {}).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! Per-vault ignore rules
+//!
+//! Decides which files belong to the vault. Directory listings, the watcher,
+//! vault search and the full-text and link indexes all go through `VaultIgnore`,
+//! so they agree on what is hidden. Vector (RAG) indexing reads the vault through
+//! `list_directory` and picks the rules up from there.
+//!
+//! Rules use gitignore syntax. The defaults hide dotfiles, `node_modules` and
+//! `target`; a `.luminaignore` file in the vault root is applied on top, so it can
+//! both hide more (`attachments/`) and re-include defaults (`!.obsidian/`).
+//! The app's own bookkeeping (`.git`, sync state, trash) is always hidden.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Component, Path};
+
+/// Ignore file in the vault root
+pub const IGNORE_FILE: &str = ".luminaignore";
+
+/// Default rules, overridable from the ignore file
+const DEFAULT_RULES: &[&str] = &[".*", "node_modules/", "target/"];
+
+/// Names that are always hidden, whatever the ignore file says
+const INTERNAL_NAMES: &[&str] = &[
+    ".git",
+    ".lumina",
+    ".lumina-sync",
+    ".lumina-trash",
+    ".lumina-sync-state.json",
+];
+
+/// Compiled ignore rules for one vault
+pub struct VaultIgnore {
+    matcher: Gitignore,
+}
+
+impl VaultIgnore {
+    /// Load the defaults plus the vault's `.luminaignore`
+    ///
+    /// Invalid lines in the ignore file are skipped rather than failing, so a
+    /// typo never hides the whole vault.
+    pub fn load(vault_path: &Path) -> Self {
+        let mut builder = GitignoreBuilder::new(vault_path);
+        for rule in DEFAULT_RULES {
+            let _ = builder.add_line(None, rule);
+        }
+
+        let ignore_file = vault_path.join(IGNORE_FILE);
+        if ignore_file.is_file() {
+            if let Some(e) = builder.add(&ignore_file) {
+                eprintln!(
+                    "[VaultIgnore] Skipping invalid rules in {}: {}",
+                    ignore_file.display(),
+                    e
+                );
+            }
+        }
+
+        let matcher = builder.build().unwrap_or_else(|e| {
+            eprintln!("[VaultIgnore] Failed to build ignore rules: {}", e);
+            Gitignore::empty()
+        });
+        Self { matcher }
+    }
+
+    /// Whether a path relative to the vault root (or any of its parents) is ignored
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        let mut components = relative.components().peekable();
+        if components.peek().is_none() {
+            return false;
+        }
+        let escapes = relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+        if escapes {
+            return true;
+        }
+
+        let internal = relative
+            .components()
+            .any(|c| INTERNAL_NAMES.iter().any(|n| c.as_os_str() == *n));
+        internal
+            || self
+                .matcher
+                .matched_path_or_any_parents(relative, is_dir)
+                .is_ignore()
+    }
+
+    /// Whether the entry itself matches (parents are assumed to be visible,
+    /// as when walking the tree top-down)
+    pub fn is_entry_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        let internal = relative
+            .file_name()
+            .is_some_and(|name| INTERNAL_NAMES.iter().any(|n| name == *n));
+        internal || self.matcher.matched(relative, is_dir).is_ignore()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_defaults() {
+        let vault = tempfile::tempdir().unwrap();
+        let rules = VaultIgnore::load(vault.path());
+
+        assert!(rules.is_ignored(Path::new(".obsidian"), true));
+        assert!(rules.is_ignored(Path::new("notes/.hidden.md"), false));
+        assert!(rules.is_ignored(Path::new("app/node_modules/x.md"), false));
+        assert!(rules.is_ignored(Path::new("../outside.md"), false));
+        assert!(!rules.is_ignored(Path::new("notes/a.md"), false));
+        assert!(!rules.is_ignored(Path::new(""), true));
+    }
+
+    #[test]
+    fn test_ignore_file_hides_and_reincludes() {
+        let vault = tempfile::tempdir().unwrap();
+        fs::write(
+            vault.path().join(IGNORE_FILE),
+            "# vault rules\nattachments/\n*.pdf\n!.obsidian/\n!.git/\n[invalid\n",
+        )
+        .unwrap();
+        let rules = VaultIgnore::load(vault.path());
+
+        assert!(rules.is_ignored(Path::new("attachments"), true));
+        assert!(rules.is_ignored(Path::new("attachments/img.png"), false));
+        assert!(rules.is_ignored(Path::new("papers/a.pdf"), false));
+        assert!(!rules.is_ignored(Path::new(".obsidian/app.json"), false));
+        assert!(!rules.is_entry_ignored(Path::new(".obsidian"), true));
+        // Internal directories cannot be re-included
+        assert!(rules.is_ignored(Path::new(".git/config"), false));
+        assert!(!rules.is_ignored(Path::new("notes/a.md"), false));
+    }
+}
@@ -1,22 +1,102 @@
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::Path;
-use std::sync::mpsc::channel;
-use tauri::{AppHandle, Emitter};
-
-/// File system event types
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(tag = "type")]
-#[allow(dead_code)]
-pub enum FsEvent {
-    Created { path: String },
-    Modified { path: String },
-    Deleted { path: String },
-    Renamed { old_path: String, new_path: String },
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, EventTarget, Manager};
+
+use super::events::{ChangeListeners, EventProcessor, WatchOptions};
+use super::vault_ignore::IGNORE_FILE;
+use super::TreeChange;
+use crate::note_index::NoteIndexState;
+use crate::search_index::SearchIndexState;
+
+pub use super::events::FsEvent;
+
+/// Longest a busy stream of events can delay a batch, in debounce periods
+const MAX_BATCH_PERIODS: u32 = 10;
+
+/// Running watchers, keyed by owning window label and watched path
+///
+/// Dropping a watcher closes its event channel, which ends its thread.
+#[derive(Default)]
+pub struct WatcherState {
+    watchers: Mutex<HashMap<(String, String), RecommendedWatcher>>,
+    listeners: ChangeListeners,
+}
+
+impl WatcherState {
+    /// Start watching `watch_path` for `window`, replacing an existing watcher for the same pair
+    pub fn start(
+        &self,
+        app: AppHandle,
+        window: &str,
+        watch_path: String,
+        options: WatchOptions,
+    ) -> Result<(), String> {
+        let key = (window.to_string(), watch_key(&watch_path));
+        let watcher = start_watcher(
+            app,
+            window.to_string(),
+            watch_path,
+            options,
+            self.listeners.clone(),
+        )?;
+        let mut watchers = self.watchers.lock().map_err(|e| e.to_string())?;
+        watchers.insert(key, watcher);
+        Ok(())
+    }
+
+    /// Subscribers to the raw changes seen by the watchers
+    pub fn listeners(&self) -> &ChangeListeners {
+        &self.listeners
+    }
+
+    /// Whether some window watches `watch_path`, i.e. its listeners are fed
+    pub fn is_watching(&self, watch_path: &str) -> bool {
+        let key = watch_key(watch_path);
+        self.watchers
+            .lock()
+            .map(|watchers| watchers.keys().any(|(_, watched)| *watched == key))
+            .unwrap_or(false)
+    }
+
+    /// Stop the window's watcher for `watch_path`, or all of its watchers when `None`
+    pub fn stop(&self, window: &str, watch_path: Option<&str>) {
+        if let Ok(mut watchers) = self.watchers.lock() {
+            let path = watch_path.map(watch_key);
+            watchers.retain(|(label, watched), _| {
+                label != window || path.as_ref().is_some_and(|p| p != watched)
+            });
+        }
+    }
+}
+
+/// Watched path as a key (without trailing separators)
+fn watch_key(path: &str) -> String {
+    let trimmed = path.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() { path } else { trimmed }.to_string()
 }
 
 /// Start watching a directory for changes
-/// Emits "fs:change" events to the frontend
-pub fn start_watcher(app: AppHandle, watch_path: String) -> Result<(), String> {
+/// Emits "fs:change" with a debounced batch of `FsEvent`s for files matching
+/// the extension filter (leaving out `window`'s own writes), and "fs:tree" events (`TreeChange`) for every visible
+/// entry, so the file tree can be patched in place. Events go only to `window`.
+/// Paths hidden by the vault's ignore rules are not reported; editing the
+/// ignore file reloads the rules and emits `TreeChange::Reset`. Open full-text
+/// and link indexes of the watched vault are updated with every batch, and
+/// "links:changed" is emitted when the link index changed. `listeners`
+/// subscribed to the vault get every batch of changed paths.
+///
+/// Watching stops when the returned watcher is dropped.
+fn start_watcher(
+    app: AppHandle,
+    window: String,
+    watch_path: String,
+    options: WatchOptions,
+    listeners: ChangeListeners,
+) -> Result<RecommendedWatcher, String> {
     let (tx, rx) = channel();
 
     let mut watcher = RecommendedWatcher::new(
@@ -33,50 +113,68 @@ pub fn start_watcher(app: AppHandle, watch_path: String) -> Result<(), String> {
         .watch(Path::new(&watch_path), RecursiveMode::Recursive)
         .map_err(|e| e.to_string())?;
 
-    // Spawn a thread to handle events
+    // Spawn a thread to handle events; it ends once the watcher is dropped
     std::thread::spawn(move || {
-        // Keep watcher alive
-        let _watcher = watcher;
+        let target = EventTarget::webview_window(window.clone());
+        let root = PathBuf::from(&watch_path);
+        let ignore_file = root.join(IGNORE_FILE);
+        let mut processor = EventProcessor::new(&root, &window, &options);
+        let debounce = Duration::from_millis(options.debounce_ms);
 
-        while let Ok(event) = rx.recv() {
-            // Process .md and .db.json files
-            let paths: Vec<_> = event
-                .paths
+        while let Ok(first) = rx.recv() {
+            // Collect until the stream has been quiet for one debounce period
+            let mut batch = vec![first];
+            let deadline = Instant::now() + debounce * MAX_BATCH_PERIODS;
+            let mut connected = true;
+            while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                match rx.recv_timeout(debounce.min(remaining)) {
+                    Ok(event) => batch.push(event),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        connected = false;
+                        break;
+                    }
+                }
+            }
+
+            let touched: BTreeSet<&PathBuf> = batch
                 .iter()
-                .filter(|p| {
-                    let path_str = p.to_string_lossy();
-                    path_str.ends_with(".md") || path_str.ends_with(".db.json")
-                })
+                .filter(|e| !matches!(e.kind, notify::EventKind::Access(_)))
+                .flat_map(|e| &e.paths)
                 .collect();
 
-            if paths.is_empty() {
-                continue;
+            // Keep the vault's indexes and subscribers current, including the app's own writes
+            let paths: Vec<&Path> = touched.iter().map(|p| p.as_path()).collect();
+            listeners.notify(&root, &paths);
+            if let Some(index) = app.try_state::<SearchIndexState>() {
+                index.update(&root, &paths);
             }
-
-            let fs_event = match event.kind {
-                notify::EventKind::Create(_) => {
-                    paths.first().map(|p| FsEvent::Created {
-                        path: p.to_string_lossy().to_string(),
-                    })
-                }
-                notify::EventKind::Modify(_) => {
-                    paths.first().map(|p| FsEvent::Modified {
-                        path: p.to_string_lossy().to_string(),
-                    })
+            if let Some(index) = app.try_state::<NoteIndexState>() {
+                if index.update(&root, &paths) {
+                    let _ = app.emit_to(target.clone(), "links:changed", ());
                 }
-                notify::EventKind::Remove(_) => {
-                    paths.first().map(|p| FsEvent::Deleted {
-                        path: p.to_string_lossy().to_string(),
-                    })
+            }
+
+            if touched.contains(&ignore_file) {
+                processor.reload_rules();
+                let _ = app.emit_to(target.clone(), "fs:tree", TreeChange::Reset);
+            }
+            for path in touched {
+                if let Some(change) = super::tree_change(&root, processor.rules(), path) {
+                    let _ = app.emit_to(target.clone(), "fs:tree", change);
                 }
-                _ => None,
-            };
+            }
+
+            let events = processor.process(&batch);
+            if !events.is_empty() {
+                let _ = app.emit_to(target.clone(), "fs:change", events);
+            }
 
-            if let Some(evt) = fs_event {
-                let _ = app.emit("fs:change", evt);
+            if !connected {
+                break;
             }
         }
     });
 
-    Ok(())
+    Ok(watcher)
 }
@@ -2,6 +2,8 @@ mod commands;
 mod error;
 mod fs;
 mod vector_db;
+mod search_index;
+mod note_index;
 mod llm;
 
 pub use commands::*;
@@ -16,3 +18,14 @@ pub use vector_db::{
     delete_file_vectors, delete_vectors, get_vector_index_status,
     check_file_needs_reindex, clear_vector_index,
 };
+
+pub use search_index::{
+    SearchIndex, SearchIndexState, IndexHit, IndexStats,
+    open_search_index, rebuild_search_index, query_search_index,
+};
+
+pub use note_index::{
+    NoteIndex, NoteIndexState, NoteLink, UnresolvedLink, UnresolvedReason,
+    GraphNode, GraphEdge, LinkGraph,
+    open_note_index, get_backlinks, get_outgoing_links, get_unresolved_links, get_link_graph,
+};
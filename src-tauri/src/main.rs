@@ -7,6 +7,8 @@ mod commands;
 mod fs;
 mod error;
 mod vector_db;
+mod search_index;
+mod note_index;
 mod llm;
 mod cef;
 mod webdav;
@@ -20,15 +22,22 @@ fn main() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_os::init())
         .invoke_handler(tauri::generate_handler![
+            commands::set_vault_root,
+            commands::pick_vault_root,
+            commands::allow_external_path,
             commands::read_file,
             commands::save_file,
+            commands::merge_text,
             commands::write_binary_file,
             commands::read_binary_file_base64,
             commands::list_directory,
+            commands::list_directory_page,
+            commands::stat_file,
             commands::create_file,
             commands::create_dir,
             commands::delete_file,
             commands::rename_file,
+            commands::search_vault,
             commands::show_in_explorer,
             commands::open_video_window,
             commands::close_video_window,
@@ -44,6 +53,7 @@ fn main() {
             commands::fill_danmaku_prefix,
             commands::setup_danmaku_autofill,
             commands::start_file_watcher,
+            commands::stop_file_watcher,
             // Browser WebView commands
             commands::create_browser_webview,
             commands::update_browser_webview_bounds,
@@ -81,6 +91,16 @@ fn main() {
             vector_db::get_vector_index_status,
             vector_db::check_file_needs_reindex,
             vector_db::clear_vector_index,
+            // Full-text index commands
+            search_index::open_search_index,
+            search_index::rebuild_search_index,
+            search_index::query_search_index,
+            // Note link index commands
+            note_index::open_note_index,
+            note_index::get_backlinks,
+            note_index::get_outgoing_links,
+            note_index::get_unresolved_links,
+            note_index::get_link_graph,
             // LLM HTTP client
             llm::llm_fetch,
             llm::llm_fetch_stream,
@@ -88,8 +108,11 @@ fn main() {
             llm::append_debug_log,
             llm::get_debug_log_path,
             // WebDAV commands
-            webdav::commands::webdav_set_config,
-            webdav::commands::webdav_get_config,
+            webdav::commands::webdav_list_profiles,
+            webdav::commands::webdav_save_profile,
+            webdav::commands::webdav_delete_profile,
+            webdav::commands::webdav_set_active_profile,
+            webdav::commands::webdav_test_profile,
             webdav::commands::webdav_test_connection,
             webdav::commands::webdav_list_remote,
             webdav::commands::webdav_list_all_remote,
@@ -100,9 +123,33 @@ fn main() {
             webdav::commands::webdav_compute_sync_plan,
             webdav::commands::webdav_execute_sync,
             webdav::commands::webdav_quick_sync,
+            webdav::commands::webdav_cancel_sync,
+            webdav::commands::webdav_get_interrupted_sync,
+            webdav::commands::webdav_resume_sync,
+            webdav::commands::webdav_preview_exclusions,
+            webdav::commands::webdav_get_sync_history,
+            webdav::commands::webdav_list_versions,
+            webdav::commands::webdav_restore_version,
             webdav::commands::webdav_scan_local,
+            webdav::commands::webdav_start_auto_sync,
+            webdav::commands::webdav_stop_auto_sync,
         ])
         .manage(webdav::commands::WebDAVState::new())
+        .manage(fs::watcher::WatcherState::default())
+        .manage(fs::sandbox::VaultSandbox::default())
+        .manage(search_index::SearchIndexState::default())
+        .manage(note_index::NoteIndexState::default())
+        .on_window_event(|window, event| {
+            // 窗口关闭时停止它的文件监听并移除它的 vault
+            if let tauri::WindowEvent::Destroyed = event {
+                window
+                    .state::<fs::watcher::WatcherState>()
+                    .stop(window.label(), None);
+                window
+                    .state::<fs::sandbox::VaultSandbox>()
+                    .remove_window(window.label());
+            }
+        })
         .setup(|app| {
             let window = app.get_webview_window("main").unwrap();
             
@@ -0,0 +1,114 @@
+//! Tauri commands for the note link index
+
+use std::path::Path;
+use tauri::State;
+
+use super::{LinkGraph, NoteIndexState, NoteLink, UnresolvedLink};
+use crate::error::AppError;
+use crate::fs::sandbox::VaultSandbox;
+
+/// Run a query against the vault's open index off the async runtime
+async fn with_index<T: Send + 'static>(
+    state: &NoteIndexState,
+    sandbox: &VaultSandbox,
+    window: &str,
+    vault_path: &str,
+    f: impl FnOnce(&super::NoteIndex) -> T + Send + 'static,
+) -> Result<T, AppError> {
+    sandbox.check(window, vault_path)?;
+    let index = state
+        .get(Path::new(vault_path))
+        .ok_or_else(|| AppError::Database("Note index not opened".into()))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = index.lock().map_err(|_| AppError::Database("Lock poisoned".into()))?;
+        Ok(f(&index))
+    })
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?
+}
+
+/// Open the vault's link index and parse notes that changed since the last sync
+/// Afterwards the file watcher of the vault keeps it current and emits "links:changed".
+/// Returns the number of notes.
+#[tauri::command]
+pub async fn open_note_index(
+    state: State<'_, NoteIndexState>,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+) -> Result<usize, AppError> {
+    sandbox.check(window.label(), &vault_path)?;
+    let index = state.open(Path::new(&vault_path))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut index = index.lock().map_err(|_| AppError::Database("Lock poisoned".into()))?;
+        index.sync()
+    })
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?
+}
+
+/// Links to the note at `path` (self-links included)
+#[tauri::command]
+pub async fn get_backlinks(
+    state: State<'_, NoteIndexState>,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+    path: String,
+) -> Result<Vec<NoteLink>, AppError> {
+    with_index(
+        &state,
+        &sandbox,
+        window.label(),
+        &vault_path,
+        move |index| index.backlinks(&path),
+    )
+    .await
+}
+
+/// Links written in the note at `path`, with their resolved targets
+#[tauri::command]
+pub async fn get_outgoing_links(
+    state: State<'_, NoteIndexState>,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+    path: String,
+) -> Result<Vec<NoteLink>, AppError> {
+    with_index(
+        &state,
+        &sandbox,
+        window.label(),
+        &vault_path,
+        move |index| index.outgoing_links(&path),
+    )
+    .await
+}
+
+/// Links to missing notes, headings or blocks
+#[tauri::command]
+pub async fn get_unresolved_links(
+    state: State<'_, NoteIndexState>,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+) -> Result<Vec<UnresolvedLink>, AppError> {
+    with_index(&state, &sandbox, window.label(), &vault_path, |index| {
+        index.unresolved_links()
+    })
+    .await
+}
+
+/// All notes as nodes and the links between them as edges
+#[tauri::command]
+pub async fn get_link_graph(
+    state: State<'_, NoteIndexState>,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+) -> Result<LinkGraph, AppError> {
+    with_index(&state, &sandbox, window.label(), &vault_path, |index| {
+        index.graph()
+    })
+    .await
+}
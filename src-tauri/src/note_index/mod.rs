@@ -0,0 +1,577 @@
+//! Note Link Index Module
+//!
+//! In-memory index of the links between notes, replacing the frontend parse of
+//! every note. It is built when a vault is opened and kept current from watcher
+//! events; links are resolved at query time, so a link starts resolving as soon
+//! as its target note is created.
+
+pub mod commands;
+pub mod parser;
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::error::AppError;
+use crate::fs::vault_ignore::IGNORE_FILE;
+use crate::fs::{visit_files, FileEntry, VaultIgnore};
+use parser::{normalize_heading, parse_note, LinkKind, LinkRef, ParsedNote};
+
+pub use commands::*;
+
+/// A link between two files
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteLink {
+    /// Absolute path of the note containing the link
+    pub source: String,
+    /// Absolute path of the linked file, `None` if it doesn't exist
+    pub target_path: Option<String>,
+    #[serde(flatten)]
+    pub link: LinkRef,
+}
+
+/// Why a link doesn't resolve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnresolvedReason {
+    /// No such note or file
+    Note,
+    /// The note exists but has no such heading
+    Heading,
+    /// The note exists but has no such block id
+    Block,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnresolvedLink {
+    pub reason: UnresolvedReason,
+    #[serde(flatten)]
+    pub link: NoteLink,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphNode {
+    pub path: String,
+    pub relative_path: String,
+    /// File name without `.md`
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+/// Links from one note to another, merged per pair
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    /// Number of links from `source` to `target`
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LinkGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+struct IndexedNote {
+    stamp: (u64, u64),
+    parsed: ParsedNote,
+}
+
+/// Link index of one vault
+pub struct NoteIndex {
+    vault: PathBuf,
+    rules: VaultIgnore,
+    /// Notes by relative path (`/` separators)
+    notes: HashMap<String, IndexedNote>,
+    /// Other visible files (attachments), by relative path
+    files: HashSet<String>,
+}
+
+impl NoteIndex {
+    pub fn new(vault: &Path) -> Self {
+        Self {
+            vault: vault.to_path_buf(),
+            rules: VaultIgnore::load(vault),
+            notes: HashMap::new(),
+            files: HashSet::new(),
+        }
+    }
+
+    /// Re-read the vault; only notes whose mtime or size changed are parsed again
+    ///
+    /// Returns the number of notes.
+    pub fn sync(&mut self) -> Result<usize, AppError> {
+        self.rules = VaultIgnore::load(&self.vault);
+        let mut notes = HashMap::new();
+        let mut files = HashSet::new();
+        let mut previous = std::mem::take(&mut self.notes);
+        visit_files(&self.vault, &self.vault, &self.rules, &mut |entry| {
+            let relative = relative_path(&self.vault, Path::new(&entry.path));
+            if is_note(&relative) {
+                let note = match previous.remove(&relative) {
+                    Some(note) if note.stamp == stamp(entry) => note,
+                    _ => read_note(entry),
+                };
+                notes.insert(relative, note);
+            } else {
+                files.insert(relative);
+            }
+            true
+        })?;
+        self.notes = notes;
+        self.files = files;
+        Ok(self.notes.len())
+    }
+
+    /// Update the index for paths reported by the watcher
+    ///
+    /// Paths may be files or directories, created, modified or gone. Returns
+    /// whether anything changed.
+    pub fn update_paths(&mut self, paths: &[&Path]) -> Result<bool, AppError> {
+        if paths.iter().any(|p| *p == self.vault.join(IGNORE_FILE)) {
+            self.sync()?;
+            return Ok(true);
+        }
+
+        let mut changed = false;
+        for path in paths {
+            let Ok(relative) = path.strip_prefix(&self.vault) else {
+                continue;
+            };
+            let is_dir = path.is_dir();
+            if relative.as_os_str().is_empty() || self.rules.is_ignored(relative, is_dir) {
+                continue;
+            }
+            let relative = relative_path(&self.vault, path);
+
+            if !path.exists() {
+                let prefix = format!("{}/", relative);
+                let before = self.notes.len() + self.files.len();
+                self.notes.retain(|p, _| *p != relative && !p.starts_with(&prefix));
+                self.files.retain(|p| *p != relative && !p.starts_with(&prefix));
+                changed |= self.notes.len() + self.files.len() != before;
+                continue;
+            }
+
+            let mut update = |entry: &FileEntry| {
+                let relative = relative_path(&self.vault, Path::new(&entry.path));
+                if !is_note(&relative) {
+                    changed |= self.files.insert(relative);
+                } else if self.notes.get(&relative).map(|n| n.stamp) != Some(stamp(entry)) {
+                    self.notes.insert(relative, read_note(entry));
+                    changed = true;
+                }
+                true
+            };
+            if is_dir {
+                visit_files(&self.vault, path, &self.rules, &mut update)?;
+            } else if let Ok(entry) =
+                crate::fs::stat_entry(&path.to_string_lossy(), Some(&self.vault.to_string_lossy()))
+            {
+                update(&entry);
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Links pointing at `path`, including links within the note itself
+    pub fn backlinks(&self, path: &str) -> Vec<NoteLink> {
+        let target = relative_path(&self.vault, Path::new(path));
+        let resolver = Resolver::new(self);
+        let mut sources: Vec<&String> = self.notes.keys().collect();
+        sources.sort();
+        let mut links = Vec::new();
+        for source in sources {
+            for link in &self.notes[source].parsed.links {
+                if resolver.resolve(source, link).as_ref() == Some(&target) {
+                    links.push(self.note_link(source, link, Some(&target)));
+                }
+            }
+        }
+        links
+    }
+
+    /// Links written in the note at `path`
+    pub fn outgoing_links(&self, path: &str) -> Vec<NoteLink> {
+        let source = relative_path(&self.vault, Path::new(path));
+        let Some(note) = self.notes.get(&source) else {
+            return Vec::new();
+        };
+        let resolver = Resolver::new(self);
+        note.parsed
+            .links
+            .iter()
+            .map(|link| {
+                let target = resolver.resolve(&source, link);
+                self.note_link(&source, link, target.as_ref())
+            })
+            .collect()
+    }
+
+    /// Links to missing notes, headings or blocks, across the vault
+    pub fn unresolved_links(&self) -> Vec<UnresolvedLink> {
+        let resolver = Resolver::new(self);
+        let mut sources: Vec<&String> = self.notes.keys().collect();
+        sources.sort();
+        let mut unresolved = Vec::new();
+        for source in sources {
+            for link in &self.notes[source].parsed.links {
+                let target = resolver.resolve(source, link);
+                let reason = match target.as_ref().map(|t| self.notes.get(t)) {
+                    None => Some(UnresolvedReason::Note),
+                    Some(Some(note)) => match (&link.heading, &link.block) {
+                        (Some(h), _) if !note.parsed.headings.contains(&normalize_heading(h)) => {
+                            Some(UnresolvedReason::Heading)
+                        }
+                        (_, Some(b)) if !note.parsed.blocks.contains(b) => Some(UnresolvedReason::Block),
+                        _ => None,
+                    },
+                    // Attachments have no headings or blocks
+                    Some(None) => None,
+                };
+                if let Some(reason) = reason {
+                    unresolved.push(UnresolvedLink {
+                        reason,
+                        link: self.note_link(source, link, target.as_ref()),
+                    });
+                }
+            }
+        }
+        unresolved
+    }
+
+    /// All notes and the resolved links between them
+    pub fn graph(&self) -> LinkGraph {
+        let resolver = Resolver::new(self);
+        let mut relatives: Vec<&String> = self.notes.keys().collect();
+        relatives.sort();
+
+        let mut graph = LinkGraph::default();
+        let mut edges: HashMap<(&str, String), usize> = HashMap::new();
+        for relative in &relatives {
+            let note = &self.notes[*relative];
+            graph.nodes.push(GraphNode {
+                path: self.absolute(relative),
+                relative_path: relative.to_string(),
+                name: note_name(relative).to_string(),
+                aliases: note.parsed.aliases.clone(),
+                tags: note.parsed.tags.clone(),
+            });
+            for link in &note.parsed.links {
+                match resolver.resolve(relative, link) {
+                    Some(target) if target != **relative && self.notes.contains_key(&target) => {
+                        *edges.entry((relative.as_str(), target)).or_default() += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut edges: Vec<_> = edges.into_iter().collect();
+        edges.sort();
+        graph.edges = edges
+            .into_iter()
+            .map(|((source, target), count)| GraphEdge {
+                source: self.absolute(source),
+                target: self.absolute(&target),
+                count,
+            })
+            .collect();
+        graph
+    }
+
+    fn absolute(&self, relative: &str) -> String {
+        self.vault.join(relative).to_string_lossy().to_string()
+    }
+
+    fn note_link(&self, source: &str, link: &LinkRef, target: Option<&String>) -> NoteLink {
+        NoteLink {
+            source: self.absolute(source),
+            target_path: target.map(|t| self.absolute(t)),
+            link: link.clone(),
+        }
+    }
+}
+
+/// Resolves link targets against the notes and files of an index
+struct Resolver<'a> {
+    index: &'a NoteIndex,
+    /// Lowercased note names and aliases -> relative paths
+    names: HashMap<String, Vec<&'a str>>,
+    /// Lowercased relative paths of all notes and files -> relative paths
+    paths: HashMap<String, &'a str>,
+    /// Lowercased attachment file names -> relative paths
+    file_names: HashMap<String, Vec<&'a str>>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(index: &'a NoteIndex) -> Self {
+        let mut names: HashMap<String, Vec<&str>> = HashMap::new();
+        let mut paths = HashMap::new();
+        let mut file_names: HashMap<String, Vec<&str>> = HashMap::new();
+        for (relative, note) in &index.notes {
+            names.entry(note_name(relative).to_lowercase()).or_default().push(relative);
+            for alias in &note.parsed.aliases {
+                names.entry(alias.to_lowercase()).or_default().push(relative);
+            }
+            paths.insert(relative.to_lowercase(), relative.as_str());
+        }
+        for relative in &index.files {
+            let name = relative.rsplit('/').next().unwrap_or(relative);
+            file_names.entry(name.to_lowercase()).or_default().push(relative);
+            paths.insert(relative.to_lowercase(), relative.as_str());
+        }
+        Self { index, names, paths, file_names }
+    }
+
+    /// Relative path of the linked note or file
+    fn resolve(&self, source: &str, link: &LinkRef) -> Option<String> {
+        let target = link.target.trim().replace('\\', "/");
+        if target.is_empty() {
+            return Some(source.to_string());
+        }
+        match link.kind {
+            LinkKind::Markdown => self.resolve_path(source, &target),
+            LinkKind::Wiki => self.resolve_wiki(source, &target),
+        }
+    }
+
+    /// `[[name]]`, `[[folder/name]]` or `[[file.png]]`
+    fn resolve_wiki(&self, source: &str, target: &str) -> Option<String> {
+        let lower = target.trim_start_matches('/').to_lowercase();
+        let note = lower.strip_suffix(".md").unwrap_or(&lower);
+
+        if note.contains('/') {
+            let with_ext = format!("{}.md", note);
+            if let Some(path) = self.paths.get(&with_ext).or_else(|| self.paths.get(&lower)) {
+                return Some(path.to_string());
+            }
+            // A partial path matches notes in any folder ending with it
+            let suffix = format!("/{}", with_ext);
+            let candidates: Vec<&str> = self
+                .index
+                .notes
+                .keys()
+                .filter(|p| p.to_lowercase().ends_with(&suffix))
+                .map(String::as_str)
+                .collect();
+            return pick_closest(source, &candidates);
+        }
+
+        if let Some(candidates) = self.names.get(note) {
+            return pick_closest(source, candidates);
+        }
+        self.file_names
+            .get(&lower)
+            .and_then(|candidates| pick_closest(source, candidates))
+    }
+
+    /// `[text](relative/path.md)`, relative to the linking note (or the vault with a leading `/`)
+    fn resolve_path(&self, source: &str, target: &str) -> Option<String> {
+        let base = if target.starts_with('/') {
+            ""
+        } else {
+            source.rsplit_once('/').map_or("", |(dir, _)| dir)
+        };
+        let joined = normalize(&format!("{}/{}", base, target.trim_start_matches('/')))?;
+        let lower = joined.to_lowercase();
+        self.paths
+            .get(&lower)
+            .or_else(|| self.paths.get(&format!("{}.md", lower)))
+            .map(|p| p.to_string())
+    }
+}
+
+/// Among several matches prefer the one closest to the linking note, then the shortest path
+fn pick_closest(source: &str, candidates: &[&str]) -> Option<String> {
+    let source_dir = source.rsplit_once('/').map_or("", |(dir, _)| dir);
+    candidates
+        .iter()
+        .min_by_key(|path| {
+            let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+            (dir != source_dir, path.matches('/').count(), path.len(), **path)
+        })
+        .map(|p| p.to_string())
+}
+
+/// Resolve `.` and `..` in a relative path; `None` if it leaves the vault
+fn normalize(path: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            _ => {}
+        }
+    }
+    Some(parts.join("/"))
+}
+
+fn relative_path(vault: &Path, path: &Path) -> String {
+    path.strip_prefix(vault)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn is_note(relative: &str) -> bool {
+    relative.to_lowercase().ends_with(".md")
+}
+
+fn note_name(relative: &str) -> &str {
+    let name = relative.rsplit('/').next().unwrap_or(relative);
+    &name[..name.len() - 3]
+}
+
+fn stamp(entry: &FileEntry) -> (u64, u64) {
+    (entry.modified.unwrap_or(0), entry.size)
+}
+
+fn read_note(entry: &FileEntry) -> IndexedNote {
+    let content = fs::read_to_string(&entry.path).unwrap_or_default();
+    IndexedNote {
+        stamp: stamp(entry),
+        parsed: parse_note(&content),
+    }
+}
+
+/// Open link indexes, keyed by vault path
+#[derive(Default)]
+pub struct NoteIndexState {
+    indexes: Mutex<HashMap<PathBuf, Arc<Mutex<NoteIndex>>>>,
+}
+
+impl NoteIndexState {
+    /// The vault's index, creating an empty one on first use
+    pub fn open(&self, vault: &Path) -> Result<Arc<Mutex<NoteIndex>>, AppError> {
+        let mut indexes = self.indexes.lock().map_err(|_| AppError::Database("Lock poisoned".into()))?;
+        Ok(indexes
+            .entry(vault.to_path_buf())
+            .or_insert_with(|| Arc::new(Mutex::new(NoteIndex::new(vault))))
+            .clone())
+    }
+
+    /// The vault's index if it has been opened
+    pub fn get(&self, vault: &Path) -> Option<Arc<Mutex<NoteIndex>>> {
+        self.indexes.lock().ok()?.get(vault).cloned()
+    }
+
+    /// Apply watcher changes to the vault's index, if it is open; returns whether links may have changed
+    pub fn update(&self, vault: &Path, paths: &[&Path]) -> bool {
+        let Some(index) = self.get(vault) else {
+            return false;
+        };
+        let Ok(mut index) = index.lock() else {
+            return false;
+        };
+        index.update_paths(paths).unwrap_or_else(|e| {
+            eprintln!("[NoteIndex] Failed to update index: {}", e);
+            false
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_links_backlinks_and_graph() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("a")).unwrap();
+        fs::create_dir_all(vault.join("b")).unwrap();
+        fs::write(vault.join("Index.md"), "[[Topic]] [[Alias Name#Part 1]] [[missing]] [[Topic#Nope]]\n[x](a/topic.md) ![[pic.png]]").unwrap();
+        fs::write(vault.join("a/topic.md"), "# Part 1\nBack to [[index]] and [[#Part 1]] ^blk").unwrap();
+        fs::write(vault.join("b/Topic.md"), "[[Topic]] [[a/topic#^blk]]").unwrap();
+        fs::write(vault.join("b/other.md"), "---\naliases: [Alias Name]\n---\n# Part 1\n").unwrap();
+        fs::write(vault.join("pic.png"), "").unwrap();
+
+        let mut index = NoteIndex::new(vault);
+        assert_eq!(index.sync().unwrap(), 4);
+
+        // Same-name notes: the one in the linking note's folder wins, then the shorter path
+        let outgoing = index.outgoing_links(&s(&vault.join("Index.md")));
+        let targets: Vec<_> = outgoing.iter().map(|l| l.target_path.clone()).collect();
+        assert_eq!(
+            targets,
+            vec![
+                Some(s(&vault.join("a/topic.md"))),
+                Some(s(&vault.join("b/other.md"))),
+                None,
+                Some(s(&vault.join("a/topic.md"))),
+                Some(s(&vault.join("pic.png"))),
+                Some(s(&vault.join("a/topic.md"))),
+            ]
+        );
+        let backlinks = index.backlinks(&s(&vault.join("b/Topic.md")));
+        assert_eq!(backlinks.len(), 1);
+        assert_eq!(backlinks[0].source, s(&vault.join("b/Topic.md")));
+
+        let backlinks = index.backlinks(&s(&vault.join("a/topic.md")));
+        let sources: Vec<_> = backlinks.iter().map(|l| (l.source.clone(), l.link.line)).collect();
+        assert_eq!(
+            sources,
+            vec![
+                (s(&vault.join("Index.md")), 1),
+                (s(&vault.join("Index.md")), 1),
+                (s(&vault.join("Index.md")), 2),
+                (s(&vault.join("a/topic.md")), 2),
+                (s(&vault.join("b/Topic.md")), 1),
+            ]
+        );
+
+        let unresolved: Vec<_> = index
+            .unresolved_links()
+            .into_iter()
+            .map(|u| (u.reason, u.link.link.target))
+            .collect();
+        assert_eq!(
+            unresolved,
+            vec![(UnresolvedReason::Note, "missing".to_string()), (UnresolvedReason::Heading, "Topic".to_string())]
+        );
+
+        let graph = index.graph();
+        assert_eq!(graph.nodes.len(), 4);
+        let edges: Vec<_> = graph
+            .edges
+            .iter()
+            .map(|e| (relative_path(vault, Path::new(&e.source)), relative_path(vault, Path::new(&e.target)), e.count))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("Index.md".to_string(), "a/topic.md".to_string(), 3),
+                ("Index.md".to_string(), "b/other.md".to_string(), 1),
+                ("a/topic.md".to_string(), "Index.md".to_string(), 1),
+                ("b/Topic.md".to_string(), "a/topic.md".to_string(), 1),
+            ]
+        );
+
+        // Watcher updates: a new note resolves the missing link, a deleted folder drops its notes
+        fs::write(vault.join("missing.md"), "").unwrap();
+        fs::remove_dir_all(vault.join("b")).unwrap();
+        assert!(index.update_paths(&[&vault.join("missing.md"), &vault.join("b")]).unwrap());
+        assert!(!index.update_paths(&[&vault.join("missing.md")]).unwrap());
+        let unresolved: Vec<_> = index
+            .unresolved_links()
+            .into_iter()
+            .map(|u| (u.reason, u.link.link.target))
+            .collect();
+        assert_eq!(
+            unresolved,
+            vec![(UnresolvedReason::Note, "Alias Name".to_string()), (UnresolvedReason::Heading, "Topic".to_string())]
+        );
+        assert_eq!(index.graph().nodes.len(), 3);
+    }
+}
@@ -0,0 +1,334 @@
+//! Markdown link parsing
+//!
+//! Extracts `[[wikilinks]]` (with `#heading`, `#^block` and `|alias`),
+//! Markdown links to local files, headings, block ids, tags and frontmatter
+//! aliases. Code blocks and inline code are skipped.
+
+use regex::Regex;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// How a link was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    Wiki,
+    Markdown,
+}
+
+/// A link as written in a note
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinkRef {
+    pub kind: LinkKind,
+    /// Note name or path as written (empty for a link within the same note)
+    pub target: String,
+    pub heading: Option<String>,
+    /// Block id, without the `^`
+    pub block: Option<String>,
+    /// Display text (`[[note|text]]` or `[text](note.md)`)
+    pub display: Option<String>,
+    /// `![[embed]]` or `![](image.png)`
+    pub embed: bool,
+    /// 1-based line number
+    pub line: usize,
+    /// The trimmed line containing the link
+    pub context: String,
+}
+
+/// Everything the link index needs from one note
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedNote {
+    pub links: Vec<LinkRef>,
+    pub aliases: Vec<String>,
+    /// Lowercased, without `#`
+    pub tags: Vec<String>,
+    /// Normalized with [`normalize_heading`]
+    pub headings: Vec<String>,
+    pub blocks: Vec<String>,
+}
+
+fn wikilink_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(!?)\[\[([^\[\]]+?)\]\]").unwrap())
+}
+
+fn markdown_link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(!?)\[([^\[\]]*)\]\(\s*(<[^>]*>|[^)\s]+)(?:\s+"[^"]*")?\s*\)"#).unwrap()
+    })
+}
+
+fn tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?:^|\s)#([\p{L}_][\p{L}\p{N}_/-]*)").unwrap())
+}
+
+fn block_id_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?:^|\s)\^([A-Za-z0-9-]+)\s*$").unwrap())
+}
+
+/// Heading text as compared by links: lowercased, whitespace collapsed
+pub fn normalize_heading(heading: &str) -> String {
+    heading
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Parse a note
+pub fn parse_note(content: &str) -> ParsedNote {
+    let mut note = ParsedNote::default();
+    let lines: Vec<&str> = content.lines().collect();
+
+    let body_start = parse_frontmatter(&lines, &mut note);
+    let mut fence: Option<(char, usize)> = None;
+    for (index, raw) in lines.iter().enumerate().skip(body_start) {
+        let trimmed = raw.trim_start();
+
+        // Fenced code blocks end with a fence of the same character, at least as long
+        let fence_len = |c: char| trimmed.chars().take_while(|&x| x == c).count();
+        if let Some((c, len)) = fence {
+            if fence_len(c) >= len && trimmed.trim_end().chars().all(|x| x == c) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(c) = ['`', '~'].into_iter().find(|&c| fence_len(c) >= 3) {
+            fence = Some((c, fence_len(c)));
+            continue;
+        }
+
+        let line = strip_inline_code(raw);
+        let context = raw.trim();
+
+        if let Some(heading) = heading_text(&line) {
+            note.headings.push(normalize_heading(heading));
+        }
+        if let Some(caps) = block_id_regex().captures(&line) {
+            note.blocks.push(caps[1].to_string());
+        }
+        for caps in tag_regex().captures_iter(&line) {
+            push_unique(&mut note.tags, caps[1].to_lowercase());
+        }
+
+        for caps in wikilink_regex().captures_iter(&line) {
+            let inner = &caps[2];
+            let (target, display) = match inner.split_once('|') {
+                Some((target, display)) => (target, Some(display.trim().to_string())),
+                None => (inner, None),
+            };
+            let (target, heading, block) = split_anchor(target);
+            note.links.push(LinkRef {
+                kind: LinkKind::Wiki,
+                target,
+                heading,
+                block,
+                display,
+                embed: &caps[1] == "!",
+                line: index + 1,
+                context: context.to_string(),
+            });
+        }
+
+        for caps in markdown_link_regex().captures_iter(&line) {
+            let url = caps[3].trim_start_matches('<').trim_end_matches('>');
+            if url.contains("://") || url.starts_with("mailto:") {
+                continue;
+            }
+            let decoded = urlencoding::decode(url)
+                .map(|d| d.into_owned())
+                .unwrap_or_else(|_| url.to_string());
+            let (target, heading, block) = split_anchor(&decoded);
+            let display = Some(caps[2].trim().to_string()).filter(|d| !d.is_empty());
+            note.links.push(LinkRef {
+                kind: LinkKind::Markdown,
+                target,
+                heading,
+                block,
+                display,
+                embed: &caps[1] == "!",
+                line: index + 1,
+                context: context.to_string(),
+            });
+        }
+    }
+    note
+}
+
+/// Read `aliases`/`alias` and `tags` from YAML frontmatter; returns the first body line
+fn parse_frontmatter(lines: &[&str], note: &mut ParsedNote) -> usize {
+    if lines.first().map(|l| l.trim_end()) != Some("---") {
+        return 0;
+    }
+    let Some(end) = lines
+        .iter()
+        .skip(1)
+        .position(|l| matches!(l.trim_end(), "---" | "..."))
+        .map(|p| p + 1)
+    else {
+        return 0;
+    };
+
+    let mut key: Option<String> = None;
+    for line in &lines[1..end] {
+        let trimmed = line.trim();
+        // `  - value` continues a list under the previous key
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            if let Some(key) = &key {
+                add_frontmatter_value(note, key, item);
+            }
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            continue;
+        }
+        key = None;
+        if let Some((name, value)) = trimmed.split_once(':') {
+            let name = name.trim().to_lowercase();
+            let value = value.trim();
+            if value.is_empty() {
+                key = Some(name);
+                continue;
+            }
+            let items = value
+                .strip_prefix('[')
+                .and_then(|v| v.strip_suffix(']'))
+                .unwrap_or(value);
+            for item in items.split(',') {
+                add_frontmatter_value(note, &name, item);
+            }
+        }
+    }
+    end + 1
+}
+
+fn add_frontmatter_value(note: &mut ParsedNote, key: &str, value: &str) {
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'').trim();
+    if value.is_empty() {
+        return;
+    }
+    match key {
+        "aliases" | "alias" => push_unique(&mut note.aliases, value.to_string()),
+        "tags" | "tag" => push_unique(
+            &mut note.tags,
+            value.trim_start_matches('#').to_lowercase(),
+        ),
+        _ => {}
+    }
+}
+
+fn push_unique(list: &mut Vec<String>, value: String) {
+    if !list.contains(&value) {
+        list.push(value);
+    }
+}
+
+/// Split `note#heading` / `note#^block` (nested `#a#b` headings use the last part)
+fn split_anchor(target: &str) -> (String, Option<String>, Option<String>) {
+    let Some((note, anchor)) = target.split_once('#') else {
+        return (target.trim().to_string(), None, None);
+    };
+    let note = note.trim().to_string();
+    let anchor = anchor.rsplit('#').next().unwrap_or("").trim();
+    match anchor.strip_prefix('^') {
+        Some(block) if !block.is_empty() => (note, None, Some(block.to_string())),
+        _ if anchor.is_empty() => (note, None, None),
+        _ => (note, Some(anchor.to_string()), None),
+    }
+}
+
+/// ATX heading text (`## Title ##` -> `Title`)
+fn heading_text(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let text = rest.trim().trim_end_matches('#').trim();
+    Some(text).filter(|t| !t.is_empty())
+}
+
+/// Blank out `inline code` so links and tags inside it are ignored
+fn strip_inline_code(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find('`') {
+        let ticks = rest[start..].chars().take_while(|&c| c == '`').count();
+        let fence = &rest[start..start + ticks];
+        let after = &rest[start + ticks..];
+        match after.find(fence) {
+            Some(end) => {
+                out.push_str(&rest[..start]);
+                out.push_str(&" ".repeat(ticks * 2 + after[..end].len()));
+                rest = &after[end + ticks..];
+            }
+            None => {
+                out.push_str(&rest[..start + ticks]);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_links() {
+        let content = "---\n\
+            aliases: [Rust Lang, \"RL\"]\n\
+            tags:\n  - lang\n\
+            ---\n\
+            # Intro\n\
+            See [[Ownership#Move semantics|moves]] and ![[diagram.png]].\n\
+            Block ref [[Notes/Borrow#^abc-1]], self [[#Intro]] #rust #中文标签\n\
+            A [md link](../other%20note.md#Part) and [web](https://example.com).\n\
+            `[[not a link]]` text ^blk1\n\
+            ```\n\
+            [[inside code]] #nottag\n\
+            ```\n";
+        let note = parse_note(content);
+
+        assert_eq!(note.aliases, vec!["Rust Lang", "RL"]);
+        assert_eq!(note.tags, vec!["lang", "rust", "中文标签"]);
+        assert_eq!(note.headings, vec!["intro"]);
+        assert_eq!(note.blocks, vec!["blk1"]);
+
+        let summary: Vec<_> = note
+            .links
+            .iter()
+            .map(|l| {
+                (
+                    l.kind,
+                    l.target.as_str(),
+                    l.heading.as_deref(),
+                    l.block.as_deref(),
+                    l.display.as_deref(),
+                    l.embed,
+                    l.line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (LinkKind::Wiki, "Ownership", Some("Move semantics"), None, Some("moves"), false, 7),
+                (LinkKind::Wiki, "diagram.png", None, None, None, true, 7),
+                (LinkKind::Wiki, "Notes/Borrow", None, Some("abc-1"), None, false, 8),
+                (LinkKind::Wiki, "", Some("Intro"), None, None, false, 8),
+                (LinkKind::Markdown, "../other note.md", Some("Part"), None, Some("md link"), false, 9),
+            ]
+        );
+        assert_eq!(note.links[0].context, "See [[Ownership#Move semantics|moves]] and ![[diagram.png]].");
+    }
+}
@@ -0,0 +1,70 @@
+//! Tauri commands for the full-text index
+
+use std::path::Path;
+use tauri::State;
+
+use super::{IndexHit, IndexStats, SearchIndexState};
+use crate::error::AppError;
+use crate::fs::sandbox::VaultSandbox;
+
+/// Default number of hits returned by a query
+const DEFAULT_LIMIT: usize = 50;
+
+/// Open the vault's index and bring it up to date
+/// Afterwards the file watcher of the vault keeps it current.
+#[tauri::command]
+pub async fn open_search_index(
+    state: State<'_, SearchIndexState>,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+) -> Result<IndexStats, AppError> {
+    sandbox.check(window.label(), &vault_path)?;
+    let index = state.open(Path::new(&vault_path))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut index = index.lock().map_err(|_| AppError::Database("Lock poisoned".into()))?;
+        index.sync()
+    })
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?
+}
+
+/// Rebuild the vault's index from scratch
+#[tauri::command]
+pub async fn rebuild_search_index(
+    state: State<'_, SearchIndexState>,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+) -> Result<IndexStats, AppError> {
+    sandbox.check(window.label(), &vault_path)?;
+    let index = state.open(Path::new(&vault_path))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut index = index.lock().map_err(|_| AppError::Database("Lock poisoned".into()))?;
+        index.rebuild()
+    })
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?
+}
+
+/// Query the vault's index, best hits first
+#[tauri::command]
+pub async fn query_search_index(
+    state: State<'_, SearchIndexState>,
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<IndexHit>, AppError> {
+    sandbox.check(window.label(), &vault_path)?;
+    let index = state
+        .get(Path::new(&vault_path))
+        .ok_or_else(|| AppError::Database("Search index not opened".into()))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = index.lock().map_err(|_| AppError::Database("Lock poisoned".into()))?;
+        index.query(&query, limit.unwrap_or(DEFAULT_LIMIT))
+    })
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?
+}
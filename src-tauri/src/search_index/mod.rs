@@ -0,0 +1,571 @@
+//! Full-Text Index Module
+//!
+//! Persistent SQLite FTS5 index of the vault's notes, stored in
+//! `.lumina/search-index.db`. It is brought up to date on open (only files whose
+//! mtime or size changed are re-read) and then kept current from watcher events.
+//! CJK text is indexed per character, so Chinese words match as phrases.
+
+pub mod commands;
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::error::AppError;
+use crate::fs::vault_ignore::IGNORE_FILE;
+use crate::fs::{visit_files, FileEntry, VaultIgnore};
+
+pub use commands::*;
+
+/// Index location inside the vault (hidden from the tree)
+const INDEX_FILE: &str = ".lumina/search-index.db";
+
+/// Bump when the schema or tokenization changes; older indexes are rebuilt
+const SCHEMA_VERSION: i32 = 1;
+
+/// Extensions of indexed files
+const NOTE_EXTENSIONS: &[&str] = &["md"];
+
+/// Ranking weight of the note title relative to its body
+const TITLE_WEIGHT: f64 = 10.0;
+
+/// A ranked search hit
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexHit {
+    pub path: String,
+    /// Path relative to the vault, with `/` separators
+    pub relative_path: String,
+    /// 1-based line of the best matching line
+    pub line: usize,
+    pub snippet: String,
+    /// Relevance (higher is better)
+    pub score: f64,
+}
+
+/// Index size after a sync
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexStats {
+    pub total_files: usize,
+    /// Files (re)indexed by this sync
+    pub updated_files: usize,
+    /// Files dropped from the index by this sync
+    pub removed_files: usize,
+}
+
+/// Full-text index of one vault
+pub struct SearchIndex {
+    conn: Connection,
+    vault: PathBuf,
+    rules: VaultIgnore,
+}
+
+impl SearchIndex {
+    /// Open (or create) the index stored in the vault
+    pub fn open(vault: &Path) -> Result<Self, AppError> {
+        let db_path = vault.join(INDEX_FILE);
+        if let Some(parent) = db_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(&db_path)
+            .map_err(|e| AppError::Database(format!("Failed to open search index: {}", e)))?;
+        Self::with_connection(conn, vault)
+    }
+
+    fn with_connection(conn: Connection, vault: &Path) -> Result<Self, AppError> {
+        let version: i32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(db_error)?;
+        if version != SCHEMA_VERSION {
+            conn.execute_batch("DROP TABLE IF EXISTS notes_fts; DROP TABLE IF EXISTS files;")
+                .map_err(db_error)?;
+        }
+        conn.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS files (
+                id INTEGER PRIMARY KEY,
+                path TEXT NOT NULL UNIQUE,
+                modified INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                content TEXT NOT NULL
+            );
+            CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+                title, body, tokenize = 'unicode61 remove_diacritics 2'
+            );
+            PRAGMA user_version = {};",
+            SCHEMA_VERSION
+        ))
+        .map_err(|e| AppError::Database(format!("Failed to create search index: {}", e)))?;
+
+        Ok(Self {
+            conn,
+            vault: vault.to_path_buf(),
+            rules: VaultIgnore::load(vault),
+        })
+    }
+
+    /// Bring the whole index up to date with the vault
+    pub fn sync(&mut self) -> Result<IndexStats, AppError> {
+        self.rules = VaultIgnore::load(&self.vault);
+        let known: HashMap<String, (i64, i64)> = {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT path, modified, size FROM files")
+                .map_err(db_error)?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+                .map_err(db_error)?;
+            rows.filter_map(|r| r.ok()).collect()
+        };
+
+        let mut stats = IndexStats::default();
+        let mut seen = HashSet::new();
+        let tx = self.conn.transaction().map_err(db_error)?;
+        let mut error = None;
+        visit_files(&self.vault, &self.vault, &self.rules, &mut |entry| {
+            let Some(relative) = note_path(&self.vault, entry) else {
+                return true;
+            };
+            if known.get(&relative) != Some(&file_stamp(entry)) {
+                match index_file(&tx, &relative, entry) {
+                    Ok(()) => stats.updated_files += 1,
+                    Err(e) => {
+                        error = Some(e);
+                        return false;
+                    }
+                }
+            }
+            seen.insert(relative);
+            true
+        })?;
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        for path in known.keys().filter(|p| !seen.contains(*p)) {
+            remove_path(&tx, path)?;
+            stats.removed_files += 1;
+        }
+        tx.commit().map_err(db_error)?;
+
+        stats.total_files = seen.len();
+        Ok(stats)
+    }
+
+    /// Drop everything and index the vault from scratch
+    pub fn rebuild(&mut self) -> Result<IndexStats, AppError> {
+        self.conn
+            .execute_batch("DELETE FROM files; DELETE FROM notes_fts;")
+            .map_err(db_error)?;
+        self.sync()
+    }
+
+    /// Update the index for paths reported by the watcher
+    ///
+    /// Paths may be files or directories, created, modified or gone; a renamed
+    /// entry shows up as its old (gone) and new path.
+    pub fn update_paths(&mut self, paths: &[&Path]) -> Result<(), AppError> {
+        if paths.iter().any(|p| *p == self.vault.join(IGNORE_FILE)) {
+            self.sync()?;
+            return Ok(());
+        }
+
+        let tx = self.conn.transaction().map_err(db_error)?;
+        for path in paths {
+            let Ok(relative) = path.strip_prefix(&self.vault) else {
+                continue;
+            };
+            let meta = fs::metadata(path).ok();
+            let is_dir = meta.as_ref().is_some_and(|m| m.is_dir());
+            if relative.as_os_str().is_empty() || self.rules.is_ignored(relative, is_dir) {
+                continue;
+            }
+            let relative = relative.to_string_lossy().replace('\\', "/");
+
+            if meta.is_none() {
+                remove_path(&tx, &relative)?;
+                continue;
+            }
+
+            let mut error = None;
+            let mut update = |entry: &FileEntry| {
+                let Some(relative) = note_path(&self.vault, entry) else {
+                    return true;
+                };
+                let stamp = tx
+                    .query_row(
+                        "SELECT modified, size FROM files WHERE path = ?1",
+                        params![relative],
+                        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+                    )
+                    .optional();
+                if !matches!(stamp, Ok(Some(s)) if s == file_stamp(entry)) {
+                    if let Err(e) = index_file(&tx, &relative, entry) {
+                        error = Some(e);
+                        return false;
+                    }
+                }
+                true
+            };
+            if is_dir {
+                visit_files(&self.vault, path, &self.rules, &mut update)?;
+            } else if let Ok(entry) =
+                crate::fs::stat_entry(&path.to_string_lossy(), Some(&self.vault.to_string_lossy()))
+            {
+                update(&entry);
+            }
+            if let Some(e) = error {
+                return Err(e);
+            }
+        }
+        tx.commit().map_err(db_error)
+    }
+
+    /// Search the index
+    ///
+    /// Words must all match (in any order); `"quoted text"` matches a phrase and
+    /// a trailing `*` matches a prefix. Hits are ranked by BM25, titles first.
+    pub fn query(&self, query: &str, limit: usize) -> Result<Vec<IndexHit>, AppError> {
+        let Some(parsed) = parse_query(query) else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT f.path, f.content, bm25(notes_fts, ?2, 1.0) AS rank
+                 FROM notes_fts JOIN files f ON f.id = notes_fts.rowid
+                 WHERE notes_fts MATCH ?1
+                 ORDER BY rank LIMIT ?3",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![parsed.expression, TITLE_WEIGHT, limit as i64], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)?))
+            })
+            .map_err(|e| AppError::InvalidQuery(e.to_string()))?;
+
+        let mut hits = Vec::new();
+        for row in rows {
+            let (relative, content, rank) = row.map_err(db_error)?;
+            let (line, snippet) = locate(&content, &parsed.terms);
+            hits.push(IndexHit {
+                path: self.vault.join(&relative).to_string_lossy().to_string(),
+                relative_path: relative,
+                line,
+                snippet,
+                score: -rank,
+            });
+        }
+        Ok(hits)
+    }
+}
+
+/// Open indexes, keyed by vault path
+#[derive(Default)]
+pub struct SearchIndexState {
+    indexes: Mutex<HashMap<PathBuf, Arc<Mutex<SearchIndex>>>>,
+}
+
+impl SearchIndexState {
+    /// The vault's index, opening it on first use
+    pub fn open(&self, vault: &Path) -> Result<Arc<Mutex<SearchIndex>>, AppError> {
+        let mut indexes = self.indexes.lock().map_err(|_| AppError::Database("Lock poisoned".into()))?;
+        if let Some(index) = indexes.get(vault) {
+            return Ok(index.clone());
+        }
+        let index = Arc::new(Mutex::new(SearchIndex::open(vault)?));
+        indexes.insert(vault.to_path_buf(), index.clone());
+        Ok(index)
+    }
+
+    /// The vault's index if it has been opened
+    pub fn get(&self, vault: &Path) -> Option<Arc<Mutex<SearchIndex>>> {
+        self.indexes.lock().ok()?.get(vault).cloned()
+    }
+
+    /// Apply watcher changes to the vault's index, if it is open
+    pub fn update(&self, vault: &Path, paths: &[&Path]) {
+        let Some(index) = self.get(vault) else {
+            return;
+        };
+        let Ok(mut index) = index.lock() else {
+            return;
+        };
+        if let Err(e) = index.update_paths(paths) {
+            eprintln!("[SearchIndex] Failed to update index: {}", e);
+        }
+    }
+}
+
+fn db_error(e: rusqlite::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+/// Relative path of an indexed note, `None` for other files
+fn note_path(vault: &Path, entry: &FileEntry) -> Option<String> {
+    let path = Path::new(&entry.path);
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    if !NOTE_EXTENSIONS.contains(&extension.as_str()) {
+        return None;
+    }
+    let relative = path.strip_prefix(vault).ok()?;
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+fn file_stamp(entry: &FileEntry) -> (i64, i64) {
+    (entry.modified.unwrap_or(0) as i64, entry.size as i64)
+}
+
+fn index_file(tx: &Transaction, relative: &str, entry: &FileEntry) -> Result<(), AppError> {
+    // Unreadable or non-UTF-8 files are indexed empty so they aren't retried every sync
+    let content = fs::read_to_string(&entry.path).unwrap_or_default();
+    let (modified, size) = file_stamp(entry);
+    let id: i64 = tx
+        .query_row(
+            "INSERT INTO files (path, modified, size, content) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(path) DO UPDATE SET modified = ?2, size = ?3, content = ?4
+             RETURNING id",
+            params![relative, modified, size, content],
+            |row| row.get(0),
+        )
+        .map_err(db_error)?;
+    let title = Path::new(relative)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    tx.execute("DELETE FROM notes_fts WHERE rowid = ?1", params![id])
+        .map_err(db_error)?;
+    tx.execute(
+        "INSERT INTO notes_fts (rowid, title, body) VALUES (?1, ?2, ?3)",
+        params![id, segment(&title), segment(&content)],
+    )
+    .map_err(db_error)?;
+    Ok(())
+}
+
+/// Remove a note, or every note below a directory
+fn remove_path(tx: &Transaction, relative: &str) -> Result<(), AppError> {
+    let files = "SELECT id FROM files WHERE path = ?1 OR substr(path, 1, length(?2)) = ?2";
+    let dir = format!("{}/", relative);
+    tx.execute(
+        &format!("DELETE FROM notes_fts WHERE rowid IN ({})", files),
+        params![relative, dir],
+    )
+    .map_err(db_error)?;
+    tx.execute(&format!("DELETE FROM files WHERE id IN ({})", files), params![relative, dir])
+        .map_err(db_error)?;
+    Ok(())
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'     // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}'   // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}'   // Hangul syllables
+        | '\u{F900}'..='\u{FAFF}'   // CJK Compatibility Ideographs
+        | '\u{20000}'..='\u{2FA1F}' // CJK Extensions B+
+    )
+}
+
+/// Split CJK text into one token per character
+///
+/// The unicode61 tokenizer would keep a whole run of Han characters as a single
+/// token; per-character tokens plus phrase queries match any word inside it.
+fn segment(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 2);
+    for c in text.chars() {
+        if is_cjk(c) {
+            out.push(' ');
+            out.push(c);
+            out.push(' ');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// A user query translated to FTS5 syntax
+#[derive(Debug, PartialEq)]
+struct ParsedQuery {
+    expression: String,
+    /// Lowercased terms, for finding the matching line
+    terms: Vec<String>,
+}
+
+/// Parse words, `"phrases"` and `prefix*` terms; FTS5 operators in the input are
+/// treated as text
+fn parse_query(input: &str) -> Option<ParsedQuery> {
+    let mut raw_terms = Vec::new();
+    let mut rest = input.trim();
+    while !rest.is_empty() {
+        let (term, remainder) = if let Some(quoted) = rest.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            }
+        } else {
+            match rest.find(char::is_whitespace) {
+                Some(end) => (&rest[..end], &rest[end..]),
+                None => (rest, ""),
+            }
+        };
+        raw_terms.push(term);
+        rest = remainder.trim_start();
+    }
+
+    let mut parts = Vec::new();
+    let mut terms = Vec::new();
+    for term in raw_terms {
+        let (text, prefix) = match term.strip_suffix('*') {
+            Some(stem) => (stem, true),
+            None => (term, false),
+        };
+        if !text.chars().any(char::is_alphanumeric) {
+            continue;
+        }
+        let phrase = format!("\"{}\"", segment(text).replace('"', "\"\""));
+        parts.push(if prefix { format!("{} *", phrase) } else { phrase });
+        terms.push(text.to_lowercase());
+    }
+
+    if parts.is_empty() {
+        return None;
+    }
+    Some(ParsedQuery {
+        expression: parts.join(" AND "),
+        terms,
+    })
+}
+
+/// Best matching line (most terms) and a snippet around the first term in it
+fn locate(content: &str, terms: &[String]) -> (usize, String) {
+    let mut best: Option<(usize, usize, &str)> = None;
+    for (index, line) in content.lines().enumerate() {
+        let lower = line.to_lowercase();
+        let count = terms.iter().filter(|t| lower.contains(t.as_str())).count();
+        if count > 0 && best.is_none_or(|(c, _, _)| count > c) {
+            best = Some((count, index, line));
+            if count == terms.len() {
+                break;
+            }
+        }
+    }
+    let (line, text) = match best {
+        Some((_, index, line)) => (index + 1, line),
+        None => (1, content.lines().next().unwrap_or("")),
+    };
+    (line, snippet(text, terms))
+}
+
+/// Characters of context kept before and after a match
+const SNIPPET_BEFORE: usize = 30;
+const SNIPPET_AFTER: usize = 90;
+
+fn snippet(line: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = line.trim().chars().collect();
+    let lower: String = chars.iter().collect::<String>().to_lowercase();
+    let position = terms
+        .iter()
+        .filter_map(|t| lower.find(t.as_str()))
+        .min()
+        .map(|byte| lower[..byte].chars().count())
+        .unwrap_or(0);
+
+    let start = position.saturating_sub(SNIPPET_BEFORE).min(chars.len());
+    let end = (position + SNIPPET_AFTER).min(chars.len());
+    let mut text: String = chars[start..end].iter().collect();
+    if start > 0 {
+        text.insert(0, '…');
+    }
+    if end < chars.len() {
+        text.push('…');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(hits: &[IndexHit]) -> Vec<&str> {
+        hits.iter().map(|h| h.relative_path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let parsed = parse_query(r#"rust "full text" 搜索 idx* -- "#).unwrap();
+        assert_eq!(
+            parsed.expression,
+            r#""rust" AND "full text" AND " 搜  索 " AND "idx" *"#
+        );
+        assert_eq!(parsed.terms, vec!["rust", "full text", "搜索", "idx"]);
+        assert!(parse_query("  \"\" ** ").is_none());
+    }
+
+    #[test]
+    fn test_index_and_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("notes")).unwrap();
+        fs::write(vault.join("notes/rust.md"), "# Rust\n\nOwnership and borrowing.\n").unwrap();
+        fs::write(vault.join("notes/中文.md"), "标题\n\n今天学习全文搜索的实现。\n").unwrap();
+        fs::write(vault.join("ownership.md"), "A note about ownership rules.").unwrap();
+        fs::write(vault.join("image.png"), "ownership").unwrap();
+        fs::write(vault.join(".hidden.md"), "ownership").unwrap();
+
+        let mut index = SearchIndex::open(vault).unwrap();
+        let stats = index.sync().unwrap();
+        assert_eq!(stats.total_files, 3);
+        assert_eq!(stats.updated_files, 3);
+
+        // Title matches rank first; line and snippet point at the match
+        let hits = index.query("ownership", 10).unwrap();
+        assert_eq!(paths(&hits), vec!["ownership.md", "notes/rust.md"]);
+        assert_eq!(hits[1].line, 3);
+        assert_eq!(hits[1].snippet, "Ownership and borrowing.");
+
+        // CJK words, phrases and prefixes
+        let hits = index.query("搜索", 10).unwrap();
+        assert_eq!(paths(&hits), vec!["notes/中文.md"]);
+        assert_eq!(hits[0].line, 3);
+        assert!(index.query("索搜", 10).unwrap().is_empty());
+        assert_eq!(index.query("\"and borrowing\"", 10).unwrap().len(), 1);
+        assert!(index.query("\"borrowing and\"", 10).unwrap().is_empty());
+        assert_eq!(index.query("borrow*", 10).unwrap().len(), 1);
+        assert!(index.query("borrow", 10).unwrap().is_empty());
+
+        // Nothing changed: nothing is re-read
+        assert_eq!(index.sync().unwrap().updated_files, 0);
+
+        // Incremental updates: edit, rename, delete, new directory
+        fs::write(vault.join("ownership.md"), "Now about lifetimes.").unwrap();
+        fs::rename(vault.join("notes"), vault.join("moved")).unwrap();
+        fs::create_dir_all(vault.join("new")).unwrap();
+        fs::write(vault.join("new/life.md"), "lifetimes too").unwrap();
+        index
+            .update_paths(&[
+                &vault.join("ownership.md"),
+                &vault.join("notes"),
+                &vault.join("moved"),
+                &vault.join("new"),
+            ])
+            .unwrap();
+        assert_eq!(paths(&index.query("borrowing", 10).unwrap()), vec!["moved/rust.md"]);
+        assert!(index.query("rules", 10).unwrap().is_empty());
+        assert_eq!(index.query("lifetimes", 10).unwrap().len(), 2);
+
+        fs::remove_file(vault.join("new/life.md")).unwrap();
+        index.update_paths(&[&vault.join("new/life.md")]).unwrap();
+        assert_eq!(index.query("lifetimes", 10).unwrap().len(), 1);
+
+        // The index persists across reopening
+        drop(index);
+        let mut index = SearchIndex::open(vault).unwrap();
+        assert_eq!(index.query("搜索", 10).unwrap().len(), 1);
+        let stats = index.sync().unwrap();
+        assert_eq!((stats.total_files, stats.updated_files, stats.removed_files), (3, 0, 0));
+    }
+}
@@ -0,0 +1,371 @@
+//! WebDAV 认证
+//!
+//! 解析 `WWW-Authenticate` 质询，生成 Basic / Digest (RFC 7616) / Bearer 认证头。
+//! Digest 质询在客户端内缓存，后续请求直接带上认证头，只有 nonce 过期时才重新质询
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use md5::Md5;
+use sha2::{Digest as _, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::types::{AuthMethod, WebDAVConfig};
+
+/// 一条认证质询
+#[derive(Debug, Clone, PartialEq)]
+pub struct Challenge {
+    /// 认证方案 (小写，如 "basic"、"digest")
+    pub scheme: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl Challenge {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// 解析 WWW-Authenticate 头 (一个头中可包含多条质询)
+pub fn parse_challenges(value: &str) -> Vec<Challenge> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut challenges: Vec<Challenge> = Vec::new();
+    let mut i = 0;
+
+    let skip_separators = |i: &mut usize| {
+        while *i < chars.len() && (chars[*i].is_whitespace() || chars[*i] == ',') {
+            *i += 1;
+        }
+    };
+    let read_token = |i: &mut usize| {
+        let start = *i;
+        while *i < chars.len() && !chars[*i].is_whitespace() && !matches!(chars[*i], ',' | '=') {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect::<String>()
+    };
+
+    loop {
+        skip_separators(&mut i);
+        if i >= chars.len() {
+            break;
+        }
+        let token = read_token(&mut i);
+
+        // token 后紧跟 '=' 为参数，否则为新的认证方案
+        let mut j = i;
+        while j < chars.len() && chars[j].is_whitespace() {
+            j += 1;
+        }
+        let is_param = j < chars.len() && chars[j] == '=' && !challenges.is_empty();
+        if !is_param {
+            challenges.push(Challenge {
+                scheme: token.to_ascii_lowercase(),
+                params: Vec::new(),
+            });
+            continue;
+        }
+
+        i = j + 1;
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        let value = if i < chars.len() && chars[i] == '"' {
+            i += 1;
+            let mut value = String::new();
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    i += 1;
+                }
+                value.push(chars[i]);
+                i += 1;
+            }
+            i += 1;
+            value
+        } else {
+            // token68 (如 "Negotiate abc==") 末尾的 '=' 也归入值
+            let mut value = read_token(&mut i);
+            while i < chars.len() && chars[i] == '=' {
+                value.push('=');
+                i += 1;
+            }
+            value
+        };
+        if let Some(challenge) = challenges.last_mut() {
+            challenge.params.push((token, value));
+        }
+    }
+
+    challenges
+}
+
+/// 缓存的 Digest 质询
+struct DigestState {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: String,
+    /// 服务器支持 qop=auth
+    qop_auth: bool,
+    /// nonce 使用次数
+    nc: u32,
+}
+
+/// 按配置生成认证头
+pub struct Authenticator {
+    method: AuthMethod,
+    username: String,
+    password: String,
+    digest: Mutex<Option<DigestState>>,
+    cnonce_counter: AtomicU64,
+}
+
+impl Authenticator {
+    pub fn new(config: &WebDAVConfig) -> Self {
+        Self {
+            method: config.auth,
+            username: config.username.clone(),
+            password: config.password.clone(),
+            digest: Mutex::new(None),
+            cnonce_counter: AtomicU64::new(0),
+        }
+    }
+
+    /// 请求的 Authorization 头，uri 为请求目标 (路径和查询)
+    ///
+    /// Digest 模式下尚未收到质询时返回 None，由服务器的 401 质询触发认证
+    pub fn authorization(&self, method: &str, uri: &str) -> Option<String> {
+        if self.method == AuthMethod::Bearer {
+            return Some(format!("Bearer {}", self.password));
+        }
+        if matches!(self.method, AuthMethod::Auto | AuthMethod::Digest) {
+            let mut digest = self.digest.lock().ok()?;
+            if let Some(state) = digest.as_mut() {
+                let cnonce = self.cnonce(&state.nonce);
+                return Some(digest_header(&self.username, &self.password, state, method, uri, &cnonce));
+            }
+        }
+        (self.method != AuthMethod::Digest).then(|| self.basic_header())
+    }
+
+    /// 处理 401 响应的质询，返回是否应使用新的凭据重试
+    pub fn accept_challenges(&self, challenges: &[Challenge]) -> bool {
+        if !matches!(self.method, AuthMethod::Auto | AuthMethod::Digest) {
+            return false;
+        }
+        // 优先 SHA-256，其次 MD5
+        let Some(challenge) = ["SHA-256", "MD5", "SHA-256-sess", "MD5-sess"]
+            .iter()
+            .find_map(|algorithm| {
+                challenges.iter().find(|c| {
+                    c.scheme == "digest"
+                        && c.param("algorithm").unwrap_or("MD5").eq_ignore_ascii_case(algorithm)
+                })
+            })
+        else {
+            return false;
+        };
+        let Some(nonce) = challenge.param("nonce") else {
+            return false;
+        };
+
+        let Ok(mut digest) = self.digest.lock() else {
+            return false;
+        };
+        // 同一 nonce 再次被拒且未标记 stale，说明凭据错误，不再重试
+        let stale = challenge.param("stale").is_some_and(|s| s.eq_ignore_ascii_case("true"));
+        if digest.as_ref().is_some_and(|d| d.nonce == nonce) && !stale {
+            return false;
+        }
+
+        *digest = Some(DigestState {
+            realm: challenge.param("realm").unwrap_or_default().to_string(),
+            nonce: nonce.to_string(),
+            opaque: challenge.param("opaque").map(str::to_string),
+            algorithm: challenge.param("algorithm").unwrap_or("MD5").to_string(),
+            qop_auth: challenge
+                .param("qop")
+                .is_some_and(|q| q.split(',').any(|q| q.trim().eq_ignore_ascii_case("auth"))),
+            nc: 0,
+        });
+        true
+    }
+
+    /// 认证失败时的说明
+    pub fn failure_reason(&self, challenges: &[Challenge]) -> String {
+        let offered: Vec<&str> = challenges.iter().map(|c| c.scheme.as_str()).collect();
+        let supported = match self.method {
+            AuthMethod::Auto => offered.iter().any(|s| *s == "basic" || *s == "digest"),
+            AuthMethod::Basic => offered.contains(&"basic"),
+            AuthMethod::Digest => offered.contains(&"digest"),
+            AuthMethod::Bearer => offered.contains(&"bearer"),
+        };
+
+        if offered.is_empty() || supported {
+            match self.method {
+                AuthMethod::Bearer => "token rejected".to_string(),
+                _ => "check username and password".to_string(),
+            }
+        } else {
+            format!("server requires {} authentication", offered.join(" or "))
+        }
+    }
+
+    fn basic_header(&self) -> String {
+        let credentials = format!("{}:{}", self.username, self.password);
+        format!("Basic {}", STANDARD.encode(credentials.as_bytes()))
+    }
+
+    /// 客户端随机数 (时间、计数器与 nonce 的哈希)
+    fn cnonce(&self, nonce: &str) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let counter = self.cnonce_counter.fetch_add(1, Ordering::Relaxed);
+        hex(&Sha256::digest(format!("{}:{}:{}", now, counter, nonce).as_bytes()))[..16].to_string()
+    }
+}
+
+/// 计算 Digest 认证头
+fn digest_header(
+    username: &str,
+    password: &str,
+    state: &mut DigestState,
+    method: &str,
+    uri: &str,
+    cnonce: &str,
+) -> String {
+    let algorithm = state.algorithm.to_ascii_uppercase();
+    let hash = |data: String| -> String {
+        if algorithm.starts_with("SHA-256") {
+            hex(&Sha256::digest(data.as_bytes()))
+        } else {
+            hex(&Md5::digest(data.as_bytes()))
+        }
+    };
+
+    state.nc += 1;
+    let nc = format!("{:08x}", state.nc);
+
+    let mut ha1 = hash(format!("{}:{}:{}", username, state.realm, password));
+    if algorithm.ends_with("-SESS") {
+        ha1 = hash(format!("{}:{}:{}", ha1, state.nonce, cnonce));
+    }
+    let ha2 = hash(format!("{}:{}", method, uri));
+    let response = if state.qop_auth {
+        hash(format!("{}:{}:{}:{}:auth:{}", ha1, state.nonce, nc, cnonce, ha2))
+    } else {
+        hash(format!("{}:{}:{}", ha1, state.nonce, ha2))
+    };
+
+    let mut header = format!(
+        r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", algorithm={}, response="{}""#,
+        quote(username),
+        quote(&state.realm),
+        quote(&state.nonce),
+        quote(uri),
+        state.algorithm,
+        response
+    );
+    if state.qop_auth {
+        header.push_str(&format!(r#", qop=auth, nc={}, cnonce="{}""#, nc, cnonce));
+    }
+    if let Some(opaque) = &state.opaque {
+        header.push_str(&format!(r#", opaque="{}""#, quote(opaque)));
+    }
+    header
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 转义引号字符串中的 `"` 和 `\`
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authenticator(method: AuthMethod, username: &str, password: &str) -> Authenticator {
+        Authenticator::new(&WebDAVConfig {
+            username: username.to_string(),
+            password: password.to_string(),
+            auth: method,
+            ..WebDAVConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_parse_multiple_challenges() {
+        let challenges = parse_challenges(
+            r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=SHA-256, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS", Basic realm="files", Negotiate abc=="#,
+        );
+        let schemes: Vec<&str> = challenges.iter().map(|c| c.scheme.as_str()).collect();
+        assert_eq!(schemes, vec!["digest", "basic", "negotiate"]);
+        assert_eq!(challenges[0].param("qop"), Some("auth, auth-int"));
+        assert_eq!(challenges[0].param("algorithm"), Some("SHA-256"));
+        assert_eq!(challenges[1].param("realm"), Some("files"));
+    }
+
+    /// RFC 7616 3.9.1 的 SHA-256 和 MD5 示例
+    #[test]
+    fn test_digest_matches_rfc_examples() {
+        for (algorithm, expected) in [
+            ("SHA-256", "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"),
+            ("MD5", "8ca523f5e9506fed4657c9700eebdbec"),
+        ] {
+            let auth = authenticator(AuthMethod::Digest, "Mufasa", "Circle of Life");
+            assert_eq!(auth.authorization("GET", "/dir/index.html"), None);
+
+            let challenge = format!(
+                r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm={}, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#,
+                algorithm
+            );
+            assert!(auth.accept_challenges(&parse_challenges(&challenge)));
+
+            // 使用 RFC 中固定的 cnonce
+            let mut digest = auth.digest.lock().unwrap();
+            let header = digest_header(
+                "Mufasa",
+                "Circle of Life",
+                digest.as_mut().unwrap(),
+                "GET",
+                "/dir/index.html",
+                "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ",
+            );
+            assert!(header.contains(&format!(r#"response="{}""#, expected)), "{}", header);
+            assert!(header.contains("nc=00000001"));
+            assert!(header.contains(r#"opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#));
+        }
+    }
+
+    #[test]
+    fn test_retry_rules() {
+        let challenge = parse_challenges(r#"Digest realm="r", nonce="n1", qop="auth""#);
+
+        // Basic 和 Bearer 不响应 Digest 质询
+        let basic = authenticator(AuthMethod::Basic, "u", "p");
+        assert!(basic.authorization("GET", "/").unwrap().starts_with("Basic "));
+        assert!(!basic.accept_challenges(&challenge));
+        let bearer = authenticator(AuthMethod::Bearer, "", "token");
+        assert_eq!(bearer.authorization("GET", "/").as_deref(), Some("Bearer token"));
+        assert_eq!(bearer.failure_reason(&challenge), "server requires digest authentication");
+
+        // 自动模式先发 Basic，收到质询后切换到 Digest；同一 nonce 再次被拒时不重试
+        let auto = authenticator(AuthMethod::Auto, "u", "p");
+        assert!(auto.authorization("GET", "/").unwrap().starts_with("Basic "));
+        assert!(auto.accept_challenges(&challenge));
+        assert!(auto.authorization("GET", "/").unwrap().starts_with("Digest "));
+        assert!(!auto.accept_challenges(&challenge));
+        let stale = parse_challenges(r#"Digest realm="r", nonce="n1", qop="auth", stale=true"#);
+        assert!(auto.accept_challenges(&stale));
+        assert_eq!(auto.failure_reason(&challenge), "check username and password");
+    }
+}
@@ -2,11 +2,14 @@
 //! 
 //! 封装 WebDAV 协议的 HTTP 请求，提供高层 API
 
-use reqwest::{Client, Method, StatusCode};
-use reqwest::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
-use base64::{Engine as _, engine::general_purpose::STANDARD};
+use async_trait::async_trait;
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, ETAG, WWW_AUTHENTICATE};
 use std::time::Duration;
 
+use super::auth::{self, Authenticator};
+use super::propfind::{self, DavProps};
+use super::storage::RemoteStorage;
 use super::types::{WebDAVConfig, RemoteEntry};
 use crate::error::AppError;
 
@@ -14,6 +17,7 @@ use crate::error::AppError;
 pub struct WebDAVClient {
     client: Client,
     config: WebDAVConfig,
+    auth: Authenticator,
 }
 
 impl WebDAVClient {
@@ -25,14 +29,58 @@ impl WebDAVClient {
             .build()
             .map_err(|e| AppError::WebDAV(format!("Failed to create HTTP client: {}", e)))?;
         
-        Ok(Self { client, config })
+        let auth = Authenticator::new(&config);
+        Ok(Self { client, config, auth })
     }
 
-    /// 构建认证头
-    fn auth_header(&self) -> HeaderValue {
-        let credentials = format!("{}:{}", self.config.username, self.config.password);
-        let encoded = STANDARD.encode(credentials.as_bytes());
-        HeaderValue::from_str(&format!("Basic {}", encoded)).unwrap()
+    /// 发送带认证的请求
+    ///
+    /// 收到 401 质询 (如服务器要求 Digest 或 nonce 过期) 时按质询重试一次，
+    /// `build` 会被再次调用以重建请求体
+    async fn send(
+        &self,
+        action: &str,
+        method: Method,
+        url: &str,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<Response, AppError> {
+        // Digest 的 uri 为编码后的请求目标，与 reqwest 实际发送的一致
+        let uri = reqwest::Url::parse(url)
+            .map(|u| match u.query() {
+                Some(query) => format!("{}?{}", u.path(), query),
+                None => u.path().to_string(),
+            })
+            .map_err(|e| AppError::WebDAV(format!("Invalid URL {}: {}", url, e)))?;
+
+        let mut retried = false;
+        loop {
+            let mut request = build(self.client.request(method.clone(), url));
+            if let Some(authorization) = self.auth.authorization(method.as_str(), &uri) {
+                request = request.header(AUTHORIZATION, authorization);
+            }
+            let response = request
+                .send()
+                .await
+                .map_err(|e| AppError::WebDAV(format!("{} failed: {}", action, e)))?;
+
+            if response.status() == StatusCode::UNAUTHORIZED && !retried {
+                retried = true;
+                if self.auth.accept_challenges(&challenges(&response)) {
+                    continue;
+                }
+            }
+            return Ok(response);
+        }
+    }
+
+    /// 将失败响应转换为错误：认证失败、无权限、不存在分别对应不同的错误类型
+    fn status_error(&self, action: &str, path: &str, response: &Response) -> AppError {
+        match response.status() {
+            StatusCode::UNAUTHORIZED => AppError::WebDAVAuth(self.auth.failure_reason(&challenges(response))),
+            StatusCode::FORBIDDEN => AppError::WebDAVForbidden(format!("{} {}", action, path)),
+            StatusCode::NOT_FOUND => AppError::WebDAVNotFound(path.to_string()),
+            status => AppError::WebDAV(format!("{} failed with status: {}", action, status)),
+        }
     }
 
     /// 构建完整 URL
@@ -52,52 +100,22 @@ impl WebDAVClient {
     pub async fn test_connection(&self) -> Result<bool, AppError> {
         let url = self.build_url("");
         
-        let response = self.client
-            .request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
-            .header(AUTHORIZATION, self.auth_header())
-            .header("Depth", "0")
-            .send()
-            .await
-            .map_err(|e| AppError::WebDAV(format!("Connection failed: {}", e)))?;
+        let response = self
+            .send("Connection", propfind_method(), &url, |r| r.header("Depth", "0"))
+            .await?;
 
         match response.status() {
             StatusCode::OK | StatusCode::MULTI_STATUS => Ok(true),
-            StatusCode::UNAUTHORIZED => Err(AppError::WebDAV("Authentication failed".to_string())),
-            status => Err(AppError::WebDAV(format!("Unexpected status: {}", status))),
+            _ => Err(self.status_error("Connection", "", &response)),
         }
     }
 
-    /// 列出目录内容 (PROPFIND)
+    /// 列出目录内容 (PROPFIND, Depth: 1)
     pub async fn list_dir(&self, path: &str) -> Result<Vec<RemoteEntry>, AppError> {
-        let url = self.build_url(path);
-        
-        // PROPFIND 请求体
-        let body = r#"<?xml version="1.0" encoding="utf-8"?>
-<D:propfind xmlns:D="DAV:">
-  <D:prop>
-    <D:resourcetype/>
-    <D:getcontentlength/>
-    <D:getlastmodified/>
-    <D:getetag/>
-    <D:getcontenttype/>
-  </D:prop>
-</D:propfind>"#;
-
-        let response = self.client
-            .request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
-            .header(AUTHORIZATION, self.auth_header())
-            .header(CONTENT_TYPE, "application/xml")
-            .header("Depth", "1")
-            .body(body)
-            .send()
-            .await
-            .map_err(|e| AppError::WebDAV(format!("PROPFIND failed: {}", e)))?;
+        let response = self.propfind(path, "1").await?;
 
         if response.status() != StatusCode::MULTI_STATUS {
-            return Err(AppError::WebDAV(format!(
-                "PROPFIND failed with status: {}",
-                response.status()
-            )));
+            return Err(self.status_error("PROPFIND", path, &response));
         }
 
         let body = response.text().await
@@ -107,7 +125,14 @@ impl WebDAVClient {
     }
 
     /// 递归列出所有文件
+    ///
+    /// 优先尝试 `Depth: infinity` 一次性获取，服务器拒绝时 (RFC 4918 允许禁用)
+    /// 回退为逐层 `Depth: 1` 遍历
     pub async fn list_all_recursive(&self, path: &str) -> Result<Vec<RemoteEntry>, AppError> {
+        if let Some(entries) = self.list_infinity(path).await? {
+            return Ok(entries);
+        }
+
         let mut all_entries = Vec::new();
         let mut dirs_to_scan = vec![path.to_string()];
 
@@ -125,129 +150,140 @@ impl WebDAVClient {
         Ok(all_entries)
     }
 
+    /// 尝试 `Depth: infinity` 列表，服务器不支持时返回 None
+    async fn list_infinity(&self, path: &str) -> Result<Option<Vec<RemoteEntry>>, AppError> {
+        let response = self.propfind(path, "infinity").await?;
+
+        match response.status() {
+            StatusCode::MULTI_STATUS => {}
+            // 403 (propfind-finite-depth)、400、501 等均视为不支持
+            StatusCode::UNAUTHORIZED => return Err(self.status_error("PROPFIND", path, &response)),
+            _ => return Ok(None),
+        }
+
+        let body = response.text().await
+            .map_err(|e| AppError::WebDAV(format!("Failed to read response: {}", e)))?;
+
+        self.parse_propfind_response(&body, path).map(Some)
+    }
+
+    /// 发送 PROPFIND 请求
+    async fn propfind(&self, path: &str, depth: &str) -> Result<reqwest::Response, AppError> {
+        let url = self.build_url(path);
+        
+        // PROPFIND 请求体
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:propfind xmlns:D="DAV:">
+  <D:prop>
+    <D:resourcetype/>
+    <D:getcontentlength/>
+    <D:getlastmodified/>
+    <D:getetag/>
+    <D:getcontenttype/>
+  </D:prop>
+</D:propfind>"#;
+
+        self.send("PROPFIND", propfind_method(), &url, |r| {
+            r.header(CONTENT_TYPE, "application/xml")
+                .header("Depth", depth)
+                .body(body)
+        })
+        .await
+    }
+
     /// 解析 PROPFIND 响应
     fn parse_propfind_response(&self, xml: &str, base_path: &str) -> Result<Vec<RemoteEntry>, AppError> {
-        let mut entries = Vec::new();
-        
-        // 简单的 XML 解析 (生产环境建议使用 quick-xml)
-        // 这里使用字符串解析来避免额外依赖
-        
         let base_path_normalized = base_path.trim_matches('/');
-        
-        // 按 <D:response> 分割
-        for response_block in xml.split("<D:response>").skip(1) {
-            let href = self.extract_xml_value(response_block, "D:href")
-                .or_else(|| self.extract_xml_value(response_block, "d:href"));
-            
-            if let Some(href) = href {
-                // URL 解码
-                let decoded_href = urlencoding_decode(&href);
-                let path = self.extract_relative_path(&decoded_href);
-                
-                // 跳过根目录本身
-                if path.trim_matches('/') == base_path_normalized {
-                    continue;
-                }
-                
-                let is_dir = response_block.contains("<D:collection")
-                    || response_block.contains("<d:collection")
-                    || response_block.contains("resourcetype><D:collection")
-                    || response_block.contains("resourcetype><d:collection");
-                
-                let size = self.extract_xml_value(response_block, "D:getcontentlength")
-                    .or_else(|| self.extract_xml_value(response_block, "d:getcontentlength"))
-                    .and_then(|s| s.parse::<u64>().ok())
-                    .unwrap_or(0);
-                
-                let modified = self.extract_xml_value(response_block, "D:getlastmodified")
-                    .or_else(|| self.extract_xml_value(response_block, "d:getlastmodified"))
-                    .and_then(|s| parse_http_date(&s))
-                    .unwrap_or(0);
-                
-                let etag = self.extract_xml_value(response_block, "D:getetag")
-                    .or_else(|| self.extract_xml_value(response_block, "d:getetag"));
-                
-                let content_type = self.extract_xml_value(response_block, "D:getcontenttype")
-                    .or_else(|| self.extract_xml_value(response_block, "d:getcontenttype"));
-                
-                let name = path.split('/').last().unwrap_or("").to_string();
-                
-                if !name.is_empty() {
-                    entries.push(RemoteEntry {
-                        path,
-                        name,
-                        is_dir,
-                        size,
-                        modified,
-                        etag,
-                        content_type,
-                    });
-                }
+        let mut entries = Vec::new();
+
+        for response in propfind::parse_multistatus(xml)? {
+            if !response.is_ok() || response.href.is_empty() {
+                continue;
+            }
+
+            let decoded_href = urlencoding_decode(&response.href);
+            let path = self.extract_relative_path(&decoded_href);
+
+            // 跳过根目录本身
+            if path.trim_matches('/') == base_path_normalized {
+                continue;
+            }
+
+            if let Some(entry) = to_remote_entry(&path, response.props) {
+                entries.push(entry);
             }
         }
 
         Ok(entries)
     }
 
-    /// 从 XML 中提取标签值
-    fn extract_xml_value(&self, xml: &str, tag: &str) -> Option<String> {
-        let open_tag = format!("<{}>", tag);
-        let close_tag = format!("</{}>", tag);
-        
-        if let Some(start) = xml.find(&open_tag) {
-            let value_start = start + open_tag.len();
-            if let Some(end) = xml[value_start..].find(&close_tag) {
-                return Some(xml[value_start..value_start + end].trim().to_string());
-            }
+    /// 获取单个文件/目录的属性 (PROPFIND, Depth: 0)，不存在时返回 None
+    pub async fn stat(&self, path: &str) -> Result<Option<RemoteEntry>, AppError> {
+        let response = self.propfind(path, "0").await?;
+
+        match response.status() {
+            StatusCode::MULTI_STATUS => {}
+            StatusCode::NOT_FOUND => return Ok(None),
+            _ => return Err(self.status_error("PROPFIND", path, &response)),
         }
-        None
+
+        let body = response.text().await
+            .map_err(|e| AppError::WebDAV(format!("Failed to read response: {}", e)))?;
+
+        Ok(propfind::parse_multistatus(&body)?
+            .into_iter()
+            .find(|r| r.is_ok())
+            .and_then(|r| to_remote_entry(path.trim_matches('/'), r.props)))
+    }
+
+    /// 服务器上远程根目录对应的路径 (server_url 路径部分 + remote_base_path)
+    fn base_href_path(&self) -> String {
+        let server_path = url_path(&self.config.server_url);
+        let server_path = urlencoding_decode(server_path);
+        let remote_base = self.config.remote_base_path.trim_matches('/');
+
+        [server_path.trim_matches('/'), remote_base]
+            .iter()
+            .filter(|p| !p.is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("/")
     }
 
-    /// 从完整 URL 提取相对路径
+    /// 从 href 提取相对于远程根目录的路径
     fn extract_relative_path(&self, href: &str) -> String {
-        // 移除服务器 URL 部分
-        let path = if href.starts_with("http://") || href.starts_with("https://") {
-            // 完整 URL，提取路径部分
-            if let Some(pos) = href.find("://") {
-                if let Some(path_start) = href[pos + 3..].find('/') {
-                    &href[pos + 3 + path_start..]
-                } else {
-                    "/"
+        let path = url_path(href).trim_start_matches('/');
+
+        // 优先剥离完整前缀 (如 Nextcloud 的 /remote.php/dav/files/<user>/...)，
+        // 部分服务器返回的 href 不含 server_url 路径，此时仅剥离 remote_base_path
+        let base = self.base_href_path();
+        let remote_base = self.config.remote_base_path.trim_matches('/').to_string();
+
+        for prefix in [base, remote_base] {
+            if prefix.is_empty() {
+                continue;
+            }
+            if path == prefix {
+                return String::new();
+            }
+            if let Some(rest) = path.strip_prefix(&prefix) {
+                if rest.starts_with('/') {
+                    return rest.trim_start_matches('/').to_string();
                 }
-            } else {
-                href
             }
-        } else {
-            href
-        };
-
-        // 移除 remote_base_path 前缀
-        let base = self.config.remote_base_path.trim_matches('/');
-        let path = path.trim_start_matches('/');
-        
-        if !base.is_empty() && path.starts_with(base) {
-            path[base.len()..].trim_start_matches('/').to_string()
-        } else {
-            path.to_string()
         }
+
+        path.to_string()
     }
 
     /// 下载文件 (GET)
     pub async fn download(&self, path: &str) -> Result<Vec<u8>, AppError> {
         let url = self.build_url(path);
         
-        let response = self.client
-            .get(&url)
-            .header(AUTHORIZATION, self.auth_header())
-            .send()
-            .await
-            .map_err(|e| AppError::WebDAV(format!("Download failed: {}", e)))?;
+        let response = self.send("Download", Method::GET, &url, |r| r).await?;
 
         if !response.status().is_success() {
-            return Err(AppError::WebDAV(format!(
-                "Download failed with status: {}",
-                response.status()
-            )));
+            return Err(self.status_error("Download", path, &response));
         }
 
         response.bytes().await
@@ -262,46 +298,43 @@ impl WebDAVClient {
             .map_err(|e| AppError::WebDAV(format!("Invalid UTF-8: {}", e)))
     }
 
-    /// 上传文件 (PUT)
-    pub async fn upload(&self, path: &str, content: &[u8]) -> Result<(), AppError> {
+    /// 上传文件 (PUT)，返回服务器给出的新 ETag (如果有)
+    pub async fn upload(&self, path: &str, content: &[u8]) -> Result<Option<String>, AppError> {
         let url = self.build_url(path);
         
-        let response = self.client
-            .put(&url)
-            .header(AUTHORIZATION, self.auth_header())
-            .body(content.to_vec())
-            .send()
-            .await
-            .map_err(|e| AppError::WebDAV(format!("Upload failed: {}", e)))?;
+        let response = self
+            .send("Upload", Method::PUT, &url, |r| r.body(content.to_vec()))
+            .await?;
 
         match response.status() {
-            StatusCode::OK | StatusCode::CREATED | StatusCode::NO_CONTENT => Ok(()),
-            status => Err(AppError::WebDAV(format!("Upload failed with status: {}", status))),
+            StatusCode::OK | StatusCode::CREATED | StatusCode::NO_CONTENT => Ok(response
+                .headers()
+                .get(ETAG)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())),
+            _ => Err(self.status_error("Upload", path, &response)),
         }
     }
 
     /// 上传文本文件
     pub async fn upload_text(&self, path: &str, content: &str) -> Result<(), AppError> {
-        self.upload(path, content.as_bytes()).await
+        self.upload(path, content.as_bytes()).await.map(|_| ())
     }
 
     /// 创建目录 (MKCOL)
     pub async fn create_dir(&self, path: &str) -> Result<(), AppError> {
         let url = self.build_url(path);
         
-        let response = self.client
-            .request(Method::from_bytes(b"MKCOL").unwrap(), &url)
-            .header(AUTHORIZATION, self.auth_header())
-            .send()
-            .await
-            .map_err(|e| AppError::WebDAV(format!("MKCOL failed: {}", e)))?;
+        let response = self
+            .send("MKCOL", Method::from_bytes(b"MKCOL").unwrap(), &url, |r| r)
+            .await?;
 
         match response.status() {
             StatusCode::CREATED | StatusCode::OK | StatusCode::METHOD_NOT_ALLOWED => {
                 // METHOD_NOT_ALLOWED 通常表示目录已存在
                 Ok(())
             }
-            status => Err(AppError::WebDAV(format!("MKCOL failed with status: {}", status))),
+            _ => Err(self.status_error("MKCOL", path, &response)),
         }
     }
 
@@ -309,16 +342,35 @@ impl WebDAVClient {
     pub async fn delete(&self, path: &str) -> Result<(), AppError> {
         let url = self.build_url(path);
         
-        let response = self.client
-            .delete(&url)
-            .header(AUTHORIZATION, self.auth_header())
-            .send()
-            .await
-            .map_err(|e| AppError::WebDAV(format!("DELETE failed: {}", e)))?;
+        let response = self.send("DELETE", Method::DELETE, &url, |r| r).await?;
 
         match response.status() {
             StatusCode::OK | StatusCode::NO_CONTENT | StatusCode::NOT_FOUND => Ok(()),
-            status => Err(AppError::WebDAV(format!("DELETE failed with status: {}", status))),
+            _ => Err(self.status_error("DELETE", path, &response)),
+        }
+    }
+
+    /// 移动/重命名文件或目录 (MOVE)，目标已存在时失败
+    pub async fn move_to(&self, from: &str, to: &str) -> Result<(), AppError> {
+        let url = self.build_url(from);
+        // Destination 头必须是编码后的完整 URL
+        let destination = reqwest::Url::parse(&self.build_url(to))
+            .map_err(|e| AppError::WebDAV(format!("Invalid MOVE destination: {}", e)))?;
+
+        let response = self
+            .send("MOVE", Method::from_bytes(b"MOVE").unwrap(), &url, |r| {
+                r.header("Destination", destination.as_str())
+                    .header("Overwrite", "F")
+            })
+            .await?;
+
+        match response.status() {
+            StatusCode::OK | StatusCode::CREATED | StatusCode::NO_CONTENT => Ok(()),
+            StatusCode::PRECONDITION_FAILED => Err(AppError::WebDAV(format!(
+                "MOVE failed: destination already exists: {}",
+                to
+            ))),
+            _ => Err(self.status_error("MOVE", from, &response)),
         }
     }
 
@@ -339,28 +391,98 @@ impl WebDAVClient {
     }
 }
 
-/// 简单的 URL 解码
-fn urlencoding_decode(s: &str) -> String {
-    let mut result = String::new();
-    let mut chars = s.chars().peekable();
-    
-    while let Some(c) = chars.next() {
-        if c == '%' {
-            let hex: String = chars.by_ref().take(2).collect();
-            if let Ok(byte) = u8::from_str_radix(&hex, 16) {
-                result.push(byte as char);
-            } else {
-                result.push('%');
-                result.push_str(&hex);
-            }
-        } else if c == '+' {
-            result.push(' ');
+#[async_trait]
+impl RemoteStorage for WebDAVClient {
+    async fn test_connection(&self) -> Result<bool, AppError> {
+        WebDAVClient::test_connection(self).await
+    }
+
+    async fn list(&self, path: &str, recursive: bool) -> Result<Vec<RemoteEntry>, AppError> {
+        if recursive {
+            self.list_all_recursive(path).await
         } else {
-            result.push(c);
+            self.list_dir(path).await
         }
     }
-    
-    result
+
+    async fn get(&self, path: &str) -> Result<Vec<u8>, AppError> {
+        self.download(path).await
+    }
+
+    async fn put(&self, path: &str, content: &[u8]) -> Result<Option<String>, AppError> {
+        self.upload(path, content).await
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), AppError> {
+        self.ensure_dir(path).await
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), AppError> {
+        WebDAVClient::delete(self, path).await
+    }
+
+    async fn move_to(&self, from: &str, to: &str) -> Result<(), AppError> {
+        WebDAVClient::move_to(self, from, to).await
+    }
+
+    async fn metadata(&self, path: &str) -> Result<Option<RemoteEntry>, AppError> {
+        self.stat(path).await
+    }
+}
+
+fn propfind_method() -> Method {
+    Method::from_bytes(b"PROPFIND").unwrap()
+}
+
+/// 响应中的全部认证质询
+fn challenges(response: &Response) -> Vec<auth::Challenge> {
+    response
+        .headers()
+        .get_all(WWW_AUTHENTICATE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(auth::parse_challenges)
+        .collect()
+}
+
+/// 由 DAV 属性构建远程条目，路径为空时返回 None
+fn to_remote_entry(path: &str, props: DavProps) -> Option<RemoteEntry> {
+    let path = path.trim_end_matches('/').to_string();
+    let name = path.rsplit('/').next().unwrap_or("").to_string();
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(RemoteEntry {
+        path,
+        name,
+        is_dir: props.is_collection,
+        size: props.content_length.unwrap_or(0),
+        modified: props.last_modified
+            .as_deref()
+            .and_then(parse_http_date)
+            .unwrap_or(0),
+        etag: props.etag,
+        content_type: props.content_type,
+    })
+}
+
+/// URL 路径解码 (按 UTF-8 解码百分号编码；路径中的 `+` 保持原样)
+fn urlencoding_decode(s: &str) -> String {
+    urlencoding::decode(s)
+        .map(|d| d.into_owned())
+        .unwrap_or_else(|_| String::from_utf8_lossy(&urlencoding::decode_binary(s.as_bytes())).into_owned())
+}
+
+/// 提取 URL 的路径部分；非完整 URL 原样返回
+fn url_path(href: &str) -> &str {
+    match href.find("://") {
+        Some(pos) => {
+            let rest = &href[pos + 3..];
+            rest.find('/').map(|i| &rest[i..]).unwrap_or("/")
+        }
+        None => href,
+    }
 }
 
 /// 解析 HTTP 日期格式
@@ -396,11 +518,197 @@ fn parse_http_date(s: &str) -> Option<u64> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::webdav::test_server::{TestAuth, TestServer};
+    use crate::webdav::types::AuthMethod;
 
     #[test]
     fn test_url_decode() {
         assert_eq!(urlencoding_decode("hello%20world"), "hello world");
-        assert_eq!(urlencoding_decode("test+file"), "test file");
+        // href 是 URL 路径，`+` 不代表空格
+        assert_eq!(urlencoding_decode("test+file"), "test+file");
         assert_eq!(urlencoding_decode("%E4%B8%AD%E6%96%87"), "中文");
     }
+
+    fn client(server_url: &str, remote_base_path: &str) -> WebDAVClient {
+        WebDAVClient::new(WebDAVConfig {
+            server_url: server_url.to_string(),
+            remote_base_path: remote_base_path.to_string(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn paths(entries: &[RemoteEntry]) -> Vec<(String, bool)> {
+        let mut paths: Vec<_> = entries.iter().map(|e| (e.path.clone(), e.is_dir)).collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_parse_nextcloud_listing() {
+        let client = client("https://cloud.example.com/remote.php/dav/files/alice", "/notes");
+        let entries = client
+            .parse_propfind_response(include_str!("fixtures/propfind_nextcloud.xml"), "")
+            .unwrap();
+
+        assert_eq!(
+            paths(&entries),
+            vec![("Daily Note.md".to_string(), false), ("Projects".to_string(), true)]
+        );
+        let note = entries.iter().find(|e| e.name == "Daily Note.md").unwrap();
+        assert_eq!(note.size, 1342);
+        assert_eq!(note.modified, 1733221800);
+    }
+
+    #[test]
+    fn test_parse_absolute_hrefs() {
+        let client = client("https://nas.local:5006/home", "vault");
+        let entries = client
+            .parse_propfind_response(include_str!("fixtures/propfind_synology.xml"), "")
+            .unwrap();
+
+        assert_eq!(
+            paths(&entries),
+            vec![("assets".to_string(), true), ("中文.md".to_string(), false)]
+        );
+    }
+
+    #[test]
+    fn test_parse_subdirectory_listing() {
+        let client = client("https://dav.example.com", "/dav");
+        let entries = client
+            .parse_propfind_response(include_str!("fixtures/propfind_apache.xml"), "notes")
+            .unwrap();
+
+        assert_eq!(
+            paths(&entries),
+            vec![("notes/archive".to_string(), true), ("notes/readme.md".to_string(), false)]
+        );
+    }
+
+    #[test]
+    fn test_parse_default_namespace_listing() {
+        let client = client("http://files.example.com", "/Notes");
+        let entries = client
+            .parse_propfind_response(include_str!("fixtures/propfind_iis.xml"), "")
+            .unwrap();
+
+        assert_eq!(paths(&entries), vec![("todo.txt".to_string(), false)]);
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_against_server() {
+        let server = TestServer::start().await;
+        let client = WebDAVClient::new(server.config()).unwrap();
+
+        assert!(client.test_connection().await.unwrap());
+        client.ensure_dir("/notes/daily").await.unwrap();
+        let etag = client.upload("notes/daily/a.md", b"alpha").await.unwrap();
+        assert!(etag.is_some());
+        assert_eq!(client.download_text("notes/daily/a.md").await.unwrap(), "alpha");
+
+        let entry = client.stat("notes/daily/a.md").await.unwrap().unwrap();
+        assert_eq!(entry.size, 5);
+        assert_eq!(entry.etag, etag);
+        assert!(entry.modified > 0);
+
+        // 重新上传后 ETag 改变
+        let new_etag = client.upload("notes/daily/a.md", b"alpha 2").await.unwrap();
+        assert_ne!(new_etag, etag);
+
+        client.move_to("notes/daily/a.md", "notes/b 中文.md").await.unwrap();
+        assert!(client.stat("notes/daily/a.md").await.unwrap().is_none());
+        assert_eq!(client.download_text("notes/b 中文.md").await.unwrap(), "alpha 2");
+        assert!(client.move_to("notes/missing.md", "notes/c.md").await.is_err());
+
+        client.delete("notes").await.unwrap();
+        assert!(client.stat("notes/b 中文.md").await.unwrap().is_none());
+        assert!(client.download("notes/b 中文.md").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recursive_listing_with_and_without_infinity() {
+        let server = TestServer::start().await;
+        server.put_file("a.md", b"a");
+        server.put_file("notes/b.md", b"b");
+        server.put_file("notes/deep/c.md", b"c");
+        let client = WebDAVClient::new(server.config()).unwrap();
+
+        let expected = vec![
+            ("a.md".to_string(), false),
+            ("notes".to_string(), true),
+            ("notes/b.md".to_string(), false),
+            ("notes/deep".to_string(), true),
+            ("notes/deep/c.md".to_string(), false),
+        ];
+
+        // 服务器拒绝 infinity 时逐层遍历
+        assert_eq!(paths(&client.list_all_recursive("").await.unwrap()), expected);
+        assert_eq!(server.requests("PROPFIND").len(), 4);
+
+        server.set_depth_infinity(true);
+        server.clear_requests();
+        assert_eq!(paths(&client.list_all_recursive("").await.unwrap()), expected);
+        assert_eq!(server.requests("PROPFIND").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_digest_challenge_and_stale_nonce() {
+        let server = TestServer::start().await;
+        server.set_auth(TestAuth::Digest);
+        let client = WebDAVClient::new(server.config()).unwrap();
+
+        // 自动模式：Basic 被拒后按质询切换到 Digest
+        assert!(client.test_connection().await.unwrap());
+        assert_eq!(server.requests("PROPFIND").len(), 2);
+
+        // 之后的请求直接带 Digest 认证头
+        client.upload("a.md", b"alpha").await.unwrap();
+        assert_eq!(server.requests("PUT").len(), 1);
+
+        // nonce 过期时重新质询一次
+        server.expire_nonce();
+        assert_eq!(client.download("a.md").await.unwrap(), b"alpha");
+        assert_eq!(server.requests("GET").len(), 2);
+
+        let mut config = server.config();
+        config.auth = AuthMethod::Digest;
+        config.password = "wrong".to_string();
+        let err = WebDAVClient::new(config).unwrap().test_connection().await.unwrap_err();
+        assert!(matches!(err, AppError::WebDAVAuth(ref m) if m == "check username and password"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_bearer_and_basic_auth() {
+        let server = TestServer::start().await;
+        server.set_auth(TestAuth::Bearer("app-token".to_string()));
+
+        let mut config = server.config();
+        config.auth = AuthMethod::Bearer;
+        config.password = "app-token".to_string();
+        assert!(WebDAVClient::new(config).unwrap().test_connection().await.unwrap());
+
+        server.set_auth(TestAuth::Basic);
+        assert!(WebDAVClient::new(server.config()).unwrap().test_connection().await.unwrap());
+
+        // 只允许 Basic 时不会尝试服务器要求的 Digest
+        server.set_auth(TestAuth::Digest);
+        let mut config = server.config();
+        config.auth = AuthMethod::Basic;
+        let err = WebDAVClient::new(config).unwrap().test_connection().await.unwrap_err();
+        assert!(matches!(err, AppError::WebDAVAuth(ref m) if m == "server requires digest authentication"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_error_kinds() {
+        let server = TestServer::start().await;
+        let client = WebDAVClient::new(server.config()).unwrap();
+        client.upload("a.md", b"alpha").await.unwrap();
+
+        assert!(matches!(client.download("missing.md").await, Err(AppError::WebDAVNotFound(_))));
+        server.fail_with("GET", "a.md", 403);
+        assert!(matches!(client.download("a.md").await, Err(AppError::WebDAVForbidden(_))));
+        server.fail_with("PUT", "b.md", 507);
+        assert!(matches!(client.upload("b.md", b"beta").await, Err(AppError::WebDAV(_))));
+    }
 }
@@ -2,23 +2,45 @@
 //! 
 //! 暴露给前端的命令接口
 
-use std::sync::Mutex;
-use tauri::State;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
 
 use super::types::*;
-use super::client::WebDAVClient;
+use super::history;
+use super::profiles::ProfileStore;
+use super::storage;
+use super::scheduler::AutoSyncScheduler;
 use super::sync::SyncEngine;
 use crate::error::AppError;
+use crate::fs::sandbox::VaultSandbox;
+use crate::fs::watcher::WatcherState;
 
 /// WebDAV 状态管理
 pub struct WebDAVState {
-    config: Mutex<Option<WebDAVConfig>>,
+    /// 自动同步使用的配置 (所在 vault 的当前配置)
+    config: Arc<Mutex<Option<WebDAVConfig>>>,
+    /// 自动同步所在的 vault
+    auto_sync_vault: Mutex<Option<String>>,
+    /// 串行化配置文件的读改写
+    profiles_lock: Mutex<()>,
+    /// 同步互斥锁，防止手动同步与自动同步重叠
+    sync_lock: Arc<tokio::sync::Mutex<()>>,
+    /// 取消标志，由 webdav_cancel_sync 置位
+    cancel: Arc<AtomicBool>,
+    auto_sync: Mutex<Option<AutoSyncScheduler>>,
 }
 
 impl WebDAVState {
     pub fn new() -> Self {
         Self {
-            config: Mutex::new(None),
+            config: Arc::new(Mutex::new(None)),
+            auto_sync_vault: Mutex::new(None),
+            profiles_lock: Mutex::new(()),
+            sync_lock: Arc::new(tokio::sync::Mutex::new(())),
+            cancel: Arc::new(AtomicBool::new(false)),
+            auto_sync: Mutex::new(None),
         }
     }
 }
@@ -29,134 +51,390 @@ impl Default for WebDAVState {
     }
 }
 
-/// 设置 WebDAV 配置
+fn lock_error<T>(_: T) -> AppError {
+    AppError::WebDAV("Failed to acquire lock".to_string())
+}
+
+/// 应用数据目录中的配置存储
+fn profile_store(app: &AppHandle) -> Result<ProfileStore, AppError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::WebDAV(format!("Failed to resolve app data dir: {}", e)))?;
+    Ok(ProfileStore::new(&dir))
+}
+
+/// 读取完整配置，profile_id 为空时使用 vault 的当前配置
+fn profile_config(
+    app: &AppHandle,
+    vault_path: &str,
+    profile_id: Option<&str>,
+) -> Result<WebDAVConfig, AppError> {
+    Ok(profile_store(app)?.get(vault_path, profile_id)?.config)
+}
+
+/// 配置变更后刷新自动同步使用的配置 (调度器每次运行前重新读取)
+fn refresh_auto_sync_config(
+    app: &AppHandle,
+    state: &WebDAVState,
+    vault_path: &str,
+) -> Result<(), AppError> {
+    let vault = state.auto_sync_vault.lock().map_err(lock_error)?;
+    if vault.as_deref() != Some(vault_path) {
+        return Ok(());
+    }
+    let config = profile_store(app)?.get(vault_path, None).ok().map(|p| p.config);
+    *state.config.lock().map_err(lock_error)? = config;
+    Ok(())
+}
+
+/// 列出 vault 的同步配置 (不含密码等密钥)
+#[tauri::command]
+pub async fn webdav_list_profiles(
+    app: AppHandle,
+    vault_path: String,
+) -> Result<VaultProfiles, AppError> {
+    profile_store(&app)?.list(&vault_path)
+}
+
+/// 新建或更新同步配置 (id 为空时新建；密钥留空表示沿用已保存的值)
 #[tauri::command]
-pub async fn webdav_set_config(
+pub async fn webdav_save_profile(
+    app: AppHandle,
     state: State<'_, WebDAVState>,
-    config: WebDAVConfig,
+    vault_path: String,
+    profile: SyncProfile,
+) -> Result<SyncProfile, AppError> {
+    let saved = {
+        let _guard = state.profiles_lock.lock().map_err(lock_error)?;
+        profile_store(&app)?.save(&vault_path, profile)?
+    };
+    refresh_auto_sync_config(&app, &state, &vault_path)?;
+    Ok(saved)
+}
+
+/// 删除同步配置
+#[tauri::command]
+pub async fn webdav_delete_profile(
+    app: AppHandle,
+    state: State<'_, WebDAVState>,
+    vault_path: String,
+    profile_id: String,
 ) -> Result<(), AppError> {
-    let mut guard = state.config.lock().map_err(|_| {
-        AppError::WebDAV("Failed to acquire lock".to_string())
-    })?;
-    *guard = Some(config);
-    Ok(())
+    {
+        let _guard = state.profiles_lock.lock().map_err(lock_error)?;
+        profile_store(&app)?.delete(&vault_path, &profile_id)?;
+    }
+    refresh_auto_sync_config(&app, &state, &vault_path)
 }
 
-/// 获取 WebDAV 配置
+/// 切换 vault 当前使用的同步配置
 #[tauri::command]
-pub async fn webdav_get_config(
+pub async fn webdav_set_active_profile(
+    app: AppHandle,
     state: State<'_, WebDAVState>,
-) -> Result<Option<WebDAVConfig>, AppError> {
-    let guard = state.config.lock().map_err(|_| {
-        AppError::WebDAV("Failed to acquire lock".to_string())
-    })?;
-    Ok(guard.clone())
+    vault_path: String,
+    profile_id: String,
+) -> Result<(), AppError> {
+    {
+        let _guard = state.profiles_lock.lock().map_err(lock_error)?;
+        profile_store(&app)?.set_active(&vault_path, &profile_id)?;
+    }
+    refresh_auto_sync_config(&app, &state, &vault_path)
 }
 
-/// 测试 WebDAV 连接
+/// 测试已保存配置的连接
+#[tauri::command]
+pub async fn webdav_test_profile(
+    app: AppHandle,
+    vault_path: String,
+    profile_id: Option<String>,
+) -> Result<bool, AppError> {
+    let config = profile_config(&app, &vault_path, profile_id.as_deref())?;
+    storage::connect(&config)?.test_connection().await
+}
+
+/// 测试尚未保存的配置的连接 (用于设置表单)
 #[tauri::command]
 pub async fn webdav_test_connection(config: WebDAVConfig) -> Result<bool, AppError> {
-    let client = WebDAVClient::new(config)?;
-    client.test_connection().await
+    storage::connect(&config)?.test_connection().await
 }
 
 /// 列出远程目录
 #[tauri::command]
 pub async fn webdav_list_remote(
-    config: WebDAVConfig,
+    app: AppHandle,
+    vault_path: String,
+    profile_id: Option<String>,
     path: String,
 ) -> Result<Vec<RemoteEntry>, AppError> {
-    let client = WebDAVClient::new(config)?;
-    client.list_dir(&path).await
+    storage::connect(&profile_config(&app, &vault_path, profile_id.as_deref())?)?
+        .list(&path, false)
+        .await
 }
 
 /// 列出所有远程文件（递归）
 #[tauri::command]
 pub async fn webdav_list_all_remote(
-    config: WebDAVConfig,
+    app: AppHandle,
+    vault_path: String,
+    profile_id: Option<String>,
 ) -> Result<Vec<RemoteEntry>, AppError> {
-    let client = WebDAVClient::new(config)?;
-    client.list_all_recursive("").await
+    storage::connect(&profile_config(&app, &vault_path, profile_id.as_deref())?)?
+        .list("", true)
+        .await
 }
 
 /// 下载远程文件
 #[tauri::command]
 pub async fn webdav_download(
-    config: WebDAVConfig,
+    app: AppHandle,
+    vault_path: String,
+    profile_id: Option<String>,
     remote_path: String,
 ) -> Result<String, AppError> {
-    let client = WebDAVClient::new(config)?;
-    client.download_text(&remote_path).await
+    let config = profile_config(&app, &vault_path, profile_id.as_deref())?;
+    let content = storage::connect(&config)?.get(&remote_path).await?;
+    String::from_utf8(content).map_err(|e| AppError::WebDAV(format!("Invalid UTF-8: {}", e)))
 }
 
 /// 上传文件到远程
 #[tauri::command]
 pub async fn webdav_upload(
-    config: WebDAVConfig,
+    app: AppHandle,
+    vault_path: String,
+    profile_id: Option<String>,
     remote_path: String,
     content: String,
 ) -> Result<(), AppError> {
-    let client = WebDAVClient::new(config)?;
-    client.upload_text(&remote_path, &content).await
+    storage::connect(&profile_config(&app, &vault_path, profile_id.as_deref())?)?
+        .put(&remote_path, content.as_bytes())
+        .await
+        .map(|_| ())
 }
 
 /// 在远程创建目录
 #[tauri::command]
 pub async fn webdav_create_dir(
-    config: WebDAVConfig,
+    app: AppHandle,
+    vault_path: String,
+    profile_id: Option<String>,
     remote_path: String,
 ) -> Result<(), AppError> {
-    let client = WebDAVClient::new(config)?;
-    client.ensure_dir(&remote_path).await
+    storage::connect(&profile_config(&app, &vault_path, profile_id.as_deref())?)?
+        .mkdir(&remote_path)
+        .await
 }
 
 /// 删除远程文件/目录
 #[tauri::command]
 pub async fn webdav_delete(
-    config: WebDAVConfig,
+    app: AppHandle,
+    vault_path: String,
+    profile_id: Option<String>,
     remote_path: String,
 ) -> Result<(), AppError> {
-    let client = WebDAVClient::new(config)?;
-    client.delete(&remote_path).await
+    storage::connect(&profile_config(&app, &vault_path, profile_id.as_deref())?)?
+        .delete(&remote_path)
+        .await
+}
+
+/// 获取同步历史 (最新的在前)
+#[tauri::command]
+pub async fn webdav_get_sync_history(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+    limit: Option<usize>,
+) -> Result<Vec<SyncHistoryEntry>, AppError> {
+    sandbox.check(window.label(), &vault_path)?;
+    history::load_history(&vault_path, limit)
+}
+
+/// 列出文件被同步覆盖前保存的历史版本
+#[tauri::command]
+pub async fn webdav_list_versions(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+    path: String,
+) -> Result<Vec<NoteVersion>, AppError> {
+    sandbox.check(window.label(), &vault_path)?;
+    history::list_versions(&vault_path, &path)
+}
+
+/// 恢复文件的某个历史版本
+#[tauri::command]
+pub async fn webdav_restore_version(
+    window: tauri::WebviewWindow,
+    sandbox: State<'_, VaultSandbox>,
+    vault_path: String,
+    path: String,
+    version_id: String,
+) -> Result<(), AppError> {
+    sandbox.check(window.label(), &vault_path)?;
+    history::restore_version(&vault_path, &path, &version_id)
+}
+
+/// 预览同步规则排除的路径及原因 (不执行同步)
+#[tauri::command]
+pub async fn webdav_preview_exclusions(
+    app: AppHandle,
+    vault_path: String,
+    profile_id: Option<String>,
+) -> Result<Vec<ExcludedPath>, AppError> {
+    let config = profile_config(&app, &vault_path, profile_id.as_deref())?;
+    let mut engine = SyncEngine::new(config, vault_path)?;
+    engine.preview_exclusions().await
+}
+
+/// 将同步进度转发为前端 "webdav:progress" 事件
+fn progress_sink(app: AppHandle) -> ProgressSink {
+    Arc::new(move |progress: SyncProgress| {
+        let _ = app.emit("webdav:progress", progress);
+    })
 }
 
 /// 计算同步计划
 #[tauri::command]
 pub async fn webdav_compute_sync_plan(
-    config: WebDAVConfig,
+    app: AppHandle,
     vault_path: String,
+    profile_id: Option<String>,
 ) -> Result<SyncPlan, AppError> {
-    let mut engine = SyncEngine::new(config, vault_path)?;
+    let config = profile_config(&app, &vault_path, profile_id.as_deref())?;
+    let mut engine = SyncEngine::new(config, vault_path)?.with_progress(progress_sink(app));
     engine.compute_sync_plan().await
 }
 
 /// 执行同步
 #[tauri::command]
 pub async fn webdav_execute_sync(
-    config: WebDAVConfig,
+    app: AppHandle,
+    state: State<'_, WebDAVState>,
     vault_path: String,
+    profile_id: Option<String>,
     plan: SyncPlan,
 ) -> Result<SyncResult, AppError> {
-    let mut engine = SyncEngine::new(config, vault_path)?;
+    let config = profile_config(&app, &vault_path, profile_id.as_deref())?;
+    let _guard = state.sync_lock.lock().await;
+    let mut engine = new_cancellable_engine(&state, config, vault_path)?.with_progress(progress_sink(app));
     engine.execute_sync(&plan).await
 }
 
 /// 快速同步（跳过冲突）
 #[tauri::command]
 pub async fn webdav_quick_sync(
-    config: WebDAVConfig,
+    app: AppHandle,
+    state: State<'_, WebDAVState>,
     vault_path: String,
+    profile_id: Option<String>,
 ) -> Result<SyncResult, AppError> {
-    let mut engine = SyncEngine::new(config, vault_path)?;
+    let config = profile_config(&app, &vault_path, profile_id.as_deref())?;
+    let _guard = state.sync_lock.lock().await;
+    let mut engine = new_cancellable_engine(&state, config, vault_path)?.with_progress(progress_sink(app));
     engine.quick_sync().await
 }
 
+/// 创建可被 webdav_cancel_sync 取消的引擎 (须在持有同步锁后调用)
+fn new_cancellable_engine(
+    state: &WebDAVState,
+    config: WebDAVConfig,
+    vault_path: String,
+) -> Result<SyncEngine, AppError> {
+    state.cancel.store(false, Ordering::SeqCst);
+    Ok(SyncEngine::new(config, vault_path)?.with_cancel(state.cancel.clone()))
+}
+
+/// 取消正在运行的同步 (当前文件完成后停止，未完成的条目可恢复)
+#[tauri::command]
+pub async fn webdav_cancel_sync(state: State<'_, WebDAVState>) -> Result<(), AppError> {
+    state.cancel.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// 获取上次被中断的同步计划
+#[tauri::command]
+pub async fn webdav_get_interrupted_sync(vault_path: String) -> Result<Option<SyncPlan>, AppError> {
+    Ok(SyncEngine::pending_plan(&vault_path))
+}
+
+/// 恢复上次被中断的同步，没有未完成计划时返回 None
+#[tauri::command]
+pub async fn webdav_resume_sync(
+    app: AppHandle,
+    state: State<'_, WebDAVState>,
+    vault_path: String,
+    profile_id: Option<String>,
+) -> Result<Option<SyncResult>, AppError> {
+    let config = profile_config(&app, &vault_path, profile_id.as_deref())?;
+    let _guard = state.sync_lock.lock().await;
+    let mut engine = new_cancellable_engine(&state, config, vault_path)?.with_progress(progress_sink(app));
+    engine.resume_sync().await
+}
+
+/// 启动自动同步 (使用 vault 的当前配置，之后切换或修改配置会自动生效)
+///
+/// 配置未开启 auto_sync 时不启动并返回 false。
+/// 本地变化来自窗口对该 vault 的文件监听；没有窗口监听时另起一个只供调度器使用的监听
+#[tauri::command]
+pub async fn webdav_start_auto_sync(
+    app: AppHandle,
+    state: State<'_, WebDAVState>,
+    vault_path: String,
+) -> Result<bool, AppError> {
+    let config = profile_config(&app, &vault_path, None)?;
+    let auto_sync = config.auto_sync;
+
+    let mut scheduler = state.auto_sync.lock().map_err(lock_error)?;
+    // 先停止旧的调度器 (可能对应另一个 vault)
+    *scheduler = None;
+    *state.config.lock().map_err(lock_error)? = Some(config);
+    *state.auto_sync_vault.lock().map_err(lock_error)? = Some(vault_path.clone());
+
+    if !auto_sync {
+        return Ok(false);
+    }
+
+    let watchers = app.state::<WatcherState>();
+    let feed = if watchers.is_watching(&vault_path) {
+        None
+    } else {
+        let feed = watchers
+            .listeners()
+            .watch(Path::new(&vault_path))
+            .map_err(|e| AppError::WebDAV(format!("Failed to watch vault: {}", e)))?;
+        Some(feed)
+    };
+    *scheduler = Some(AutoSyncScheduler::start(
+        state.config.clone(),
+        state.sync_lock.clone(),
+        state.cancel.clone(),
+        vault_path,
+        progress_sink(app.clone()),
+        watchers.listeners(),
+        feed,
+    ));
+    Ok(true)
+}
+
+/// 停止自动同步
+#[tauri::command]
+pub async fn webdav_stop_auto_sync(state: State<'_, WebDAVState>) -> Result<(), AppError> {
+    let mut scheduler = state.auto_sync.lock().map_err(lock_error)?;
+    *scheduler = None;
+    *state.auto_sync_vault.lock().map_err(lock_error)? = None;
+    Ok(())
+}
+
 /// 扫描本地文件
 #[tauri::command]
 pub async fn webdav_scan_local(
-    config: WebDAVConfig,
+    app: AppHandle,
     vault_path: String,
+    profile_id: Option<String>,
 ) -> Result<Vec<LocalFileInfo>, AppError> {
+    let config = profile_config(&app, &vault_path, profile_id.as_deref())?;
     let engine = SyncEngine::new(config, vault_path)?;
     engine.scan_local_files()
 }
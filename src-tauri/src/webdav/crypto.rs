@@ -0,0 +1,379 @@
+//! 端到端加密
+//!
+//! 由用户口令经 Argon2id 派生主密钥，文件内容使用 XChaCha20-Poly1305 加密后再上传。
+//! 远程根目录保存一份加密清单，其他设备解开清单即可验证口令是否正确。
+//! 文件名可选加密：逐段确定性加密 (nonce 由 HMAC 派生)，同一路径总是映射到同一远程路径
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine as _;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::AppError;
+
+/// 加密清单在远程根目录下的路径
+pub const MANIFEST_PATH: &str = ".lumina-e2ee.json";
+
+/// 加密文件头
+const MAGIC: &[u8; 4] = b"LNE1";
+const NONCE_LEN: usize = 24;
+const TAG_LEN: usize = 16;
+/// 每个加密文件比明文多出的字节数
+pub const OVERHEAD: u64 = (MAGIC.len() + NONCE_LEN + TAG_LEN) as u64;
+/// 远程路径单段的常见长度上限 (字节)
+const MAX_REMOTE_NAME_LEN: usize = 255;
+/// 加密后不超过该上限的最长文件名 (base64 前为 nonce + 文件名 + tag)
+const MAX_ENCRYPTED_NAME_LEN: usize = MAX_REMOTE_NAME_LEN * 3 / 4 - NONCE_LEN - TAG_LEN;
+
+const MANIFEST_VERSION: u32 = 1;
+
+// OWASP 推荐的 Argon2id 参数；测试中使用最低成本以加快速度
+#[cfg(not(test))]
+const KDF_MEMORY_KIB: u32 = 19 * 1024;
+#[cfg(not(test))]
+const KDF_ITERATIONS: u32 = 2;
+#[cfg(test)]
+const KDF_MEMORY_KIB: u32 = 64;
+#[cfg(test)]
+const KDF_ITERATIONS: u32 = 1;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 密钥派生参数 (随清单保存，修改默认值不影响已有数据)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub algorithm: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    /// 盐 (base64)
+    pub salt: String,
+}
+
+/// 远程加密清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct E2EEManifest {
+    pub version: u32,
+    pub kdf: KdfParams,
+    /// 用派生密钥加密的 [`ManifestPayload`] (base64)
+    pub payload: String,
+}
+
+/// 清单中的加密部分
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ManifestPayload {
+    encrypt_names: bool,
+    created_at: u64,
+}
+
+impl E2EEManifest {
+    /// 首次启用加密：生成新盐和清单
+    pub fn create(passphrase: &str, encrypt_names: bool) -> Result<(Self, VaultCipher), AppError> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+
+        let kdf = KdfParams {
+            algorithm: "argon2id".to_string(),
+            memory_kib: KDF_MEMORY_KIB,
+            iterations: KDF_ITERATIONS,
+            parallelism: 1,
+            salt: STANDARD.encode(salt),
+        };
+        let master = derive_master_key(passphrase, &kdf)?;
+        let cipher = VaultCipher::new(&master, encrypt_names);
+
+        let payload = ManifestPayload {
+            encrypt_names,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        };
+        let payload = serde_json::to_vec(&payload)
+            .map_err(|e| AppError::WebDAV(format!("Failed to serialize manifest: {}", e)))?;
+
+        let manifest = Self {
+            version: MANIFEST_VERSION,
+            kdf,
+            payload: STANDARD.encode(cipher.encrypt(&payload)?),
+        };
+        Ok((manifest, cipher))
+    }
+
+    /// 用口令解开清单，口令错误时返回错误
+    pub fn unlock(&self, passphrase: &str) -> Result<VaultCipher, AppError> {
+        if self.version != MANIFEST_VERSION || self.kdf.algorithm != "argon2id" {
+            return Err(AppError::WebDAV(format!(
+                "Unsupported encryption manifest (version {}, {})",
+                self.version, self.kdf.algorithm
+            )));
+        }
+
+        let master = derive_master_key(passphrase, &self.kdf)?;
+        let payload = STANDARD
+            .decode(&self.payload)
+            .map_err(|e| AppError::WebDAV(format!("Invalid encryption manifest: {}", e)))?;
+
+        // 先以内容密钥解密清单，成功即说明口令正确，再按清单决定是否加密文件名
+        let probe = VaultCipher::new(&master, false);
+        let payload = probe
+            .decrypt(&payload)
+            .map_err(|_| AppError::WebDAV("Wrong encryption passphrase".to_string()))?;
+        let payload: ManifestPayload = serde_json::from_slice(&payload)
+            .map_err(|e| AppError::WebDAV(format!("Invalid encryption manifest: {}", e)))?;
+
+        Ok(VaultCipher::new(&master, payload.encrypt_names))
+    }
+
+    pub fn to_json(&self) -> Result<String, AppError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::WebDAV(format!("Failed to serialize manifest: {}", e)))
+    }
+
+    pub fn from_json(content: &[u8]) -> Result<Self, AppError> {
+        serde_json::from_slice(content)
+            .map_err(|e| AppError::WebDAV(format!("Invalid encryption manifest: {}", e)))
+    }
+}
+
+/// 已解锁的加密器
+pub struct VaultCipher {
+    content: XChaCha20Poly1305,
+    names: Option<NameCipher>,
+}
+
+/// 文件名加密器
+struct NameCipher {
+    cipher: XChaCha20Poly1305,
+    nonce_key: [u8; 32],
+}
+
+impl VaultCipher {
+    fn new(master: &[u8; 32], encrypt_names: bool) -> Self {
+        let content = XChaCha20Poly1305::new(Key::from_slice(&subkey(master, b"content")));
+        let names = encrypt_names.then(|| NameCipher {
+            cipher: XChaCha20Poly1305::new(Key::from_slice(&subkey(master, b"names"))),
+            nonce_key: subkey(master, b"name-nonce"),
+        });
+        Self { content, names }
+    }
+
+    /// 加密文件内容：`MAGIC || nonce || ciphertext`
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, AppError> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .content
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| AppError::WebDAV("Encryption failed".to_string()))?;
+
+        let mut out = Vec::with_capacity(plaintext.len() + OVERHEAD as usize);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// 解密文件内容，数据被篡改或密钥不符时返回错误
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, AppError> {
+        if data.len() < OVERHEAD as usize || !data.starts_with(MAGIC) {
+            return Err(AppError::WebDAV("File is not encrypted".to_string()));
+        }
+        let (nonce, ciphertext) = data[MAGIC.len()..].split_at(NONCE_LEN);
+        self.content
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| AppError::WebDAV("Decryption failed (corrupted or wrong key)".to_string()))
+    }
+
+    /// 明文路径 -> 远程路径 (未启用文件名加密时原样返回)
+    ///
+    /// 加密后的文件名约为 4/3 × (名称字节数 + 40) 个字符；超过远程单段上限的名称
+    /// 直接报错，避免上传时得到难以理解的服务器错误
+    pub fn encrypt_path(&self, path: &str) -> Result<String, AppError> {
+        let Some(names) = &self.names else {
+            return Ok(path.to_string());
+        };
+        path.split('/')
+            .filter(|s| !s.is_empty())
+            .map(|segment| names.encrypt(segment))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|segments| segments.join("/"))
+    }
+
+    /// 远程路径 -> 明文路径，无法解密 (非本 vault 写入) 时返回 None
+    pub fn decrypt_path(&self, path: &str) -> Option<String> {
+        let Some(names) = &self.names else {
+            return Some(path.to_string());
+        };
+        path.split('/')
+            .filter(|s| !s.is_empty())
+            .map(|segment| names.decrypt(segment))
+            .collect::<Option<Vec<_>>>()
+            .map(|segments| segments.join("/"))
+    }
+
+    /// 由远程文件大小推算明文大小
+    pub fn plaintext_size(&self, remote_size: u64) -> u64 {
+        remote_size.saturating_sub(OVERHEAD)
+    }
+}
+
+impl NameCipher {
+    fn encrypt(&self, segment: &str) -> Result<String, AppError> {
+        if segment.len() > MAX_ENCRYPTED_NAME_LEN {
+            return Err(AppError::WebDAV(format!(
+                "Name too long for file name encryption ({} bytes, at most {}): {}",
+                segment.len(),
+                MAX_ENCRYPTED_NAME_LEN,
+                segment
+            )));
+        }
+
+        // 确定性 nonce：同名总是得到同一密文
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&self.nonce_key).expect("HMAC accepts any key length");
+        mac.update(segment.as_bytes());
+        let digest = mac.finalize().into_bytes();
+        let nonce = XNonce::from_slice(&digest[..NONCE_LEN]);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(nonce, segment.as_bytes())
+            .expect("XChaCha20-Poly1305 encryption is infallible for in-memory buffers");
+
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&ciphertext);
+        Ok(URL_SAFE_NO_PAD.encode(out))
+    }
+
+    fn decrypt(&self, segment: &str) -> Option<String> {
+        let data = URL_SAFE_NO_PAD.decode(segment).ok()?;
+        if data.len() < NONCE_LEN + TAG_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self.cipher.decrypt(XNonce::from_slice(nonce), ciphertext).ok()?;
+        String::from_utf8(plaintext).ok()
+    }
+}
+
+/// 由口令派生主密钥
+fn derive_master_key(passphrase: &str, kdf: &KdfParams) -> Result<[u8; 32], AppError> {
+    if passphrase.is_empty() {
+        return Err(AppError::WebDAV("Encryption passphrase is empty".to_string()));
+    }
+    let salt = STANDARD
+        .decode(&kdf.salt)
+        .map_err(|e| AppError::WebDAV(format!("Invalid encryption salt: {}", e)))?;
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| AppError::WebDAV(format!("Invalid KDF parameters: {}", e)))?;
+
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| AppError::WebDAV(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+/// 由主密钥派生用途独立的子密钥
+fn subkey(master: &[u8; 32], purpose: &[u8]) -> [u8; 32] {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(master).expect("HMAC accepts any key length");
+    mac.update(b"lumina-e2ee/");
+    mac.update(purpose);
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_round_trip() {
+        let (_, cipher) = E2EEManifest::create("correct horse", false).unwrap();
+        let plaintext = "# 笔记\n\nsecret content".as_bytes();
+
+        let encrypted = cipher.encrypt(plaintext).unwrap();
+        assert_eq!(encrypted.len() as u64, plaintext.len() as u64 + OVERHEAD);
+        assert!(!encrypted.windows(6).any(|w| w == b"secret"));
+        assert_eq!(cipher.plaintext_size(encrypted.len() as u64), plaintext.len() as u64);
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+
+        // 每次加密使用新 nonce
+        assert_ne!(cipher.encrypt(plaintext).unwrap(), encrypted);
+    }
+
+    #[test]
+    fn test_tampered_content_rejected() {
+        let (_, cipher) = E2EEManifest::create("correct horse", false).unwrap();
+        let mut encrypted = cipher.encrypt(b"hello").unwrap();
+        let last = encrypted.len() - 1;
+        encrypted[last] ^= 1;
+
+        assert!(cipher.decrypt(&encrypted).is_err());
+        assert!(cipher.decrypt(b"plain text file").is_err());
+    }
+
+    #[test]
+    fn test_manifest_unlock() {
+        let (manifest, cipher) = E2EEManifest::create("correct horse", true).unwrap();
+        let json = manifest.to_json().unwrap();
+        let manifest = E2EEManifest::from_json(json.as_bytes()).unwrap();
+
+        let other = manifest.unlock("correct horse").unwrap();
+        let encrypted = cipher.encrypt(b"shared").unwrap();
+        assert_eq!(other.decrypt(&encrypted).unwrap(), b"shared");
+        assert_eq!(other.encrypt_path("a/b.md").unwrap(), cipher.encrypt_path("a/b.md").unwrap());
+
+        assert!(manifest.unlock("wrong horse").is_err());
+    }
+
+    #[test]
+    fn test_name_encryption() {
+        let (_, cipher) = E2EEManifest::create("correct horse", true).unwrap();
+
+        let remote = cipher.encrypt_path("日记/2024/Daily Note.md").unwrap();
+        assert_eq!(remote.split('/').count(), 3);
+        assert!(!remote.contains("Daily"));
+        assert!(remote
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '/'));
+        assert_eq!(remote, cipher.encrypt_path("日记/2024/Daily Note.md").unwrap());
+        assert_eq!(cipher.decrypt_path(&remote).as_deref(), Some("日记/2024/Daily Note.md"));
+
+        // 相同父目录得到相同的密文前缀
+        let sibling = cipher.encrypt_path("日记/2024/other.md").unwrap();
+        assert_eq!(remote.rsplit_once('/').unwrap().0, sibling.rsplit_once('/').unwrap().0);
+
+        assert_eq!(cipher.decrypt_path("plain.md"), None);
+    }
+
+    #[test]
+    fn test_long_names_rejected() {
+        let (_, cipher) = E2EEManifest::create("correct horse", true).unwrap();
+
+        // 最长的可加密名称刚好不超过远程单段上限
+        let longest = "a".repeat(MAX_ENCRYPTED_NAME_LEN);
+        let remote = cipher.encrypt_path(&format!("notes/{}", longest)).unwrap();
+        assert!(remote.split('/').all(|segment| segment.len() <= MAX_REMOTE_NAME_LEN));
+        assert_eq!(cipher.decrypt_path(&remote), Some(format!("notes/{}", longest)));
+
+        let too_long = format!("notes/{}.md", "长".repeat(60));
+        let error = cipher.encrypt_path(&too_long).unwrap_err().to_string();
+        assert!(error.contains("Name too long"), "{}", error);
+
+        // 未启用文件名加密时不受影响
+        let (_, plain) = E2EEManifest::create("correct horse", false).unwrap();
+        assert_eq!(plain.encrypt_path(&too_long).unwrap(), too_long);
+    }
+
+    #[test]
+    fn test_names_kept_when_disabled() {
+        let (_, cipher) = E2EEManifest::create("correct horse", false).unwrap();
+        assert_eq!(cipher.encrypt_path("a/b.md").unwrap(), "a/b.md");
+        assert_eq!(cipher.decrypt_path("a/b.md").as_deref(), Some("a/b.md"));
+    }
+}
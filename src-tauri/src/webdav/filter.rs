@@ -0,0 +1,271 @@
+//! 同步过滤规则
+//!
+//! 决定哪些路径参与同步：内置规则 (隐藏文件、依赖目录、临时文件)、
+//! vault 根目录下 gitignore 语法的 `.luminasyncignore`，以及配置中的
+//! include/exclude glob、文件大小上限和扩展名过滤。本地扫描与远程列表使用同一套规则
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::path::Path;
+
+use super::types::{ExcludeReason, SyncRules};
+use crate::error::AppError;
+
+/// vault 根目录下的忽略规则文件
+pub const IGNORE_FILE: &str = ".luminasyncignore";
+
+/// 内置排除的名称 (任意层级)
+const BUILTIN_NAMES: &[&str] = &["node_modules", "target"];
+/// 内置排除的后缀
+const BUILTIN_SUFFIXES: &[&str] = &[".tmp", ".swp"];
+
+/// 同步过滤器
+pub struct SyncFilter {
+    ignore_file: Option<Gitignore>,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    exclude_patterns: Vec<String>,
+    max_file_size: Option<u64>,
+    allowed_extensions: Vec<String>,
+    blocked_extensions: Vec<String>,
+}
+
+impl SyncFilter {
+    /// 由配置规则和 vault 下的忽略文件构建过滤器
+    pub fn new(rules: &SyncRules, vault_path: &str) -> Result<Self, AppError> {
+        let ignore_path = Path::new(vault_path).join(IGNORE_FILE);
+        let ignore_file = if ignore_path.is_file() {
+            let mut builder = GitignoreBuilder::new(vault_path);
+            if let Some(e) = builder.add(&ignore_path) {
+                return Err(AppError::WebDAV(format!("Invalid {}: {}", IGNORE_FILE, e)));
+            }
+            let gitignore = builder
+                .build()
+                .map_err(|e| AppError::WebDAV(format!("Invalid {}: {}", IGNORE_FILE, e)))?;
+            Some(gitignore)
+        } else {
+            None
+        };
+
+        let include = if rules.include.is_empty() {
+            None
+        } else {
+            Some(build_globset(&rules.include)?)
+        };
+
+        Ok(Self {
+            ignore_file,
+            include,
+            exclude: build_globset(&rules.exclude)?,
+            exclude_patterns: rules.exclude.clone(),
+            max_file_size: rules.max_file_size,
+            allowed_extensions: normalize_extensions(&rules.allowed_extensions),
+            blocked_extensions: normalize_extensions(&rules.blocked_extensions),
+        })
+    }
+
+    /// 检查单个条目 (不检查上级目录)，返回排除原因
+    ///
+    /// include、大小和扩展名规则只作用于文件，目录总是可以进入
+    pub fn check(&self, path: &str, is_dir: bool, size: u64) -> Option<ExcludeReason> {
+        let name = path.rsplit('/').next().unwrap_or(path);
+
+        if let Some(rule) = builtin_rule(name) {
+            return Some(ExcludeReason::Builtin { rule });
+        }
+
+        if let Some(gitignore) = &self.ignore_file {
+            if let Match::Ignore(glob) = gitignore.matched(path, is_dir) {
+                return Some(ExcludeReason::IgnoreFile {
+                    pattern: glob.original().to_string(),
+                });
+            }
+        }
+
+        if let Some(index) = self.exclude.matches(path).first() {
+            return Some(ExcludeReason::ExcludeGlob {
+                pattern: self.exclude_patterns[*index].clone(),
+            });
+        }
+
+        if is_dir {
+            return None;
+        }
+
+        if let Some(include) = &self.include {
+            if !include.is_match(path) {
+                return Some(ExcludeReason::NotIncluded);
+            }
+        }
+
+        if let Some(limit) = self.max_file_size {
+            if size > limit {
+                return Some(ExcludeReason::TooLarge { size, limit });
+            }
+        }
+
+        let extension = extension_of(name);
+        let not_allowed = !self.allowed_extensions.is_empty()
+            && !self.allowed_extensions.contains(&extension);
+        if not_allowed || self.blocked_extensions.contains(&extension) {
+            return Some(ExcludeReason::FileType { extension });
+        }
+
+        None
+    }
+
+    /// 检查条目及其所有上级目录 (用于扁平的远程列表)
+    pub fn check_with_parents(&self, path: &str, is_dir: bool, size: u64) -> Option<ExcludeReason> {
+        self.check_parents(path).or_else(|| self.check(path, is_dir, size))
+    }
+
+    /// 只检查上级目录
+    pub fn check_parents(&self, path: &str) -> Option<ExcludeReason> {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        (1..segments.len()).find_map(|depth| self.check(&segments[..depth].join("/"), true, 0))
+    }
+}
+
+/// 内置规则：隐藏文件、依赖/构建目录、编辑器临时文件
+fn builtin_rule(name: &str) -> Option<String> {
+    if name.starts_with('.') {
+        return Some("hidden".to_string());
+    }
+    if BUILTIN_NAMES.contains(&name) {
+        return Some(name.to_string());
+    }
+    BUILTIN_SUFFIXES
+        .iter()
+        .find(|suffix| name.ends_with(*suffix))
+        .map(|suffix| format!("*{}", suffix))
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet, AppError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| AppError::WebDAV(format!("Invalid sync pattern '{}': {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| AppError::WebDAV(format!("Invalid sync patterns: {}", e)))
+}
+
+/// 扩展名统一为小写、不带点
+fn normalize_extensions(extensions: &[String]) -> Vec<String> {
+    extensions
+        .iter()
+        .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect()
+}
+
+fn extension_of(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn filter(rules: SyncRules) -> SyncFilter {
+        SyncFilter::new(&rules, "/nonexistent-vault").unwrap()
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let f = filter(SyncRules::default());
+        assert_eq!(f.check(".obsidian", true, 0), Some(ExcludeReason::Builtin { rule: "hidden".to_string() }));
+        assert!(f.check("code/node_modules", true, 0).is_some());
+        assert!(f.check("draft.md.swp", false, 10).is_some());
+        assert_eq!(f.check("notes/a.md", false, 10), None);
+    }
+
+    #[test]
+    fn test_include_and_exclude_globs() {
+        let f = filter(SyncRules {
+            include: vec!["notes/**".to_string()],
+            exclude: vec!["**/*.pdf".to_string()],
+            ..SyncRules::default()
+        });
+
+        assert_eq!(f.check("notes/a.md", false, 1), None);
+        assert_eq!(
+            f.check("notes/paper.pdf", false, 1),
+            Some(ExcludeReason::ExcludeGlob { pattern: "**/*.pdf".to_string() })
+        );
+        assert_eq!(f.check("archive/a.md", false, 1), Some(ExcludeReason::NotIncluded));
+        // include 不作用于目录
+        assert_eq!(f.check("archive", true, 0), None);
+    }
+
+    #[test]
+    fn test_size_and_type_filters() {
+        let f = filter(SyncRules {
+            max_file_size: Some(1024),
+            allowed_extensions: vec!["md".to_string(), ".PNG".to_string()],
+            ..SyncRules::default()
+        });
+
+        assert_eq!(f.check("a.md", false, 100), None);
+        assert_eq!(f.check("b.png", false, 100), None);
+        assert_eq!(
+            f.check("big.md", false, 4096),
+            Some(ExcludeReason::TooLarge { size: 4096, limit: 1024 })
+        );
+        assert_eq!(
+            f.check("movie.mp4", false, 100),
+            Some(ExcludeReason::FileType { extension: "mp4".to_string() })
+        );
+
+        let f = filter(SyncRules {
+            blocked_extensions: vec!["mp4".to_string()],
+            ..SyncRules::default()
+        });
+        assert!(f.check("movie.MP4", false, 100).is_some());
+        assert_eq!(f.check("a.md", false, 100), None);
+    }
+
+    #[test]
+    fn test_ignore_file() {
+        let vault = tempfile::tempdir().unwrap();
+        fs::write(vault.path().join(IGNORE_FILE), "# comment\nprivate/\n*.log\n!keep.log\n").unwrap();
+        let f = SyncFilter::new(&SyncRules::default(), &vault.path().to_string_lossy()).unwrap();
+
+        assert_eq!(
+            f.check("private", true, 0),
+            Some(ExcludeReason::IgnoreFile { pattern: "private/".to_string() })
+        );
+        assert!(f.check("logs/app.log", false, 1).is_some());
+        assert_eq!(f.check("keep.log", false, 1), None);
+        assert_eq!(f.check("notes/a.md", false, 1), None);
+    }
+
+    #[test]
+    fn test_check_with_parents() {
+        let vault = tempfile::tempdir().unwrap();
+        fs::write(vault.path().join(IGNORE_FILE), "private/\n").unwrap();
+        let f = SyncFilter::new(&SyncRules::default(), &vault.path().to_string_lossy()).unwrap();
+
+        assert!(f.check("private/a.md", false, 1).is_none());
+        assert!(f.check_with_parents("private/a.md", false, 1).is_some());
+        assert!(f.check_with_parents("a/.git/config", false, 1).is_some());
+        assert!(f.check_with_parents("a/b/c.md", false, 1).is_none());
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let rules = SyncRules {
+            exclude: vec!["[".to_string()],
+            ..SyncRules::default()
+        };
+        assert!(SyncFilter::new(&rules, "/nonexistent-vault").is_err());
+    }
+}
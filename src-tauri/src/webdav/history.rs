@@ -0,0 +1,275 @@
+//! 同步历史与文件版本快照
+//!
+//! 每次同步的结果追加到 `.lumina-sync/history.jsonl`；下载或合并覆盖本地文件前，
+//! 旧内容保存到 `.lumina-sync/versions/<相对路径>/<时间戳>`，可随时列出和恢复
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use super::types::{NoteVersion, SyncHistoryEntry};
+use crate::error::AppError;
+
+/// 历史记录最多保留的条数
+const MAX_HISTORY_ENTRIES: usize = 500;
+/// 每个文件最多保留的版本数
+const MAX_VERSIONS_PER_FILE: usize = 20;
+
+fn history_path(vault_path: &str) -> PathBuf {
+    Path::new(vault_path).join(".lumina-sync/history.jsonl")
+}
+
+fn versions_dir(vault_path: &str, path: &str) -> PathBuf {
+    Path::new(vault_path).join(".lumina-sync/versions").join(path)
+}
+
+/// 追加一条同步历史
+pub fn append_history(vault_path: &str, entry: &SyncHistoryEntry) -> Result<(), AppError> {
+    let path = history_path(vault_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let line = serde_json::to_string(entry)
+        .map_err(|e| AppError::WebDAV(format!("Failed to serialize sync history: {}", e)))?;
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", line)?;
+    drop(file);
+
+    // 超出上限时只保留最近的记录
+    let content = fs::read_to_string(&path)?;
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.len() > MAX_HISTORY_ENTRIES {
+        let kept = lines[lines.len() - MAX_HISTORY_ENTRIES..].join("\n");
+        fs::write(&path, kept + "\n")?;
+    }
+    Ok(())
+}
+
+/// 读取同步历史 (最新的在前)，忽略无法解析的行
+pub fn load_history(vault_path: &str, limit: Option<usize>) -> Result<Vec<SyncHistoryEntry>, AppError> {
+    let content = match fs::read_to_string(history_path(vault_path)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+/// 保存文件当前内容为一个版本，文件不存在时不做任何事
+pub fn snapshot(vault_path: &str, path: &str) -> Result<Option<NoteVersion>, AppError> {
+    let source = Path::new(vault_path).join(path);
+    if !source.is_file() {
+        return Ok(None);
+    }
+
+    let dir = versions_dir(vault_path, path);
+    fs::create_dir_all(&dir)?;
+
+    let mut id = chrono::Local::now().format("%Y%m%d-%H%M%S%.3f").to_string();
+    while dir.join(&id).exists() {
+        id.push('_');
+    }
+    fs::copy(&source, dir.join(&id))?;
+    prune_versions(&dir)?;
+
+    Ok(read_version(path, &dir.join(&id)))
+}
+
+/// 列出文件的所有版本 (最新的在前)
+pub fn list_versions(vault_path: &str, path: &str) -> Result<Vec<NoteVersion>, AppError> {
+    validate_relative(path)?;
+    let dir = versions_dir(vault_path, path);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut versions: Vec<NoteVersion> = fs::read_dir(&dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .filter_map(|e| read_version(path, &e.path()))
+        .collect();
+    versions.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(versions)
+}
+
+/// 恢复指定版本；恢复前当前内容同样保存为一个版本，恢复操作本身可撤销
+pub fn restore_version(vault_path: &str, path: &str, version_id: &str) -> Result<(), AppError> {
+    validate_relative(path)?;
+    if version_id.is_empty() || version_id.contains(['/', '\\']) || version_id.starts_with('.') {
+        return Err(AppError::InvalidPath(format!("Invalid version id: {}", version_id)));
+    }
+
+    let version = versions_dir(vault_path, path).join(version_id);
+    if !version.is_file() {
+        return Err(AppError::FileNotFound(format!("{} @ {}", path, version_id)));
+    }
+    let content = fs::read(&version)?;
+
+    snapshot(vault_path, path)?;
+    let target = Path::new(vault_path).join(path);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(target, content)?;
+    Ok(())
+}
+
+fn read_version(path: &str, file: &Path) -> Option<NoteVersion> {
+    let metadata = file.metadata().ok()?;
+    Some(NoteVersion {
+        id: file.file_name()?.to_string_lossy().to_string(),
+        path: path.to_string(),
+        created_at: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        size: metadata.len(),
+    })
+}
+
+/// 删除超出上限的旧版本 (版本 id 按时间排序)
+fn prune_versions(dir: &Path) -> Result<(), AppError> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    if files.len() <= MAX_VERSIONS_PER_FILE {
+        return Ok(());
+    }
+    files.sort();
+    for old in &files[..files.len() - MAX_VERSIONS_PER_FILE] {
+        let _ = fs::remove_file(old);
+    }
+    Ok(())
+}
+
+/// 只接受 vault 内的相对路径
+fn validate_relative(path: &str) -> Result<(), AppError> {
+    let valid = !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InvalidPath(path.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webdav::types::SyncResult;
+
+    fn entry(started_at: u64) -> SyncHistoryEntry {
+        SyncHistoryEntry {
+            started_at,
+            result: Some(SyncResult {
+                success: true,
+                cancelled: false,
+                uploaded: 1,
+                downloaded: 0,
+                deleted: 0,
+                conflicts: 0,
+                merged: 0,
+                moved: 0,
+                trashed: Vec::new(),
+                affected: Vec::new(),
+                errors: Vec::new(),
+                duration_ms: 5,
+            }),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_history_newest_first() {
+        let vault = tempfile::tempdir().unwrap();
+        let vault = vault.path().to_string_lossy().to_string();
+
+        assert!(load_history(&vault, None).unwrap().is_empty());
+        for t in 1..=3 {
+            append_history(&vault, &entry(t)).unwrap();
+        }
+
+        let history = load_history(&vault, None).unwrap();
+        assert_eq!(history.iter().map(|e| e.started_at).collect::<Vec<_>>(), vec![3, 2, 1]);
+        assert_eq!(load_history(&vault, Some(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_entries_from_older_versions_load() {
+        let vault = tempfile::tempdir().unwrap();
+        let root = vault.path().to_string_lossy().to_string();
+        // 旧版本写入的记录没有 cancelled、merged、moved、trashed 和 affected
+        let old = r#"{"started_at":7,"result":{"success":true,"uploaded":2,"downloaded":0,"deleted":0,"conflicts":0,"errors":[],"duration_ms":3},"error":null}"#;
+        fs::create_dir_all(vault.path().join(".lumina-sync")).unwrap();
+        fs::write(history_path(&root), format!("{}\n", old)).unwrap();
+
+        let history = load_history(&root, None).unwrap();
+        assert_eq!(history.len(), 1);
+        let result = history[0].result.as_ref().unwrap();
+        assert_eq!(result.uploaded, 2);
+        assert!(!result.cancelled);
+        assert!(result.affected.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let vault = tempfile::tempdir().unwrap();
+        let root = vault.path().to_string_lossy().to_string();
+        fs::create_dir_all(vault.path().join("notes")).unwrap();
+        fs::write(vault.path().join("notes/a.md"), "v1").unwrap();
+
+        let v1 = snapshot(&root, "notes/a.md").unwrap().unwrap();
+        assert_eq!(v1.size, 2);
+        fs::write(vault.path().join("notes/a.md"), "v2").unwrap();
+
+        restore_version(&root, "notes/a.md", &v1.id).unwrap();
+        assert_eq!(fs::read_to_string(vault.path().join("notes/a.md")).unwrap(), "v1");
+
+        // 恢复前的内容也被保留
+        let versions = list_versions(&root, "notes/a.md").unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[1].id, v1.id);
+        let latest = fs::read_to_string(
+            vault.path().join(".lumina-sync/versions/notes/a.md").join(&versions[0].id),
+        )
+        .unwrap();
+        assert_eq!(latest, "v2");
+
+        assert!(snapshot(&root, "missing.md").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_versions_pruned() {
+        let vault = tempfile::tempdir().unwrap();
+        let root = vault.path().to_string_lossy().to_string();
+        fs::write(vault.path().join("a.md"), "x").unwrap();
+
+        for _ in 0..MAX_VERSIONS_PER_FILE + 3 {
+            snapshot(&root, "a.md").unwrap();
+        }
+        assert_eq!(list_versions(&root, "a.md").unwrap().len(), MAX_VERSIONS_PER_FILE);
+    }
+
+    #[test]
+    fn test_rejects_escaping_paths() {
+        let vault = tempfile::tempdir().unwrap();
+        let root = vault.path().to_string_lossy().to_string();
+
+        assert!(list_versions(&root, "../a.md").is_err());
+        assert!(restore_version(&root, "a.md", "../../etc").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! 本地/挂载目录存储后端
+//!
+//! 把另一个目录 (U 盘、网络共享、其他同步盘) 当作远程端。
+//! ETag 由 mtime 和大小生成，与 Apache mod_dav 一样在移动后保持不变
+
+use async_trait::async_trait;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+use super::storage::{self, RemoteStorage};
+use super::types::RemoteEntry;
+use crate::error::AppError;
+
+/// 本地目录存储
+pub struct LocalStorage {
+    /// 配置的目录 (必须存在，避免把未挂载的挂载点当作空的远程端)
+    base: PathBuf,
+    /// 同步根目录 (base 下的 remote_base_path)
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(path: &str, remote_base_path: &str) -> Self {
+        let base = PathBuf::from(path);
+        let root = base.join(storage::normalize(remote_base_path));
+        Self { base, root }
+    }
+
+    /// 确认配置的目录可用
+    fn check_available(&self) -> Result<(), AppError> {
+        if self.base.is_dir() {
+            Ok(())
+        } else {
+            Err(AppError::WebDAV(format!(
+                "Sync folder not available: {}",
+                self.base.display()
+            )))
+        }
+    }
+
+    /// 相对路径对应的绝对路径，拒绝 `..` 等越界路径
+    fn resolve(&self, path: &str) -> Result<PathBuf, AppError> {
+        let path = storage::normalize(path);
+        let relative = Path::new(path);
+        if !path.is_empty() && !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(AppError::InvalidPath(path.to_string()));
+        }
+        Ok(self.root.join(relative))
+    }
+
+    fn entry(&self, path: &Path, metadata: &fs::Metadata) -> Option<RemoteEntry> {
+        let relative = path
+            .strip_prefix(&self.root)
+            .ok()?
+            .to_string_lossy()
+            .replace('\\', "/");
+        let modified = metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok());
+        let is_dir = metadata.is_dir();
+
+        Some(RemoteEntry {
+            name: storage::file_name(&relative),
+            path: relative,
+            is_dir,
+            size: if is_dir { 0 } else { metadata.len() },
+            modified: modified.map(|d| d.as_secs()).unwrap_or(0),
+            etag: (!is_dir).then(|| {
+                format!(
+                    "\"{:x}-{:x}\"",
+                    modified.map(|d| d.as_nanos()).unwrap_or(0),
+                    metadata.len()
+                )
+            }),
+            content_type: None,
+        })
+    }
+}
+
+#[async_trait]
+impl RemoteStorage for LocalStorage {
+    async fn test_connection(&self) -> Result<bool, AppError> {
+        self.check_available()?;
+        Ok(true)
+    }
+
+    async fn list(&self, path: &str, recursive: bool) -> Result<Vec<RemoteEntry>, AppError> {
+        self.check_available()?;
+        let dir = self.resolve(path)?;
+        if !dir.exists() {
+            // 根目录尚未创建时视为空
+            if storage::normalize(path).is_empty() {
+                return Ok(Vec::new());
+            }
+            return Err(AppError::FileNotFound(dir.display().to_string()));
+        }
+
+        let walker = WalkDir::new(&dir)
+            .min_depth(1)
+            .max_depth(if recursive { usize::MAX } else { 1 })
+            .sort_by_file_name();
+
+        let mut entries = Vec::new();
+        for entry in walker {
+            let entry = entry.map_err(|e| AppError::WebDAV(format!("Failed to list folder: {}", e)))?;
+            let metadata = entry
+                .metadata()
+                .map_err(|e| AppError::WebDAV(format!("Failed to list folder: {}", e)))?;
+            entries.extend(self.entry(entry.path(), &metadata));
+        }
+        Ok(entries)
+    }
+
+    async fn get(&self, path: &str) -> Result<Vec<u8>, AppError> {
+        self.check_available()?;
+        let file = self.resolve(path)?;
+        fs::read(&file).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::FileNotFound(file.display().to_string()),
+            _ => e.into(),
+        })
+    }
+
+    async fn put(&self, path: &str, content: &[u8]) -> Result<Option<String>, AppError> {
+        self.check_available()?;
+        let file = self.resolve(path)?;
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // 先写临时文件再重命名，拔出设备时不会留下半个文件
+        let tmp = file.with_file_name(format!(".{}.tmp", storage::file_name(path)));
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, &file)?;
+
+        let metadata = fs::metadata(&file)?;
+        Ok(self.entry(&file, &metadata).and_then(|e| e.etag))
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), AppError> {
+        self.check_available()?;
+        fs::create_dir_all(self.resolve(path)?)?;
+        Ok(())
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), AppError> {
+        self.check_available()?;
+        let target = self.resolve(path)?;
+        let result = if target.is_dir() {
+            fs::remove_dir_all(&target)
+        } else {
+            fs::remove_file(&target)
+        };
+        match result {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn move_to(&self, from: &str, to: &str) -> Result<(), AppError> {
+        self.check_available()?;
+        let source = self.resolve(from)?;
+        let target = self.resolve(to)?;
+        if !source.exists() {
+            return Err(AppError::FileNotFound(source.display().to_string()));
+        }
+        if target.exists() {
+            return Err(AppError::FileExists(target.display().to_string()));
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(source, target)?;
+        Ok(())
+    }
+
+    async fn metadata(&self, path: &str) -> Result<Option<RemoteEntry>, AppError> {
+        self.check_available()?;
+        let target = self.resolve(path)?;
+        match fs::metadata(&target) {
+            Ok(metadata) => Ok(self.entry(&target, &metadata)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(&dir.path().to_string_lossy(), "/notes");
+
+        assert!(storage.list("", true).await.unwrap().is_empty());
+        let etag = storage.put("daily/a.md", b"alpha").await.unwrap();
+        assert!(etag.is_some());
+        assert_eq!(storage.get("daily/a.md").await.unwrap(), b"alpha");
+        assert_eq!(fs::read(dir.path().join("notes/daily/a.md")).unwrap(), b"alpha");
+
+        let listed: Vec<(String, bool)> = storage
+            .list("", true)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.path, e.is_dir))
+            .collect();
+        assert_eq!(listed, vec![("daily".to_string(), true), ("daily/a.md".to_string(), false)]);
+
+        // 移动后 ETag 不变
+        storage.move_to("daily/a.md", "b.md").await.unwrap();
+        assert_eq!(storage.metadata("b.md").await.unwrap().unwrap().etag, etag);
+        assert!(storage.metadata("daily/a.md").await.unwrap().is_none());
+        assert!(storage.move_to("missing.md", "c.md").await.is_err());
+
+        storage.delete("daily").await.unwrap();
+        storage.delete("daily").await.unwrap();
+        assert_eq!(storage.list("", false).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_missing_folder_and_escapes() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = LocalStorage::new(&dir.path().join("unmounted").to_string_lossy(), "/");
+        assert!(missing.test_connection().await.is_err());
+        assert!(missing.list("", true).await.is_err());
+
+        let storage = LocalStorage::new(&dir.path().to_string_lossy(), "/");
+        assert!(storage.put("../escape.md", b"x").await.is_err());
+        assert!(!dir.path().parent().unwrap().join("escape.md").exists());
+    }
+}
@@ -2,14 +2,36 @@
 //! 
 //! 提供 WebDAV 同步功能，包括：
 //! - 客户端：HTTP 请求封装
+//! - 认证：Basic / Digest / Bearer 与 WWW-Authenticate 质询处理
+//! - 存储：远程存储抽象，WebDAV 之外还支持 S3 兼容存储和本地/挂载目录
+//! - PROPFIND：命名空间感知的 multistatus 解析
 //! - 同步：本地优先的双向同步逻辑
+//! - 合并：文本文件冲突的三方合并
+//! - 加密：可选的客户端端到端加密
+//! - 过滤：include/exclude 规则与 `.luminasyncignore`
+//! - 历史：同步记录与文件版本快照
+//! - 配置：按 vault 持久化的多个命名同步配置
+//! - 调度：按间隔及本地变化触发的自动同步
 //! - 命令：Tauri 命令接口
 
 pub mod types;
+pub mod auth;
 pub mod client;
+pub mod storage;
+pub mod s3;
+pub mod local;
+pub mod propfind;
 pub mod sync;
+pub mod crypto;
+pub mod filter;
+pub mod history;
+pub mod profiles;
+pub mod scheduler;
 pub mod commands;
 
+#[cfg(test)]
+mod test_server;
+
 // Re-exports for internal use
 pub(crate) use types::*;
 pub(crate) use client::WebDAVClient;
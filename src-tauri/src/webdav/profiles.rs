@@ -0,0 +1,266 @@
+//! 同步配置持久化
+//!
+//! 每个 vault 可保存多个命名配置，存放在应用数据目录的 `webdav-profiles.json`，
+//! 不写入 vault 本身 (避免密码随笔记被同步或提交)。
+//! 返回给前端的配置会隐去密码等密钥，保存时密钥留空表示沿用已保存的值
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::types::*;
+use crate::error::AppError;
+
+const PROFILES_FILE: &str = "webdav-profiles.json";
+
+/// 配置文件内容，按 vault 路径分组
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfilesFile {
+    #[serde(default)]
+    vaults: BTreeMap<String, VaultProfiles>,
+}
+
+/// 同步配置存储
+pub struct ProfileStore {
+    path: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(app_data_dir: &Path) -> Self {
+        Self {
+            path: app_data_dir.join(PROFILES_FILE),
+        }
+    }
+
+    /// 列出 vault 的配置 (已隐去密钥)
+    pub fn list(&self, vault_path: &str) -> Result<VaultProfiles, AppError> {
+        let mut vault = self.load()?.vaults.remove(&vault_key(vault_path)).unwrap_or_default();
+        for profile in &mut vault.profiles {
+            redact(&mut profile.config);
+        }
+        Ok(vault)
+    }
+
+    /// 获取完整配置 (含密钥)，id 为 None 时返回当前使用的配置
+    pub fn get(&self, vault_path: &str, id: Option<&str>) -> Result<SyncProfile, AppError> {
+        let mut vault = self.load()?.vaults.remove(&vault_key(vault_path)).unwrap_or_default();
+        let id = match id {
+            Some(id) => id.to_string(),
+            None => vault
+                .active
+                .clone()
+                .ok_or_else(|| AppError::WebDAV("No sync profile configured".to_string()))?,
+        };
+        let index = vault
+            .profiles
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| AppError::WebDAV(format!("Sync profile not found: {}", id)))?;
+        Ok(vault.profiles.swap_remove(index))
+    }
+
+    /// 新建或更新配置，返回保存后的配置 (已隐去密钥)
+    ///
+    /// vault 的第一个配置自动成为当前配置
+    pub fn save(&self, vault_path: &str, mut profile: SyncProfile) -> Result<SyncProfile, AppError> {
+        let mut file = self.load()?;
+        let vault = file.vaults.entry(vault_key(vault_path)).or_default();
+
+        match vault.profiles.iter_mut().find(|p| !profile.id.is_empty() && p.id == profile.id) {
+            Some(existing) => {
+                keep_secrets(&mut profile.config, &existing.config);
+                *existing = profile.clone();
+            }
+            None if !profile.id.is_empty() => {
+                return Err(AppError::WebDAV(format!("Sync profile not found: {}", profile.id)));
+            }
+            None => {
+                profile.id = new_id(vault);
+                vault.profiles.push(profile.clone());
+            }
+        }
+        if vault.active.is_none() {
+            vault.active = Some(profile.id.clone());
+        }
+
+        self.store(&file)?;
+        redact(&mut profile.config);
+        Ok(profile)
+    }
+
+    /// 删除配置，删除当前配置时改用剩下的第一个
+    pub fn delete(&self, vault_path: &str, id: &str) -> Result<(), AppError> {
+        let mut file = self.load()?;
+        let key = vault_key(vault_path);
+        let Some(vault) = file.vaults.get_mut(&key) else {
+            return Ok(());
+        };
+
+        vault.profiles.retain(|p| p.id != id);
+        if vault.active.as_deref() == Some(id) {
+            vault.active = vault.profiles.first().map(|p| p.id.clone());
+        }
+        if vault.profiles.is_empty() {
+            file.vaults.remove(&key);
+        }
+        self.store(&file)
+    }
+
+    /// 切换当前使用的配置
+    pub fn set_active(&self, vault_path: &str, id: &str) -> Result<(), AppError> {
+        let mut file = self.load()?;
+        let vault = file
+            .vaults
+            .get_mut(&vault_key(vault_path))
+            .filter(|v| v.profiles.iter().any(|p| p.id == id))
+            .ok_or_else(|| AppError::WebDAV(format!("Sync profile not found: {}", id)))?;
+        vault.active = Some(id.to_string());
+        self.store(&file)
+    }
+
+    fn load(&self) -> Result<ProfilesFile, AppError> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| AppError::WebDAV(format!("Invalid sync profiles file: {}", e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ProfilesFile::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn store(&self, file: &ProfilesFile) -> Result<(), AppError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(file)
+            .map_err(|e| AppError::WebDAV(format!("Failed to serialize sync profiles: {}", e)))?;
+
+        // 先写临时文件再重命名，写入中途退出不会破坏已有配置
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, content)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            // 文件含密码，仅当前用户可读
+            fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600))?;
+        }
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// vault 路径作为键 (去掉末尾分隔符，避免同一 vault 出现两份配置)
+fn vault_key(vault_path: &str) -> String {
+    let trimmed = vault_path.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() { vault_path } else { trimmed }.to_string()
+}
+
+/// 生成 vault 内唯一的配置 ID
+fn new_id(vault: &VaultProfiles) -> String {
+    let mut stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    while vault.profiles.iter().any(|p| p.id == format!("{:x}", stamp)) {
+        stamp += 1;
+    }
+    format!("{:x}", stamp)
+}
+
+/// 清空密钥字段
+fn redact(config: &mut WebDAVConfig) {
+    config.password.clear();
+    if let Some(encryption) = &mut config.encryption {
+        encryption.passphrase.clear();
+    }
+    if let StorageBackend::S3(s3) = &mut config.backend {
+        s3.secret_key.clear();
+    }
+}
+
+/// 留空的密钥字段沿用已保存的值
+fn keep_secrets(config: &mut WebDAVConfig, saved: &WebDAVConfig) {
+    if config.password.is_empty() {
+        config.password = saved.password.clone();
+    }
+    if let (Some(encryption), Some(saved)) = (&mut config.encryption, &saved.encryption) {
+        if encryption.passphrase.is_empty() {
+            encryption.passphrase = saved.passphrase.clone();
+        }
+    }
+    if let (StorageBackend::S3(s3), StorageBackend::S3(saved)) = (&mut config.backend, &saved.backend) {
+        if s3.secret_key.is_empty() {
+            s3.secret_key = saved.secret_key.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, password: &str) -> SyncProfile {
+        SyncProfile {
+            id: String::new(),
+            name: name.to_string(),
+            config: WebDAVConfig {
+                server_url: format!("https://{}.example.com/dav", name),
+                username: "user".to_string(),
+                password: password.to_string(),
+                ..WebDAVConfig::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_profiles_per_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProfileStore::new(dir.path());
+
+        let home = store.save("/notes/", profile("home", "secret")).unwrap();
+        assert!(!home.id.is_empty());
+        assert!(home.config.password.is_empty());
+        let office = store.save("/notes", profile("office", "hunter2")).unwrap();
+        assert_ne!(home.id, office.id);
+
+        // 第一个配置成为当前配置，列表中不含密码
+        let listed = store.list("/notes").unwrap();
+        assert_eq!(listed.active.as_deref(), Some(home.id.as_str()));
+        assert_eq!(listed.profiles.len(), 2);
+        assert!(listed.profiles.iter().all(|p| p.config.password.is_empty()));
+        assert!(store.list("/other").unwrap().profiles.is_empty());
+
+        // 新实例读取同一文件
+        let store = ProfileStore::new(dir.path());
+        assert_eq!(store.get("/notes", None).unwrap().config.password, "secret");
+        store.set_active("/notes", &office.id).unwrap();
+        assert_eq!(store.get("/notes", None).unwrap().name, "office");
+        assert!(store.set_active("/other", &office.id).is_err());
+
+        store.delete("/notes", &office.id).unwrap();
+        assert_eq!(store.get("/notes", None).unwrap().id, home.id);
+        store.delete("/notes", &home.id).unwrap();
+        assert!(store.get("/notes", None).is_err());
+    }
+
+    #[test]
+    fn test_blank_secrets_keep_saved_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProfileStore::new(dir.path());
+
+        let mut saved = store.save("/notes", profile("home", "secret")).unwrap();
+        saved.name = "renamed".to_string();
+        store.save("/notes", saved.clone()).unwrap();
+        let full = store.get("/notes", Some(&saved.id)).unwrap();
+        assert_eq!(full.name, "renamed");
+        assert_eq!(full.config.password, "secret");
+
+        saved.config.password = "changed".to_string();
+        store.save("/notes", saved.clone()).unwrap();
+        assert_eq!(store.get("/notes", Some(&saved.id)).unwrap().config.password, "changed");
+
+        saved.id = "missing".to_string();
+        assert!(store.save("/notes", saved).is_err());
+    }
+}
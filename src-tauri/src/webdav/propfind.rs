@@ -0,0 +1,287 @@
+//! PROPFIND 响应解析
+//!
+//! 基于 quick-xml 的命名空间感知解析：按 `DAV:` 命名空间匹配元素，
+//! 不依赖具体前缀 (`D:`、`d:`、`ns0:`、`lp1:` 或默认命名空间)
+
+use quick_xml::events::Event;
+use quick_xml::name::{Namespace, ResolveResult};
+use quick_xml::NsReader;
+
+use crate::error::AppError;
+
+const DAV_NS: &[u8] = b"DAV:";
+
+/// 单个 `<response>` 的解析结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DavResponse {
+    /// 原始 href (未解码)
+    pub href: String,
+    /// response 级别的状态码 (整个资源不可用时服务器会直接给出，如 404)
+    pub status: Option<u16>,
+    /// 合并自所有 2xx propstat 的属性
+    pub props: DavProps,
+}
+
+/// 我们关心的 DAV 属性
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DavProps {
+    pub is_collection: bool,
+    pub content_length: Option<u64>,
+    pub last_modified: Option<String>,
+    pub etag: Option<String>,
+    pub content_type: Option<String>,
+}
+
+impl DavResponse {
+    /// 资源本身是否可用 (没有 response 级别状态，或状态为 2xx)
+    pub fn is_ok(&self) -> bool {
+        self.status.map(is_success).unwrap_or(true)
+    }
+}
+
+/// 解析 multistatus 响应体
+pub fn parse_multistatus(xml: &str) -> Result<Vec<DavResponse>, AppError> {
+    let mut reader = NsReader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut responses = Vec::new();
+    // 当前元素路径 (DAV 元素记录本地名，其他命名空间记录为 None)
+    let mut stack: Vec<Option<String>> = Vec::new();
+    let mut text = String::new();
+
+    let mut response: Option<DavResponse> = None;
+    let mut propstat_props = DavProps::default();
+    let mut propstat_status: Option<u16> = None;
+
+    loop {
+        let (ns, event) = reader
+            .read_resolved_event()
+            .map_err(|e| AppError::WebDAV(format!("Invalid PROPFIND XML: {}", e)))?;
+
+        match event {
+            Event::Start(e) => {
+                let name = dav_local_name(&ns, e.local_name().as_ref());
+                match name.as_deref() {
+                    Some("response") => response = Some(DavResponse::default()),
+                    Some("propstat") => {
+                        propstat_props = DavProps::default();
+                        propstat_status = None;
+                    }
+                    Some("collection") if in_element(&stack, "resourcetype") => {
+                        propstat_props.is_collection = true;
+                    }
+                    _ => {}
+                }
+                stack.push(name);
+                text.clear();
+            }
+            Event::Empty(e) => {
+                let name = dav_local_name(&ns, e.local_name().as_ref());
+                if name.as_deref() == Some("collection") && in_element(&stack, "resourcetype") {
+                    propstat_props.is_collection = true;
+                }
+            }
+            Event::Text(e) => {
+                let value = e
+                    .unescape()
+                    .map_err(|e| AppError::WebDAV(format!("Invalid PROPFIND XML: {}", e)))?;
+                text.push_str(&value);
+            }
+            Event::CData(e) => {
+                text.push_str(&String::from_utf8_lossy(&e));
+            }
+            Event::End(_) => {
+                let name = stack.pop().flatten();
+                let parent = stack.last().cloned().flatten();
+                let value = text.trim().to_string();
+                text.clear();
+
+                match (name.as_deref(), parent.as_deref()) {
+                    (Some("response"), _) => {
+                        if let Some(r) = response.take() {
+                            responses.push(r);
+                        }
+                    }
+                    (Some("href"), Some("response")) => {
+                        if let Some(r) = response.as_mut() {
+                            r.href = value;
+                        }
+                    }
+                    (Some("status"), Some("response")) => {
+                        if let Some(r) = response.as_mut() {
+                            r.status = parse_status_line(&value);
+                        }
+                    }
+                    (Some("status"), Some("propstat")) => {
+                        propstat_status = parse_status_line(&value);
+                    }
+                    // 仅采纳成功的 propstat，404 等表示属性不存在
+                    (Some("propstat"), _) if propstat_status.map(is_success).unwrap_or(true) => {
+                        if let Some(r) = response.as_mut() {
+                            merge_props(&mut r.props, std::mem::take(&mut propstat_props));
+                        }
+                    }
+                    (Some(prop), Some("prop")) if !value.is_empty() => match prop {
+                        "getcontentlength" => propstat_props.content_length = value.parse().ok(),
+                        "getlastmodified" => propstat_props.last_modified = Some(value),
+                        "getetag" => propstat_props.etag = Some(value),
+                        "getcontenttype" => propstat_props.content_type = Some(value),
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(responses)
+}
+
+/// 若元素属于 DAV 命名空间，返回其本地名
+fn dav_local_name(ns: &ResolveResult, local: &[u8]) -> Option<String> {
+    match ns {
+        ResolveResult::Bound(Namespace(uri)) if *uri == DAV_NS => {
+            Some(String::from_utf8_lossy(local).into_owned())
+        }
+        _ => None,
+    }
+}
+
+fn in_element(stack: &[Option<String>], name: &str) -> bool {
+    stack.last().and_then(|n| n.as_deref()) == Some(name)
+}
+
+fn merge_props(target: &mut DavProps, source: DavProps) {
+    target.is_collection |= source.is_collection;
+    if source.content_length.is_some() {
+        target.content_length = source.content_length;
+    }
+    if source.last_modified.is_some() {
+        target.last_modified = source.last_modified;
+    }
+    if source.etag.is_some() {
+        target.etag = source.etag;
+    }
+    if source.content_type.is_some() {
+        target.content_type = source.content_type;
+    }
+}
+
+/// 解析 "HTTP/1.1 200 OK" 形式的状态行
+fn parse_status_line(line: &str) -> Option<u16> {
+    line.split_whitespace().nth(1).and_then(|code| code.parse().ok())
+}
+
+fn is_success(code: u16) -> bool {
+    (200..300).contains(&code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(responses: &'a [DavResponse], suffix: &str) -> &'a DavResponse {
+        responses
+            .iter()
+            .find(|r| r.href.ends_with(suffix))
+            .unwrap_or_else(|| panic!("no response for {}", suffix))
+    }
+
+    #[test]
+    fn test_parse_nextcloud() {
+        let responses = parse_multistatus(include_str!("fixtures/propfind_nextcloud.xml")).unwrap();
+        assert_eq!(responses.len(), 3);
+
+        let root = find(&responses, "/notes/");
+        assert!(root.props.is_collection);
+
+        let note = find(&responses, "Daily%20Note.md");
+        assert!(!note.props.is_collection);
+        assert_eq!(note.props.content_length, Some(1342));
+        assert_eq!(note.props.etag.as_deref(), Some("\"6f1b7c2a9e0d4\""));
+        assert_eq!(note.props.content_type.as_deref(), Some("text/markdown"));
+
+        // 404 propstat 中的属性不应被采纳
+        let dir = find(&responses, "/Projects/");
+        assert!(dir.props.is_collection);
+        assert_eq!(dir.props.content_length, None);
+    }
+
+    #[test]
+    fn test_parse_apache_mod_dav() {
+        let responses = parse_multistatus(include_str!("fixtures/propfind_apache.xml")).unwrap();
+        assert_eq!(responses.len(), 3);
+
+        let file = find(&responses, "readme.md");
+        assert_eq!(file.props.content_length, Some(42));
+        assert_eq!(
+            file.props.last_modified.as_deref(),
+            Some("Tue, 03 Dec 2024 10:30:00 GMT")
+        );
+        assert!(find(&responses, "/archive/").props.is_collection);
+    }
+
+    #[test]
+    fn test_parse_ns0_prefix() {
+        let responses = parse_multistatus(include_str!("fixtures/propfind_synology.xml")).unwrap();
+        assert_eq!(responses.len(), 3);
+        assert!(find(&responses, "/vault/").props.is_collection);
+
+        let file = find(&responses, "%E4%B8%AD%E6%96%87.md");
+        assert_eq!(file.props.content_length, Some(2048));
+        assert_eq!(file.props.etag.as_deref(), Some("\"1733221800-2048\""));
+    }
+
+    #[test]
+    fn test_parse_default_namespace() {
+        let responses = parse_multistatus(include_str!("fixtures/propfind_iis.xml")).unwrap();
+        assert_eq!(responses.len(), 2);
+
+        let file = find(&responses, "todo.txt");
+        assert!(!file.props.is_collection);
+        assert_eq!(file.props.content_length, Some(7));
+        assert_eq!(file.props.content_type.as_deref(), Some("text/plain"));
+    }
+
+    #[test]
+    fn test_response_level_status() {
+        let xml = r#"<?xml version="1.0"?>
+<multistatus xmlns="DAV:">
+  <response>
+    <href>/dav/gone.md</href>
+    <status>HTTP/1.1 404 Not Found</status>
+  </response>
+</multistatus>"#;
+        let responses = parse_multistatus(xml).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].status, Some(404));
+        assert!(!responses[0].is_ok());
+    }
+
+    #[test]
+    fn test_ignores_foreign_namespaces() {
+        // 非 DAV 命名空间中的同名元素不应被当作 DAV 属性
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:x="urn:example">
+  <d:response>
+    <d:href>/dav/a.md</d:href>
+    <d:propstat>
+      <d:prop>
+        <x:getcontentlength>999</x:getcontentlength>
+        <d:getcontentlength>10</d:getcontentlength>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        let responses = parse_multistatus(xml).unwrap();
+        assert_eq!(responses[0].props.content_length, Some(10));
+    }
+
+    #[test]
+    fn test_invalid_xml() {
+        assert!(parse_multistatus("<d:multistatus xmlns:d=\"DAV:\"><d:response></d:multistatus>").is_err());
+    }
+}
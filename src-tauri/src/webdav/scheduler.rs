@@ -0,0 +1,178 @@
+//! 自动同步调度器
+//!
+//! 按 `sync_interval_secs` 定时执行快速同步，并在本地文件变化后防抖触发。
+//! 文件变化来自 vault 的文件监听 (订阅 `ChangeListeners`)；没有窗口监听该 vault 时
+//! 由调用方启动一个只通知订阅者的监听。同步自身写入的文件不会再次触发。
+//! 与手动同步共用同一把锁，保证同一时间只有一次同步在运行
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use super::sync::{SyncEngine, SYNC_WRITER};
+use super::types::*;
+use crate::fs::events::{is_own_write_by, ChangeFeed, ChangeListeners, ChangeSubscription};
+
+/// 本地变化后等待的静默时间
+const DEBOUNCE: Duration = Duration::from_secs(5);
+/// 最短同步间隔，避免配置为 0 时持续请求服务器
+const MIN_INTERVAL_SECS: u64 = 30;
+
+/// 运行中的自动同步，drop 时停止
+pub struct AutoSyncScheduler {
+    task: JoinHandle<()>,
+    _subscription: ChangeSubscription,
+    /// 没有窗口监听 vault 时为调度器启动的监听
+    _feed: Option<ChangeFeed>,
+}
+
+impl AutoSyncScheduler {
+    /// 启动调度器
+    ///
+    /// 每次运行前都会重新读取配置，修改间隔或关闭 `auto_sync` 无需重启。
+    /// 存在被中断的同步时立即触发一次，以恢复未完成的条目。
+    /// `feed` 为调度器专用的监听 (没有窗口监听该 vault 时)，随调度器一起停止
+    pub fn start(
+        config: Arc<Mutex<Option<WebDAVConfig>>>,
+        sync_lock: Arc<tokio::sync::Mutex<()>>,
+        cancel: Arc<AtomicBool>,
+        vault_path: String,
+        progress: ProgressSink,
+        listeners: &ChangeListeners,
+        feed: Option<ChangeFeed>,
+    ) -> Self {
+        let trigger = Arc::new(Notify::new());
+        // 有未同步的本地变化；同步进行中发生的变化在锁释放后再同步一次
+        let pending = Arc::new(AtomicBool::new(false));
+        if SyncEngine::pending_plan(&vault_path).is_some() {
+            pending.store(true, Ordering::SeqCst);
+            trigger.notify_one();
+        }
+
+        let subscription = {
+            let trigger = trigger.clone();
+            let pending = pending.clone();
+            let vault = PathBuf::from(&vault_path);
+            listeners.subscribe(Path::new(&vault_path), move |paths| {
+                // 同步自身刚写入的文件不算本地变化，否则每次同步后都会再触发一轮
+                if paths
+                    .iter()
+                    .any(|p| is_vault_content(&vault, p) && !is_own_write_by(p, SYNC_WRITER))
+                {
+                    pending.store(true, Ordering::SeqCst);
+                    trigger.notify_one();
+                }
+            })
+        };
+
+        let task = tokio::spawn(run(config, sync_lock, cancel, vault_path, trigger, pending, progress));
+
+        Self {
+            task,
+            _subscription: subscription,
+            _feed: feed,
+        }
+    }
+}
+
+impl Drop for AutoSyncScheduler {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run(
+    config: Arc<Mutex<Option<WebDAVConfig>>>,
+    sync_lock: Arc<tokio::sync::Mutex<()>>,
+    cancel: Arc<AtomicBool>,
+    vault_path: String,
+    trigger: Arc<Notify>,
+    pending: Arc<AtomicBool>,
+    progress: ProgressSink,
+) {
+    loop {
+        let interval_secs = config
+            .lock()
+            .ok()
+            .and_then(|c| c.as_ref().map(|c| c.sync_interval_secs))
+            .unwrap_or(0)
+            .max(MIN_INTERVAL_SECS);
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(interval_secs)) => {}
+            _ = trigger.notified() => {
+                // 防抖：直到文件停止变化 DEBOUNCE 后才同步
+                loop {
+                    tokio::select! {
+                        _ = tokio::time::sleep(DEBOUNCE) => break,
+                        _ = trigger.notified() => continue,
+                    }
+                }
+            }
+        }
+
+        let Some(config) = config.lock().ok().and_then(|c| c.clone()) else {
+            continue;
+        };
+        if !config.auto_sync {
+            continue;
+        }
+        // 有本地变化时等待正在运行的同步 (手动或上一次自动同步) 结束后再同步；
+        // 仅定时触发时跳过本轮
+        let _guard = if pending.load(Ordering::SeqCst) {
+            sync_lock.lock().await
+        } else {
+            match sync_lock.try_lock() {
+                Ok(guard) => guard,
+                Err(_) => continue,
+            }
+        };
+
+        // 从这里开始的本地变化会再次触发一轮
+        pending.store(false, Ordering::SeqCst);
+        cancel.store(false, Ordering::SeqCst);
+        run_once(config, &vault_path, cancel.clone(), &progress).await;
+    }
+}
+
+/// 执行一次快速同步 (进度与结果由引擎通过 progress 报告)
+async fn run_once(
+    config: WebDAVConfig,
+    vault_path: &str,
+    cancel: Arc<AtomicBool>,
+    progress: &ProgressSink,
+) {
+    let result = match SyncEngine::new(config, vault_path.to_string()) {
+        Ok(engine) => {
+            engine
+                .with_progress(progress.clone())
+                .with_cancel(cancel)
+                .quick_sync()
+                .await
+        }
+        Err(e) => {
+            let mut failed = SyncProgress::new(SyncStage::Error);
+            failed.error = Some(e.to_string());
+            progress(failed);
+            Err(e)
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("[WebDAV] Auto sync failed: {}", e);
+    }
+}
+
+/// 是否为需要同步的 vault 内容 (排除隐藏文件，包括同步状态和回收站)
+fn is_vault_content(vault: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(vault) else {
+        return false;
+    };
+    relative.components().all(|c| match c {
+        Component::Normal(name) => !name.to_string_lossy().starts_with('.'),
+        _ => true,
+    })
+}
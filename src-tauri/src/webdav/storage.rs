@@ -0,0 +1,68 @@
+//! 远程存储抽象
+//!
+//! 同步引擎只依赖 `RemoteStorage`，WebDAV、S3 兼容存储和本地/挂载目录
+//! 共用同一套计划、合并与冲突逻辑。路径均为相对于远程根目录的 `/` 分隔路径
+
+use async_trait::async_trait;
+
+use super::client::WebDAVClient;
+use super::local::LocalStorage;
+use super::s3::S3Storage;
+use super::types::{RemoteEntry, StorageBackend, WebDAVConfig};
+use crate::error::AppError;
+
+/// 远程存储后端
+#[async_trait]
+pub trait RemoteStorage: Send + Sync {
+    /// 测试能否访问远程根目录
+    async fn test_connection(&self) -> Result<bool, AppError>;
+
+    /// 列出目录内容，recursive 为 true 时包含所有后代
+    async fn list(&self, path: &str, recursive: bool) -> Result<Vec<RemoteEntry>, AppError>;
+
+    /// 读取文件内容
+    async fn get(&self, path: &str) -> Result<Vec<u8>, AppError>;
+
+    /// 写入文件，返回新的 ETag (如果后端提供)
+    async fn put(&self, path: &str, content: &[u8]) -> Result<Option<String>, AppError>;
+
+    /// 创建目录 (包括所有上级目录)
+    async fn mkdir(&self, path: &str) -> Result<(), AppError>;
+
+    /// 删除文件或目录 (递归)，不存在时视为成功
+    async fn delete(&self, path: &str) -> Result<(), AppError>;
+
+    /// 移动/重命名，目标已存在时失败
+    async fn move_to(&self, from: &str, to: &str) -> Result<(), AppError>;
+
+    /// 获取文件/目录属性，不存在时返回 None
+    async fn metadata(&self, path: &str) -> Result<Option<RemoteEntry>, AppError>;
+}
+
+/// 按配置创建对应的存储后端
+pub fn connect(config: &WebDAVConfig) -> Result<Box<dyn RemoteStorage>, AppError> {
+    Ok(match &config.backend {
+        StorageBackend::WebDav => Box::new(WebDAVClient::new(config.clone())?),
+        StorageBackend::S3(s3) => Box::new(S3Storage::new(s3.clone(), &config.remote_base_path)?),
+        StorageBackend::Local { path } => Box::new(LocalStorage::new(path, &config.remote_base_path)),
+    })
+}
+
+/// 规范化相对路径 (去掉首尾 `/`)
+pub(crate) fn normalize(path: &str) -> &str {
+    path.trim_matches('/')
+}
+
+/// 拼接相对路径
+pub(crate) fn join(base: &str, path: &str) -> String {
+    match (normalize(base), normalize(path)) {
+        ("", path) => path.to_string(),
+        (base, "") => base.to_string(),
+        (base, path) => format!("{}/{}", base, path),
+    }
+}
+
+/// 路径的最后一段
+pub(crate) fn file_name(path: &str) -> String {
+    normalize(path).rsplit('/').next().unwrap_or("").to_string()
+}
@@ -2,35 +2,107 @@
 //! 
 //! 实现本地优先的双向同步逻辑
 
-use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH, Instant};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use walkdir::WalkDir;
 
-use super::client::WebDAVClient;
+use super::crypto::{self, E2EEManifest, VaultCipher};
+use super::filter::SyncFilter;
+use super::history;
+use super::storage::{self, RemoteStorage};
 use super::types::*;
 use crate::error::AppError;
+use crate::fs::events::{as_window, record_own_write};
+use crate::fs::merge::merge3;
+
+/// 同步写入本地文件时的写入者标识 (不是任何窗口的 label)：
+/// 各窗口照常收到这些变化，自动同步调度器据此忽略同步自身的写入
+pub const SYNC_WRITER: &str = "webdav-sync";
+
+/// 记录同步刚刚修改的本地路径
+fn record_sync_write(path: &Path) {
+    as_window(SYNC_WRITER, || record_own_write(path));
+}
 
 /// 同步引擎
 pub struct SyncEngine {
-    client: WebDAVClient,
+    storage: Box<dyn RemoteStorage>,
     vault_path: String,
     state: Option<SyncState>,
+    remote_delete_mode: RemoteDeleteMode,
+    progress: Option<ProgressSink>,
+    cancel: Option<Arc<AtomicBool>>,
+    encryption: Option<EncryptionConfig>,
+    /// 解锁后的加密器 (未启用加密时为 None)
+    cipher: Option<VaultCipher>,
+    filter: SyncFilter,
+}
+
+/// 断点日志的一行：已完成条目的新记录 (None 表示不再跟踪)
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    path: String,
+    record: Option<FileRecord>,
 }
 
 impl SyncEngine {
     /// 创建新的同步引擎
     pub fn new(config: WebDAVConfig, vault_path: String) -> Result<Self, AppError> {
-        let client = WebDAVClient::new(config)?;
+        let remote_delete_mode = config.remote_delete_mode;
+        let encryption = config.encryption.clone();
+        let filter = SyncFilter::new(&config.rules, &vault_path)?;
+        let storage = storage::connect(&config)?;
         Ok(Self {
-            client,
+            storage,
             vault_path,
             state: None,
+            remote_delete_mode,
+            progress: None,
+            cancel: None,
+            encryption,
+            cipher: None,
+            filter,
         })
     }
 
-    /// 加载同步状态
+    /// 设置进度回调
+    pub fn with_progress(mut self, progress: ProgressSink) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// 设置取消标志，置位后在当前条目完成时停止
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|c| c.load(Ordering::SeqCst))
+    }
+
+    /// 报告进度
+    fn report(&self, progress: SyncProgress) {
+        if let Some(sink) = &self.progress {
+            sink(progress);
+        }
+    }
+
+    /// 报告错误阶段
+    fn report_error(&self, error: &AppError) {
+        let mut progress = SyncProgress::new(SyncStage::Error);
+        progress.error = Some(error.to_string());
+        self.report(progress);
+    }
+
+    /// 加载同步状态，并重放上次中断时留下的断点日志
     pub fn load_state(&mut self) -> Result<(), AppError> {
         let state_path = self.state_file_path();
         if Path::new(&state_path).exists() {
@@ -39,17 +111,36 @@ impl SyncEngine {
             self.state = serde_json::from_str(&content)
                 .map_err(|e| AppError::WebDAV(format!("Failed to parse sync state: {}", e)))?;
         }
+
+        // 最后一行可能在写入时被中断，无法解析的行忽略
+        let journal = match fs::read_to_string(self.checkpoint_log_path()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        for checkpoint in journal.lines().filter_map(|line| serde_json::from_str::<Checkpoint>(line).ok()) {
+            self.apply_checkpoint(&checkpoint.path, checkpoint.record);
+        }
         Ok(())
     }
 
     /// 保存同步状态
+    ///
+    /// 先写临时文件再重命名，同步中途退出也不会留下损坏的状态文件。
+    /// 写入后断点日志已包含在状态中，随之清空
     pub fn save_state(&self) -> Result<(), AppError> {
         if let Some(ref state) = self.state {
             let state_path = self.state_file_path();
+            let tmp_path = format!("{}.tmp", state_path);
             let content = serde_json::to_string_pretty(state)
                 .map_err(|e| AppError::WebDAV(format!("Failed to serialize sync state: {}", e)))?;
-            fs::write(&state_path, content)
+            fs::write(&tmp_path, content)
+                .and_then(|_| fs::rename(&tmp_path, &state_path))
                 .map_err(|e| AppError::WebDAV(format!("Failed to write sync state: {}", e)))?;
+            match fs::remove_file(self.checkpoint_log_path()) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
         }
         Ok(())
     }
@@ -58,28 +149,198 @@ impl SyncEngine {
         format!("{}/.lumina-sync-state.json", self.vault_path)
     }
 
+    /// 断点日志：同步过程中每完成一个条目追加一行，保存状态时合并进状态文件
+    fn checkpoint_log_path(&self) -> PathBuf {
+        Path::new(&self.vault_path).join(".lumina-sync/checkpoints.jsonl")
+    }
+
+    /// 记录单个条目的同步结果 (断点)
+    ///
+    /// `record` 为 None 表示不再跟踪该路径。每个条目完成后立即追加到断点日志并落盘，
+    /// 不必重写整个状态文件；中途退出后恢复时已完成的上传、删除和移动不会重复执行
+    fn checkpoint(&mut self, path: &str, record: Option<FileRecord>) -> Result<(), AppError> {
+        let line = serde_json::to_string(&Checkpoint { path: path.to_string(), record: record.clone() })
+            .map_err(|e| AppError::WebDAV(format!("Failed to serialize checkpoint: {}", e)))?;
+        let log_path = self.checkpoint_log_path();
+        if let Some(parent) = log_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&log_path)?;
+        writeln!(file, "{}", line)?;
+        file.sync_data()?;
+
+        self.apply_checkpoint(path, record);
+        Ok(())
+    }
+
+    fn apply_checkpoint(&mut self, path: &str, record: Option<FileRecord>) {
+        let state = self.state.get_or_insert_with(SyncState::default);
+        state.file_records.retain(|r| r.path != path);
+        state.file_records.extend(record);
+    }
+
+    /// 未完成的同步计划的存放位置
+    fn pending_plan_path(vault_path: &str) -> PathBuf {
+        Path::new(vault_path).join(".lumina-sync/pending-plan.json")
+    }
+
+    /// 读取上次被中断 (取消或退出) 的同步计划
+    pub fn pending_plan(vault_path: &str) -> Option<SyncPlan> {
+        let content = fs::read_to_string(Self::pending_plan_path(vault_path)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save_pending_plan(&self, plan: &SyncPlan) -> Result<(), AppError> {
+        let path = Self::pending_plan_path(&self.vault_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string(plan)
+            .map_err(|e| AppError::WebDAV(format!("Failed to serialize sync plan: {}", e)))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    fn clear_pending_plan(&self) {
+        let _ = fs::remove_file(Self::pending_plan_path(&self.vault_path));
+    }
+
+    /// 上次同步内容 (合并基线) 的存放位置
+    fn base_file_path(&self, path: &str) -> PathBuf {
+        Path::new(&self.vault_path).join(".lumina-sync/base").join(path)
+    }
+
+    /// 记录文本文件的同步基线
+    fn save_base(&self, path: &str, content: &[u8]) {
+        if !is_text_file(path) || std::str::from_utf8(content).is_err() {
+            return;
+        }
+        let base_path = self.base_file_path(path);
+        if let Some(parent) = base_path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(e) = fs::write(&base_path, content) {
+            eprintln!("[WebDAV] Failed to save merge base for {}: {}", path, e);
+        }
+    }
+
+    /// 读取同步基线
+    fn load_base(&self, path: &str) -> Option<String> {
+        fs::read_to_string(self.base_file_path(path)).ok()
+    }
+
+    /// 移除同步基线
+    fn remove_base(&self, path: &str) {
+        let _ = fs::remove_file(self.base_file_path(path));
+    }
+
+    /// 解锁端到端加密
+    ///
+    /// 远程存在加密清单时必须提供正确口令；首次启用加密时创建清单，
+    /// 此时远程目录必须为空，避免明文与密文混杂
+    async fn unlock(&mut self) -> Result<(), AppError> {
+        if self.cipher.is_some() {
+            return Ok(());
+        }
+
+        let manifest = match self.storage.metadata(crypto::MANIFEST_PATH).await? {
+            Some(_) => Some(E2EEManifest::from_json(&self.storage.get(crypto::MANIFEST_PATH).await?)?),
+            None => None,
+        };
+
+        match (&self.encryption, manifest) {
+            (None, None) => {}
+            (None, Some(_)) => {
+                return Err(AppError::WebDAV(
+                    "Remote data is end-to-end encrypted, a passphrase is required".to_string(),
+                ));
+            }
+            (Some(encryption), Some(manifest)) => {
+                self.cipher = Some(manifest.unlock(&encryption.passphrase)?);
+            }
+            (Some(encryption), None) => {
+                if !self.storage.list("", false).await?.is_empty() {
+                    return Err(AppError::WebDAV(
+                        "Remote folder already contains unencrypted files, use an empty folder for encrypted sync".to_string(),
+                    ));
+                }
+                let (manifest, cipher) = E2EEManifest::create(&encryption.passphrase, encryption.encrypt_names)?;
+                self.storage.put(crypto::MANIFEST_PATH, manifest.to_json()?.as_bytes()).await?;
+                self.cipher = Some(cipher);
+            }
+        }
+        Ok(())
+    }
+
+    /// 明文路径对应的远程路径 (加密后的文件名过长时报错)
+    fn remote_path(&self, path: &str) -> Result<String, AppError> {
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt_path(path),
+            None => Ok(path.to_string()),
+        }
+    }
+
+    /// 将远程条目还原为明文路径和大小，文件名无法解密时返回 None
+    fn decode_remote_entry(&self, mut entry: RemoteEntry) -> Option<RemoteEntry> {
+        let Some(cipher) = &self.cipher else {
+            return Some(entry);
+        };
+        let Some(path) = cipher.decrypt_path(&entry.path) else {
+            eprintln!("[WebDAV] Skipping remote entry with undecryptable name: {}", entry.path);
+            return None;
+        };
+        entry.name = path.rsplit('/').next().unwrap_or("").to_string();
+        entry.path = path;
+        if !entry.is_dir {
+            entry.size = cipher.plaintext_size(entry.size);
+        }
+        Some(entry)
+    }
+
+    /// 上传文件 (启用加密时先加密)
+    async fn put_remote(&self, path: &str, content: &[u8]) -> Result<Option<String>, AppError> {
+        match &self.cipher {
+            Some(cipher) => self.storage.put(&self.remote_path(path)?, &cipher.encrypt(content)?).await,
+            None => self.storage.put(path, content).await,
+        }
+    }
+
+    /// 下载文件 (启用加密时解密)
+    async fn get_remote(&self, path: &str) -> Result<Vec<u8>, AppError> {
+        let content = self.storage.get(&self.remote_path(path)?).await?;
+        match &self.cipher {
+            Some(cipher) => cipher.decrypt(&content)
+                .map_err(|e| AppError::WebDAV(format!("{}: {}", path, e))),
+            None => Ok(content),
+        }
+    }
+
     /// 测试连接
     pub async fn test_connection(&self) -> Result<bool, AppError> {
-        self.client.test_connection().await
+        self.storage.test_connection().await
     }
 
     /// 扫描本地文件
     pub fn scan_local_files(&self) -> Result<Vec<LocalFileInfo>, AppError> {
+        self.scan_local().map(|(files, _)| files)
+    }
+
+    /// 扫描本地文件，同时返回被同步规则排除的路径
+    fn scan_local(&self) -> Result<(Vec<LocalFileInfo>, Vec<ExcludedPath>), AppError> {
         let mut files = Vec::new();
+        let mut excluded = Vec::new();
         let vault = Path::new(&self.vault_path);
+        let mut walker = WalkDir::new(vault).into_iter();
+
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else { continue };
 
-        for entry in WalkDir::new(vault)
-            .into_iter()
-            .filter_entry(|e| !Self::should_skip(e.file_name().to_str().unwrap_or("")))
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-            
             // 跳过 vault 根目录本身
-            if path == vault {
+            if entry.depth() == 0 {
                 continue;
             }
 
+            let path = entry.path();
             let relative_path = path.strip_prefix(vault)
                 .map(|p| p.to_string_lossy().replace('\\', "/"))
                 .unwrap_or_default();
@@ -90,6 +351,21 @@ impl SyncEngine {
 
             let metadata = entry.metadata()
                 .map_err(|e| AppError::WebDAV(format!("Failed to read metadata: {}", e)))?;
+            let is_dir = metadata.is_dir();
+            let size = if metadata.is_file() { metadata.len() } else { 0 };
+
+            if let Some(reason) = self.filter.check(&relative_path, is_dir, size) {
+                if is_dir {
+                    walker.skip_current_dir();
+                }
+                excluded.push(ExcludedPath {
+                    path: relative_path,
+                    is_dir,
+                    side: SyncSide::Local,
+                    reason,
+                });
+                continue;
+            }
 
             let modified = metadata.modified()
                 .ok()
@@ -100,35 +376,91 @@ impl SyncEngine {
             files.push(LocalFileInfo {
                 relative_path,
                 absolute_path: path.to_string_lossy().to_string(),
-                is_dir: metadata.is_dir(),
-                size: if metadata.is_file() { metadata.len() } else { 0 },
+                is_dir,
+                size,
                 modified,
             });
         }
 
-        Ok(files)
-    }
-
-    /// 判断是否应该跳过的文件/目录
-    fn should_skip(name: &str) -> bool {
-        name.starts_with('.')
-            || name == "node_modules"
-            || name == "target"
-            || name.ends_with(".tmp")
-            || name.ends_with(".swp")
+        Ok((files, excluded))
     }
 
     /// 扫描远程文件
     pub async fn scan_remote_files(&self) -> Result<Vec<RemoteEntry>, AppError> {
-        self.client.list_all_recursive("").await
+        self.scan_remote().await.map(|(files, _)| files)
+    }
+
+    /// 扫描远程文件，同时返回被同步规则排除的路径
+    ///
+    /// 远程列表是扁平的，上级目录被排除时其下条目直接丢弃，只报告目录本身
+    async fn scan_remote(&self) -> Result<(Vec<RemoteEntry>, Vec<ExcludedPath>), AppError> {
+        let entries = self.storage.list("", true).await?;
+        let mut files = Vec::new();
+        let mut excluded = Vec::new();
+
+        for entry in entries {
+            if entry.path == crypto::MANIFEST_PATH {
+                continue;
+            }
+            let Some(entry) = self.decode_remote_entry(entry) else {
+                continue;
+            };
+            if self.filter.check_parents(&entry.path).is_some() {
+                continue;
+            }
+            match self.filter.check(&entry.path, entry.is_dir, entry.size) {
+                Some(reason) => excluded.push(ExcludedPath {
+                    path: entry.path,
+                    is_dir: entry.is_dir,
+                    side: SyncSide::Remote,
+                    reason,
+                }),
+                None => files.push(entry),
+            }
+        }
+
+        Ok((files, excluded))
+    }
+
+    /// 预览同步规则排除的本地与远程路径 (不执行任何同步操作)
+    pub async fn preview_exclusions(&mut self) -> Result<Vec<ExcludedPath>, AppError> {
+        self.unlock().await?;
+        let (_, mut excluded) = self.scan_local()?;
+        let (_, remote_excluded) = self.scan_remote().await?;
+        excluded.extend(remote_excluded);
+        Ok(excluded)
     }
 
     /// 计算同步计划
     pub async fn compute_sync_plan(&mut self) -> Result<SyncPlan, AppError> {
+        let result = self.compute_sync_plan_inner().await;
+        if let Err(e) = &result {
+            self.report_error(e);
+        }
+        result
+    }
+
+    async fn compute_sync_plan_inner(&mut self) -> Result<SyncPlan, AppError> {
+        self.report(SyncProgress::new(SyncStage::Connecting));
         self.load_state()?;
-        
-        let local_files = self.scan_local_files()?;
-        let remote_files = self.scan_remote_files().await?;
+        self.unlock().await?;
+
+        self.report(SyncProgress::new(SyncStage::ScanningRemote));
+        let (mut remote_files, remote_excluded) = self.scan_remote().await?;
+
+        self.report(SyncProgress::new(SyncStage::ScanningLocal));
+        let (mut local_files, local_excluded) = self.scan_local()?;
+
+        // 任一侧被排除的路径两侧都不参与同步 (如本地文件超过大小上限时不能删除远程副本)
+        let excluded: HashSet<String> = local_excluded
+            .into_iter()
+            .chain(remote_excluded)
+            .map(|e| e.path)
+            .collect();
+        local_files.retain(|f| !excluded.contains(&f.relative_path));
+        remote_files.retain(|f| !excluded.contains(&f.path));
+
+        self.report(SyncProgress::new(SyncStage::ComputingDiff));
 
         // 构建映射表
         let local_map: HashMap<String, &LocalFileInfo> = local_files
@@ -157,13 +489,20 @@ impl SyncEngine {
 
             let (action, reason) = self.determine_action(Some(local), remote, last_record);
 
-            if action != SyncAction::Skip {
+            // 内容未变但 mtime 变化 (如被重写)：保留 Skip 条目以刷新同步记录
+            let needs_refresh = action == SyncAction::Skip
+                && !local.is_dir
+                && remote.is_some()
+                && last_record.is_some_and(|r| r.local_mtime != local.modified);
+
+            if action != SyncAction::Skip || needs_refresh {
                 items.push(SyncPlanItem {
                     path: path.clone(),
                     action,
                     local: Some(local.clone()),
                     remote: remote.cloned(),
                     reason,
+                    moved_from: None,
                 });
             }
         }
@@ -188,21 +527,152 @@ impl SyncEngine {
                     local: None,
                     remote: Some(remote.clone()),
                     reason,
+                    moved_from: None,
                 });
             }
         }
 
-        // 统计
-        let upload_count = items.iter().filter(|i| i.action == SyncAction::Upload).count();
-        let download_count = items.iter().filter(|i| i.action == SyncAction::Download).count();
-        let conflict_count = items.iter().filter(|i| i.action == SyncAction::Conflict).count();
+        let mut items = self.detect_moves(items).await;
+
+        // 移动最先执行 (旧目录被删除前移出文件)；目录删除放在最后 (由深到浅)，
+        // 此时其中的文件已先行处理
+        items.sort_by_key(|i| {
+            let is_dir_delete = i.action == SyncAction::DeleteLocal
+                && i.local.as_ref().is_some_and(|l| l.is_dir);
+            (
+                i.action != SyncAction::Move,
+                is_dir_delete,
+                is_dir_delete.then(|| std::cmp::Reverse(i.path.matches('/').count())),
+            )
+        });
 
-        Ok(SyncPlan {
-            items,
-            upload_count,
-            download_count,
-            conflict_count,
-        })
+        // 双方均有变化但内容可能相同 (如首次在新设备同步)：比较内容哈希
+        for item in items.iter_mut().filter(|i| i.action == SyncAction::Conflict) {
+            if self.is_identical(item).await {
+                item.action = SyncAction::Skip;
+                item.reason = "Identical content on both sides".to_string();
+            }
+        }
+
+        Ok(plan_from_items(items))
+    }
+
+    /// 识别重命名/移动，把成对的 "新增 + 删除" 合并为 Move
+    ///
+    /// 本地重命名：本地新文件的内容哈希与某个本地已删除、远程未变的文件记录一致。
+    /// 远程重命名：远程新文件大小一致，且 ETag 与记录相同 (服务器移动时保留 ETag)
+    /// 或下载后内容哈希一致；本地旧文件须自上次同步后未修改
+    async fn detect_moves(&self, mut items: Vec<SyncPlanItem>) -> Vec<SyncPlanItem> {
+        let records: HashMap<&str, &FileRecord> = self.state
+            .as_ref()
+            .map(|s| s.file_records.iter().map(|r| (r.path.as_str(), r)).collect())
+            .unwrap_or_default();
+
+        // 本地已删除、远程未变的文件
+        let mut local_sources: Vec<usize> = items
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| i.action == SyncAction::DeleteRemote)
+            .filter(|(_, i)| {
+                let (Some(remote), Some(record)) = (&i.remote, records.get(i.path.as_str())) else {
+                    return false;
+                };
+                !remote.is_dir && record.content_hash.is_some() && !Self::remote_changed(remote, record)
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        // 远程已不存在、本地未修改的文件 (动作可能是 DeleteLocal 或本地优先的重新上传)
+        let mut remote_sources: Vec<usize> = items
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| matches!(i.action, SyncAction::DeleteLocal | SyncAction::Upload) && i.remote.is_none())
+            .filter(|(_, i)| {
+                let (Some(local), Some(record)) = (&i.local, records.get(i.path.as_str())) else {
+                    return false;
+                };
+                !local.is_dir && !Self::local_changed(local, record)
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut moves: Vec<(usize, usize)> = Vec::new();
+
+        for (target, item) in items.iter().enumerate() {
+            let is_new = !records.contains_key(item.path.as_str());
+            match (&item.action, &item.local, &item.remote) {
+                (SyncAction::Upload, Some(local), None) if is_new && !local.is_dir => {
+                    let candidates: Vec<usize> = local_sources
+                        .iter()
+                        .copied()
+                        .filter(|&s| items[s].remote.as_ref().is_some_and(|r| r.size == local.size))
+                        .collect();
+                    if candidates.is_empty() {
+                        continue;
+                    }
+                    let Some(hash) = file_hash(Path::new(&local.absolute_path)) else {
+                        continue;
+                    };
+                    let matched = candidates.into_iter().find(|&s| {
+                        records[items[s].path.as_str()].content_hash.as_ref() == Some(&hash)
+                    });
+                    if let Some(source) = matched {
+                        local_sources.retain(|&s| s != source);
+                        moves.push((target, source));
+                    }
+                }
+                (SyncAction::Download, None, Some(remote)) if is_new && !remote.is_dir => {
+                    let candidates: Vec<usize> = remote_sources
+                        .iter()
+                        .copied()
+                        .filter(|&s| items[s].local.as_ref().is_some_and(|l| l.size == remote.size))
+                        .collect();
+                    if candidates.is_empty() {
+                        continue;
+                    }
+
+                    let mut matched = candidates.iter().copied().find(|&s| {
+                        remote.etag.is_some() && records[items[s].path.as_str()].etag == remote.etag
+                    });
+                    if matched.is_none() {
+                        if let Ok(content) = self.get_remote(&item.path).await {
+                            let hash = content_hash(&content);
+                            matched = candidates.into_iter().find(|&s| {
+                                records[items[s].path.as_str()].content_hash.as_ref() == Some(&hash)
+                            });
+                        }
+                    }
+                    if let Some(source) = matched {
+                        remote_sources.retain(|&s| s != source);
+                        moves.push((target, source));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let sources: HashSet<usize> = moves.iter().map(|(_, source)| *source).collect();
+        for (target, source) in moves {
+            let source = items[source].clone();
+            let from = source.path;
+            let item = &mut items[target];
+            // 本地重命名：local 为新位置，remote 为旧位置；远程重命名则相反
+            if item.local.is_some() {
+                item.remote = source.remote;
+            } else {
+                item.local = source.local;
+            }
+            item.action = SyncAction::Move;
+            item.reason = format!("Renamed from {}", from);
+            item.moved_from = Some(from);
+        }
+
+        items
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !sources.contains(index))
+            .map(|(_, item)| item)
+            .collect()
     }
 
     /// 确定单个文件的同步动作
@@ -218,10 +688,19 @@ impl SyncEngine {
                 // 新文件，上传
                 (SyncAction::Upload, "New local file".to_string())
             }
-            (Some(_), None, Some(_)) => {
-                // 之前同步过，远程没了 -> 本地优先：重新上传
-                // 不删除本地文件，保护用户数据
-                (SyncAction::Upload, "Remote file missing, re-uploading (local-first)".to_string())
+            (Some(l), None, Some(lr)) => {
+                if self.remote_delete_mode == RemoteDeleteMode::Reupload {
+                    // 之前同步过，远程没了 -> 本地优先：重新上传
+                    // 不删除本地文件，保护用户数据
+                    return (SyncAction::Upload, "Remote file missing, re-uploading (local-first)".to_string());
+                }
+
+                if l.is_dir || !Self::local_changed(l, lr) {
+                    (SyncAction::DeleteLocal, "Deleted on remote".to_string())
+                } else {
+                    // 远程删除后本地又有修改 -> 保留本地修改
+                    (SyncAction::Upload, "Deleted on remote but modified locally, re-uploading".to_string())
+                }
             }
 
             // 本地存在，远程也存在
@@ -232,11 +711,11 @@ impl SyncEngine {
                 }
 
                 let local_changed = last_record
-                    .map(|lr| l.modified > lr.local_mtime)
+                    .map(|lr| Self::local_changed(l, lr))
                     .unwrap_or(true);
                 
                 let remote_changed = last_record
-                    .map(|lr| r.modified > lr.remote_mtime)
+                    .map(|lr| Self::remote_changed(r, lr))
                     .unwrap_or(true);
 
                 match (local_changed, remote_changed) {
@@ -271,49 +750,189 @@ impl SyncEngine {
         }
     }
 
+    /// 本地文件自上次同步后是否有实际变化
+    ///
+    /// mtime 未变直接视为未修改；mtime 变化时以内容哈希为准，
+    /// 避免仅 mtime 被改写 (下载、复制、时钟偏差) 造成误判
+    fn local_changed(local: &LocalFileInfo, record: &FileRecord) -> bool {
+        if local.modified == record.local_mtime {
+            return false;
+        }
+        match &record.content_hash {
+            Some(hash) => file_hash(Path::new(&local.absolute_path))
+                .map(|current| &current != hash)
+                .unwrap_or(true),
+            None => local.modified > record.local_mtime,
+        }
+    }
+
+    /// 远程文件自上次同步后是否有变化：有 ETag 时以 ETag 为准，否则比较 mtime
+    fn remote_changed(remote: &RemoteEntry, record: &FileRecord) -> bool {
+        match (&remote.etag, &record.etag) {
+            (Some(current), Some(previous)) => current != previous,
+            _ => remote.modified > record.remote_mtime,
+        }
+    }
+
+    /// 本地与远程内容是否完全相同 (大小不同时不下载)
+    async fn is_identical(&self, item: &SyncPlanItem) -> bool {
+        let (Some(local), Some(remote)) = (&item.local, &item.remote) else {
+            return false;
+        };
+        if local.is_dir || remote.is_dir || local.size != remote.size {
+            return false;
+        }
+        let Some(local_hash) = file_hash(Path::new(&local.absolute_path)) else {
+            return false;
+        };
+        match self.get_remote(&item.path).await {
+            Ok(content) => content_hash(&content) == local_hash,
+            Err(_) => false,
+        }
+    }
+
     /// 执行同步
     pub async fn execute_sync(&mut self, plan: &SyncPlan) -> Result<SyncResult, AppError> {
+        let started_at = unix_now();
+        let result = self.execute_sync_inner(plan).await;
+        self.record_history(started_at, result.as_ref());
+        match &result {
+            Ok(result) => {
+                let stage = if result.cancelled { SyncStage::Cancelled } else { SyncStage::Completed };
+                let mut done = SyncProgress::new(stage);
+                done.processed = result.uploaded + result.downloaded + result.deleted
+                    + result.conflicts + result.merged;
+                done.total = done.processed + result.errors.len();
+                done.error = result.errors.first().map(|e| format!("{}: {}", e.path, e.message));
+                self.report(done);
+            }
+            Err(e) => self.report_error(e),
+        }
+        result
+    }
+
+    /// 写入同步历史
+    ///
+    /// 没有任何变更的同步 (如自动同步时无事可做) 不记录，避免淹没有用的记录
+    fn record_history(&self, started_at: u64, result: Result<&SyncResult, &AppError>) {
+        let entry = match result {
+            Ok(r) if r.affected.is_empty() && r.errors.is_empty() && !r.cancelled => return,
+            Ok(r) => SyncHistoryEntry {
+                started_at,
+                result: Some(r.clone()),
+                error: None,
+            },
+            Err(e) => SyncHistoryEntry {
+                started_at,
+                result: None,
+                error: Some(e.to_string()),
+            },
+        };
+        if let Err(e) = history::append_history(&self.vault_path, &entry) {
+            eprintln!("[WebDAV] Failed to record sync history: {}", e);
+        }
+    }
+
+    async fn execute_sync_inner(&mut self, plan: &SyncPlan) -> Result<SyncResult, AppError> {
         let start = Instant::now();
         let mut uploaded = 0;
         let mut downloaded = 0;
         let mut deleted = 0;
         let mut conflicts = 0;
+        let mut merged = 0;
+        let mut moved = 0;
+        let mut trashed = Vec::new();
+        let mut affected = Vec::new();
         let mut errors = Vec::new();
-        let mut new_records = Vec::new();
+        let mut cancelled = false;
+        let trash_dir = self.vault_trash_dir();
+        let total = plan.items.len();
+
+        // 由前端传入计划时引擎尚未加载状态，须在现有记录基础上更新
+        if self.state.is_none() {
+            self.load_state()?;
+        }
+        self.unlock().await?;
+        // 记录计划，中途退出后可恢复；完成的条目通过断点写入状态文件
+        self.save_pending_plan(plan)?;
+
+        for (index, item) in plan.items.iter().enumerate() {
+            if self.is_cancelled() {
+                cancelled = true;
+                break;
+            }
+
+            self.report(SyncProgress {
+                stage: SyncStage::Syncing,
+                total,
+                processed: index,
+                current_file: Some(item.path.clone()),
+                error: None,
+            });
 
-        for item in &plan.items {
             let result = match item.action {
                 SyncAction::Upload => {
-                    self.execute_upload(item).await
+                    self.execute_upload(item).await.map(|r| (SyncAction::Upload, r))
                 }
                 SyncAction::Download => {
-                    self.execute_download(item).await
+                    self.execute_download(item).await.map(|r| (SyncAction::Download, r))
                 }
                 SyncAction::DeleteRemote => {
-                    self.execute_delete_remote(item).await
+                    self.execute_delete_remote(item).await.map(|r| (SyncAction::DeleteRemote, r))
                 }
                 SyncAction::DeleteLocal => {
-                    // 本地优先：永远不删除本地文件，跳过此操作
-                    eprintln!("[WebDAV] Skipping DeleteLocal for {} - local-first policy", item.path);
-                    continue;
+                    if self.remote_delete_mode == RemoteDeleteMode::Reupload {
+                        // 本地优先：未启用删除传播时永远不删除本地文件
+                        eprintln!("[WebDAV] Skipping DeleteLocal for {} - local-first policy", item.path);
+                        continue;
+                    }
+                    self.execute_delete_local(item, &trash_dir)
                 }
                 SyncAction::Conflict => {
                     self.handle_conflict(item).await
                 }
-                SyncAction::Skip => continue,
+                SyncAction::Skip => {
+                    // 内容一致，仅刷新同步记录
+                    if let Some(record) = self.execute_refresh_record(item) {
+                        self.checkpoint(&item.path, Some(record))?;
+                    }
+                    continue;
+                }
+                SyncAction::Move => {
+                    self.execute_move(item).await.map(|r| (SyncAction::Move, r))
+                }
+                SyncAction::Merged => continue,
             };
 
             match result {
-                Ok(record) => {
-                    match item.action {
+                Ok((outcome, record)) => {
+                    match outcome {
                         SyncAction::Upload => uploaded += 1,
                         SyncAction::Download => downloaded += 1,
-                        SyncAction::DeleteRemote | SyncAction::DeleteLocal => deleted += 1,
+                        SyncAction::DeleteRemote => deleted += 1,
+                        SyncAction::DeleteLocal => {
+                            deleted += 1;
+                            trashed.push(item.path.clone());
+                        }
                         SyncAction::Conflict => conflicts += 1,
+                        SyncAction::Merged => merged += 1,
+                        SyncAction::Move => moved += 1,
                         _ => {}
                     }
-                    if let Some(r) = record {
-                        new_records.push(r);
+                    if outcome != SyncAction::Skip {
+                        affected.push(AffectedPath {
+                            path: item.path.clone(),
+                            action: outcome.clone(),
+                            moved_from: item.moved_from.clone(),
+                        });
+                    }
+                    // 删除成功后不再跟踪；其他动作有新记录时更新
+                    let is_delete = matches!(outcome, SyncAction::DeleteRemote | SyncAction::DeleteLocal);
+                    if is_delete || record.is_some() {
+                        self.checkpoint(&item.path, record)?;
+                    }
+                    if let (SyncAction::Move, Some(from)) = (&outcome, &item.moved_from) {
+                        self.checkpoint(from, None)?;
                     }
                 }
                 Err(e) => {
@@ -326,42 +945,27 @@ impl SyncEngine {
             }
         }
 
-        // 更新同步状态 - 合并记录而非替换
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        // 保留之前的记录，只更新/添加本次处理的文件
-        let mut merged_records: HashMap<String, FileRecord> = self.state
-            .as_ref()
-            .map(|s| s.file_records.iter().map(|r| (r.path.clone(), r.clone())).collect())
-            .unwrap_or_default();
-
-        // 更新/添加新记录
-        for record in new_records {
-            merged_records.insert(record.path.clone(), record);
-        }
+        // 被取消时保留计划供下次恢复，last_sync 只在完整运行后更新；
+        // 两种情况都把断点日志合并进状态文件
+        if !cancelled {
+            self.clear_pending_plan();
 
-        // 移除已删除的文件记录（仅远程删除，本地删除不再传播）
-        for item in &plan.items {
-            if item.action == SyncAction::DeleteRemote {
-                merged_records.remove(&item.path);
-            }
+            let state = self.state.get_or_insert_with(SyncState::default);
+            state.last_sync = unix_now();
         }
-
-        self.state = Some(SyncState {
-            last_sync: now,
-            file_records: merged_records.into_values().collect(),
-        });
         self.save_state()?;
 
         Ok(SyncResult {
-            success: errors.is_empty(),
+            success: errors.is_empty() && !cancelled,
+            cancelled,
             uploaded,
             downloaded,
             deleted,
             conflicts,
+            merged,
+            moved,
+            trashed,
+            affected,
             errors,
             duration_ms: start.elapsed().as_millis() as u64,
         })
@@ -374,32 +978,74 @@ impl SyncEngine {
         })?;
 
         if local.is_dir {
-            self.client.ensure_dir(&item.path).await?;
+            self.storage.mkdir(&self.remote_path(&item.path)?).await?;
         } else {
             // 确保父目录存在
             if let Some(parent) = Path::new(&item.path).parent() {
                 let parent_str = parent.to_string_lossy().replace('\\', "/");
                 if !parent_str.is_empty() {
-                    self.client.ensure_dir(&parent_str).await?;
+                    self.storage.mkdir(&self.remote_path(&parent_str)?).await?;
                 }
             }
 
             let content = fs::read(&local.absolute_path)
                 .map_err(|e| AppError::WebDAV(format!("Failed to read local file: {}", e)))?;
-            self.client.upload(&item.path, &content).await?;
+            let etag = self.put_remote(&item.path, &content).await?;
+            self.save_base(&item.path, &content);
+
+            return Ok(Some(self.uploaded_record(&item.path, local.modified, &content, etag).await));
         }
 
-        // 重新获取远程信息
-        let remote_mtime = item.remote.as_ref().map(|r| r.modified).unwrap_or(local.modified);
-        
         Ok(Some(FileRecord {
             path: item.path.clone(),
             local_mtime: local.modified,
-            remote_mtime,
+            remote_mtime: item.remote.as_ref().map(|r| r.modified).unwrap_or(local.modified),
             etag: item.remote.as_ref().and_then(|r| r.etag.clone()),
+            content_hash: None,
         }))
     }
 
+    /// 上传完成后生成同步记录
+    ///
+    /// 重新获取远程属性，记录服务器端新的 ETag 和 mtime，
+    /// 否则下次同步会把自己的上传误判为远程修改
+    async fn uploaded_record(
+        &self,
+        path: &str,
+        local_mtime: u64,
+        content: &[u8],
+        put_etag: Option<String>,
+    ) -> FileRecord {
+        let remote = match self.remote_path(path) {
+            Ok(remote_path) => self.storage.metadata(&remote_path).await.ok().flatten(),
+            Err(_) => None,
+        };
+        let now = unix_now();
+
+        FileRecord {
+            path: path.to_string(),
+            local_mtime,
+            remote_mtime: remote.as_ref().map(|r| r.modified).unwrap_or(now),
+            etag: remote.and_then(|r| r.etag).or(put_etag),
+            content_hash: Some(content_hash(content)),
+        }
+    }
+
+    /// 内容一致时刷新同步记录
+    fn execute_refresh_record(&self, item: &SyncPlanItem) -> Option<FileRecord> {
+        let (local, remote) = (item.local.as_ref()?, item.remote.as_ref()?);
+        let content = fs::read(&local.absolute_path).ok()?;
+        self.save_base(&item.path, &content);
+
+        Some(FileRecord {
+            path: item.path.clone(),
+            local_mtime: local.modified,
+            remote_mtime: remote.modified,
+            etag: remote.etag.clone(),
+            content_hash: Some(content_hash(&content)),
+        })
+    }
+
     /// 执行下载
     async fn execute_download(&self, item: &SyncPlanItem) -> Result<Option<FileRecord>, AppError> {
         let remote = item.remote.as_ref().ok_or_else(|| {
@@ -412,54 +1058,161 @@ impl SyncEngine {
         if remote.is_dir {
             fs::create_dir_all(local_path)
                 .map_err(|e| AppError::WebDAV(format!("Failed to create directory: {}", e)))?;
+            record_sync_write(local_path);
         } else {
             // 确保父目录存在
             if let Some(parent) = local_path.parent() {
                 fs::create_dir_all(parent)
                     .map_err(|e| AppError::WebDAV(format!("Failed to create parent directory: {}", e)))?;
+                record_sync_write(parent);
             }
 
-            let content = self.client.download(&item.path).await?;
-            fs::write(local_path, &content)
-                .map_err(|e| AppError::WebDAV(format!("Failed to write local file: {}", e)))?;
-        }
+            let content = self.get_remote(&item.path).await?;
+            let hash = content_hash(&content);
 
-        let local_mtime = local_path.metadata()
-            .ok()
-            .and_then(|m| m.modified().ok())
-            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-            .map(|d| d.as_secs())
-            .unwrap_or(remote.modified);
+            // 内容相同则不重写，保留本地 mtime；覆盖前保存旧版本
+            if file_hash(local_path).as_ref() != Some(&hash) {
+                history::snapshot(&self.vault_path, &item.path)?;
+                fs::write(local_path, &content)
+                    .map_err(|e| AppError::WebDAV(format!("Failed to write local file: {}", e)))?;
+                record_sync_write(local_path);
+            }
+            self.save_base(&item.path, &content);
+
+            return Ok(Some(FileRecord {
+                path: item.path.clone(),
+                local_mtime: file_mtime(local_path).unwrap_or(remote.modified),
+                remote_mtime: remote.modified,
+                etag: remote.etag.clone(),
+                content_hash: Some(hash),
+            }));
+        }
 
         Ok(Some(FileRecord {
             path: item.path.clone(),
-            local_mtime,
+            local_mtime: file_mtime(local_path).unwrap_or(remote.modified),
             remote_mtime: remote.modified,
             etag: remote.etag.clone(),
+            content_hash: None,
         }))
     }
 
+    /// 执行移动/重命名
+    ///
+    /// 本地已在新位置时在服务器上 MOVE，否则在本地重命名。
+    /// 内容未变，沿用旧记录的哈希和同步基线
+    async fn execute_move(&self, item: &SyncPlanItem) -> Result<Option<FileRecord>, AppError> {
+        let (Some(from), Some(local), Some(remote)) = (&item.moved_from, &item.local, &item.remote) else {
+            return Err(AppError::WebDAV("Incomplete move item".to_string()));
+        };
+        let previous_hash = self.state
+            .as_ref()
+            .and_then(|s| s.file_records.iter().find(|r| &r.path == from))
+            .and_then(|r| r.content_hash.clone());
+
+        let record = if local.relative_path == item.path {
+            if let Some(parent) = Path::new(&item.path).parent() {
+                let parent_str = parent.to_string_lossy().replace('\\', "/");
+                if !parent_str.is_empty() {
+                    self.storage.mkdir(&self.remote_path(&parent_str)?).await?;
+                }
+            }
+            self.storage.move_to(&self.remote_path(from)?, &self.remote_path(&item.path)?).await?;
+
+            let moved = self.storage.metadata(&self.remote_path(&item.path)?).await.ok().flatten();
+            FileRecord {
+                path: item.path.clone(),
+                local_mtime: local.modified,
+                remote_mtime: moved.as_ref().map(|r| r.modified).unwrap_or(remote.modified),
+                etag: moved.and_then(|r| r.etag).or_else(|| remote.etag.clone()),
+                content_hash: previous_hash,
+            }
+        } else {
+            let target = format!("{}/{}", self.vault_path, item.path);
+            as_window(SYNC_WRITER, || crate::fs::rename_entry(&local.absolute_path, &target))?;
+
+            FileRecord {
+                path: item.path.clone(),
+                local_mtime: file_mtime(Path::new(&target)).unwrap_or(local.modified),
+                remote_mtime: remote.modified,
+                etag: remote.etag.clone(),
+                content_hash: previous_hash,
+            }
+        };
+
+        if let Some(base) = self.load_base(from) {
+            self.save_base(&item.path, base.as_bytes());
+            self.remove_base(from);
+        }
+        Ok(Some(record))
+    }
+
     /// 删除远程文件
     async fn execute_delete_remote(&self, item: &SyncPlanItem) -> Result<Option<FileRecord>, AppError> {
-        self.client.delete(&item.path).await?;
+        self.storage.delete(&self.remote_path(&item.path)?).await?;
+        self.remove_base(&item.path);
         Ok(None) // 删除后不再跟踪
     }
 
-    /// 删除本地文件 - 本地优先策略下禁用
-    #[allow(dead_code)]
-    async fn execute_delete_local(&self, _item: &SyncPlanItem) -> Result<Option<FileRecord>, AppError> {
-        // 本地优先：永远不删除本地文件
-        // 如果用户想删除，应该手动删除
-        eprintln!("[WebDAV] execute_delete_local called but disabled - local-first policy");
-        Ok(None)
+    /// 删除本地文件 (远程已删除)
+    ///
+    /// 只移入回收站，不做永久删除；非空目录保留 (其中有未同步的新文件)。
+    /// 返回 Skip 表示文件被保留
+    fn execute_delete_local(
+        &self,
+        item: &SyncPlanItem,
+        trash_dir: &Path,
+    ) -> Result<(SyncAction, Option<FileRecord>), AppError> {
+        let local = item.local.as_ref().ok_or_else(|| {
+            AppError::WebDAV("No local file for delete".to_string())
+        })?;
+        let path = Path::new(&local.absolute_path);
+
+        if !path.exists() {
+            return Ok((SyncAction::Skip, None));
+        }
+        if local.is_dir && fs::read_dir(path)?.next().is_some() {
+            return Ok((SyncAction::Skip, None));
+        }
+
+        match self.remote_delete_mode {
+            RemoteDeleteMode::SystemTrash => trash::delete(path)?,
+            RemoteDeleteMode::VaultTrash => {
+                let target = trash_dir.join(&item.path);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(path, &target)?;
+            }
+            RemoteDeleteMode::Reupload => return Ok((SyncAction::Skip, None)),
+        }
+        record_sync_write(path);
+
+        self.remove_base(&item.path);
+        Ok((SyncAction::DeleteLocal, None))
+    }
+
+    /// 本次同步使用的 vault 内回收站目录 (按时间分组，避免同名覆盖)
+    fn vault_trash_dir(&self) -> PathBuf {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        Path::new(&self.vault_path).join(".lumina-trash").join(stamp)
     }
 
-    /// 处理冲突 - 保留两个版本
-    async fn handle_conflict(&self, item: &SyncPlanItem) -> Result<Option<FileRecord>, AppError> {
+    /// 处理冲突
+    ///
+    /// 文本文件且存在同步基线时进行三方合并：无重叠修改则自动合并并回传远程，
+    /// 否则在本地写入带冲突标记的内容。无法合并时保留两个版本
+    async fn handle_conflict(&self, item: &SyncPlanItem) -> Result<(SyncAction, Option<FileRecord>), AppError> {
         let remote = item.remote.as_ref().ok_or_else(|| {
             AppError::WebDAV("No remote file for conflict resolution".to_string())
         })?;
 
+        let content = self.get_remote(&item.path).await?;
+
+        if let Some(outcome) = self.try_merge(item, remote, &content).await? {
+            return Ok(outcome);
+        }
+
         // 下载远程版本为 .conflict 文件
         let conflict_path = format!("{}/{}.conflict", self.vault_path, item.path);
         let conflict_path = Path::new(&conflict_path);
@@ -468,24 +1221,78 @@ impl SyncEngine {
             fs::create_dir_all(parent)?;
         }
 
-        let content = self.client.download(&item.path).await?;
         fs::write(conflict_path, &content)?;
+        record_sync_write(conflict_path);
 
         // 记录本地版本的信息
         let local = item.local.as_ref();
         let local_mtime = local.map(|l| l.modified).unwrap_or(0);
+        let local_hash = local.and_then(|l| file_hash(Path::new(&l.absolute_path)));
 
-        Ok(Some(FileRecord {
+        Ok((SyncAction::Conflict, Some(FileRecord {
             path: item.path.clone(),
             local_mtime,
             remote_mtime: remote.modified,
             etag: remote.etag.clone(),
-        }))
+            content_hash: local_hash,
+        })))
+    }
+
+    /// 尝试三方合并，不满足条件 (非文本、无基线、非 UTF-8) 时返回 None
+    async fn try_merge(
+        &self,
+        item: &SyncPlanItem,
+        remote: &RemoteEntry,
+        remote_content: &[u8],
+    ) -> Result<Option<(SyncAction, Option<FileRecord>)>, AppError> {
+        let Some(local) = item.local.as_ref() else {
+            return Ok(None);
+        };
+        if !is_text_file(&item.path) {
+            return Ok(None);
+        }
+        let Some(base) = self.load_base(&item.path) else {
+            return Ok(None);
+        };
+        let Ok(remote_text) = std::str::from_utf8(remote_content) else {
+            return Ok(None);
+        };
+        let Ok(local_text) = fs::read_to_string(&local.absolute_path) else {
+            return Ok(None);
+        };
+
+        let result = merge3(&base, &local_text, remote_text);
+        history::snapshot(&self.vault_path, &item.path)?;
+        fs::write(&local.absolute_path, &result.content)
+            .map_err(|e| AppError::WebDAV(format!("Failed to write merged file: {}", e)))?;
+        record_sync_write(Path::new(&local.absolute_path));
+        let local_mtime = file_mtime(Path::new(&local.absolute_path)).unwrap_or(local.modified);
+
+        if result.is_clean() {
+            let content = result.content.as_bytes();
+            let etag = self.put_remote(&item.path, content).await?;
+            self.save_base(&item.path, content);
+
+            let record = self.uploaded_record(&item.path, local_mtime, content, etag).await;
+            return Ok(Some((SyncAction::Merged, Some(record))));
+        }
+
+        // 存在真正的重叠修改：冲突标记只写入本地，待用户解决后再上传。
+        // 远程内容成为新的基线，记录写入后的 mtime，避免未处理的标记被立即上传
+        self.save_base(&item.path, remote_content);
+
+        Ok(Some((SyncAction::Conflict, Some(FileRecord {
+            path: item.path.clone(),
+            local_mtime,
+            remote_mtime: remote.modified,
+            etag: remote.etag.clone(),
+            content_hash: Some(content_hash(result.content.as_bytes())),
+        }))))
     }
 
     /// 快速同步：仅同步非冲突文件
     pub async fn quick_sync(&mut self) -> Result<SyncResult, AppError> {
-        let mut plan = self.compute_sync_plan().await?;
+        let mut plan = self.compute_plan_recorded().await?;
         
         // 过滤掉冲突，只处理确定性的操作
         plan.items.retain(|item| item.action != SyncAction::Conflict);
@@ -493,4 +1300,601 @@ impl SyncEngine {
 
         self.execute_sync(&plan).await
     }
+
+    /// 计算计划，失败时同样写入同步历史 (用于一步完成的快速同步和恢复)
+    async fn compute_plan_recorded(&mut self) -> Result<SyncPlan, AppError> {
+        let started_at = unix_now();
+        let result = self.compute_sync_plan().await;
+        if let Err(e) = &result {
+            self.record_history(started_at, Err(e));
+        }
+        result
+    }
+
+    /// 恢复上次被中断的同步，没有未完成计划时返回 None
+    ///
+    /// 重新计算计划并只保留原计划中的路径：已完成的条目已写入断点，
+    /// 不会再出现；原计划未包含的冲突 (快速同步) 仍然跳过
+    pub async fn resume_sync(&mut self) -> Result<Option<SyncResult>, AppError> {
+        let Some(pending) = Self::pending_plan(&self.vault_path) else {
+            return Ok(None);
+        };
+        let pending_actions: HashMap<String, SyncAction> = pending
+            .items
+            .into_iter()
+            .map(|i| (i.path, i.action))
+            .collect();
+
+        let mut plan = self.compute_plan_recorded().await?;
+        plan.items.retain(|item| match pending_actions.get(&item.path) {
+            Some(previous) => item.action != SyncAction::Conflict || *previous == SyncAction::Conflict,
+            None => false,
+        });
+
+        self.execute_sync(&plan_from_items(plan.items)).await.map(Some)
+    }
+}
+
+/// 由条目生成同步计划 (统计各类动作数量)
+fn plan_from_items(items: Vec<SyncPlanItem>) -> SyncPlan {
+    let upload_count = items.iter().filter(|i| i.action == SyncAction::Upload).count();
+    let download_count = items.iter().filter(|i| i.action == SyncAction::Download).count();
+    let conflict_count = items.iter().filter(|i| i.action == SyncAction::Conflict).count();
+    let move_count = items.iter().filter(|i| i.action == SyncAction::Move).count();
+
+    SyncPlan {
+        items,
+        upload_count,
+        download_count,
+        conflict_count,
+        move_count,
+    }
+}
+
+/// 可进行三方合并的文本文件
+fn is_text_file(path: &str) -> bool {
+    const TEXT_EXTENSIONS: &[&str] = &["md", "markdown", "txt", "json", "canvas", "csv", "yaml", "yml"];
+
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| TEXT_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// 当前 Unix 时间戳 (秒)
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 读取文件 mtime (Unix 时间戳，秒)
+fn file_mtime(path: &Path) -> Option<u64> {
+    path.metadata()
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+}
+
+/// 内容哈希 (SHA-256，十六进制)
+fn content_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// 读取文件并计算内容哈希
+fn file_hash(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(|content| content_hash(&content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webdav::client::WebDAVClient;
+    use crate::webdav::test_server::{S3TestServer, TestServer};
+
+    fn write(vault: &Path, path: &str, content: &str) {
+        let path = vault.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn encrypted(server: &TestServer, passphrase: &str) -> WebDAVConfig {
+        WebDAVConfig {
+            encryption: Some(EncryptionConfig {
+                passphrase: passphrase.to_string(),
+                encrypt_names: true,
+            }),
+            ..server.config()
+        }
+    }
+
+    /// 修改已同步的文件，mtime 前移以免与同步记录落在同一秒
+    fn edit(vault: &Path, path: &str, content: &str) {
+        let path = vault.join(path);
+        let before = fs::metadata(&path).unwrap().modified().unwrap();
+        fs::write(&path, content).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(before + std::time::Duration::from_secs(10))
+            .unwrap();
+    }
+
+    fn read(vault: &Path, path: &str) -> String {
+        fs::read_to_string(vault.join(path)).unwrap()
+    }
+
+    fn engine(config: WebDAVConfig, vault: &Path) -> SyncEngine {
+        SyncEngine::new(config, vault.to_string_lossy().to_string()).unwrap()
+    }
+
+    async fn sync(config: WebDAVConfig, vault: &Path) -> Result<SyncResult, AppError> {
+        SyncEngine::new(config, vault.to_string_lossy().to_string())?
+            .quick_sync()
+            .await
+    }
+
+    /// 计算并执行完整计划 (包括冲突)
+    async fn full_sync(config: WebDAVConfig, vault: &Path) -> SyncResult {
+        let mut engine = engine(config, vault);
+        let plan = engine.compute_sync_plan().await.unwrap();
+        engine.execute_sync(&plan).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_round_trip() {
+        let server = TestServer::start().await;
+        let device_a = tempfile::tempdir().unwrap();
+        write(device_a.path(), "notes/secret.md", "# Secret\n\ntop secret plan\n");
+
+        let result = sync(encrypted(&server, "correct horse"), device_a.path()).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.uploaded, 2);
+
+        let runs = history::load_history(&device_a.path().to_string_lossy(), None).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].result.as_ref().unwrap().affected.len(), 2);
+
+        // 服务器上只有清单和密文，文件名同样被加密
+        let paths = server.paths();
+        assert_eq!(paths.len(), 3);
+        assert!(paths.contains(&crypto::MANIFEST_PATH.to_string()));
+        assert!(paths.iter().all(|p| !p.contains("notes") && !p.contains("secret")));
+
+        let remote_file = paths.iter().find(|p| p.contains('/')).unwrap();
+        let raw = server.file(remote_file).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("top secret"));
+
+        // 另一台设备使用同一口令得到明文
+        let device_b = tempfile::tempdir().unwrap();
+        let result = sync(encrypted(&server, "correct horse"), device_b.path()).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.downloaded, 2);
+        assert_eq!(
+            fs::read_to_string(device_b.path().join("notes/secret.md")).unwrap(),
+            "# Secret\n\ntop secret plan\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wrong_passphrase_rejected() {
+        let server = TestServer::start().await;
+        let device_a = tempfile::tempdir().unwrap();
+        write(device_a.path(), "a.md", "alpha");
+        sync(encrypted(&server, "correct horse"), device_a.path()).await.unwrap();
+
+        let device_b = tempfile::tempdir().unwrap();
+        let err = sync(encrypted(&server, "wrong horse"), device_b.path()).await.unwrap_err();
+        assert!(err.to_string().contains("Wrong encryption passphrase"));
+        assert!(!device_b.path().join("a.md").exists());
+
+        // 未配置口令的设备不能把明文混入加密目录
+        write(device_b.path(), "b.md", "beta");
+        assert!(sync(server.config(), device_b.path()).await.is_err());
+        assert_eq!(server.paths().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_excluded_paths_not_synced() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "a.md", "alpha");
+        write(vault.path(), "big.bin", &"x".repeat(2048));
+        write(vault.path(), "private/diary.md", "secret");
+        write(vault.path(), crate::webdav::filter::IGNORE_FILE, "private/\n");
+
+        let mut config = server.config();
+        config.rules.max_file_size = Some(1024);
+
+        let result = sync(config.clone(), vault.path()).await.unwrap();
+        assert_eq!(result.uploaded, 1);
+        assert_eq!(server.paths(), vec!["a.md".to_string()]);
+
+        let mut engine = SyncEngine::new(config, vault.path().to_string_lossy().to_string()).unwrap();
+        let excluded = engine.preview_exclusions().await.unwrap();
+        let reason = |path: &str| excluded.iter().find(|e| e.path == path).map(|e| e.reason.clone());
+
+        assert_eq!(reason("big.bin"), Some(ExcludeReason::TooLarge { size: 2048, limit: 1024 }));
+        assert_eq!(reason("private"), Some(ExcludeReason::IgnoreFile { pattern: "private/".to_string() }));
+        assert!(reason("private/diary.md").is_none());
+        assert!(reason("a.md").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_encryption_requires_empty_remote() {
+        let server = TestServer::start().await;
+        server.put_file("plain.md", b"hello");
+
+        let vault = tempfile::tempdir().unwrap();
+        assert!(sync(encrypted(&server, "correct horse"), vault.path()).await.is_err());
+        assert!(server.file(crypto::MANIFEST_PATH).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_new_files_on_both_sides() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "a.md", "local a");
+        write(vault.path(), "notes/b.md", "local b");
+        server.put_file("c.md", b"remote c");
+        server.put_file("archive/d.md", b"remote d");
+
+        let result = sync(server.config(), vault.path()).await.unwrap();
+        assert!(result.success);
+        assert_eq!(server.file("a.md").unwrap(), b"local a");
+        assert_eq!(server.file("notes/b.md").unwrap(), b"local b");
+        assert_eq!(read(vault.path(), "c.md"), "remote c");
+        assert_eq!(read(vault.path(), "archive/d.md"), "remote d");
+
+        // 再次同步没有任何传输
+        server.clear_requests();
+        let result = sync(server.config(), vault.path()).await.unwrap();
+        assert!(result.affected.is_empty());
+        assert!(server.requests("PUT").is_empty());
+        assert!(server.requests("GET").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_remote_edit_downloaded_with_snapshot() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "a.md", "v1");
+        sync(server.config(), vault.path()).await.unwrap();
+
+        server.put_file("a.md", b"v2");
+        let result = sync(server.config(), vault.path()).await.unwrap();
+        assert_eq!(result.downloaded, 1);
+        assert_eq!(read(vault.path(), "a.md"), "v2");
+        // 自动同步调度器据此忽略同步自身的写入
+        let local = vault.path().join("a.md");
+        assert!(crate::fs::events::is_own_write_by(&local, SYNC_WRITER));
+
+        let root = vault.path().to_string_lossy().to_string();
+        let versions = history::list_versions(&root, "a.md").unwrap();
+        assert_eq!(versions.len(), 1);
+        history::restore_version(&root, "a.md", &versions[0].id).unwrap();
+        assert_eq!(read(vault.path(), "a.md"), "v1");
+    }
+
+    #[tokio::test]
+    async fn test_edits_on_both_sides_merged() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "a.md", "one\ntwo\nthree\n");
+        sync(server.config(), vault.path()).await.unwrap();
+
+        edit(vault.path(), "a.md", "ONE\ntwo\nthree\n");
+        server.put_file("a.md", b"one\ntwo\nTHREE\n");
+
+        // 快速同步跳过冲突
+        let result = sync(server.config(), vault.path()).await.unwrap();
+        assert!(result.affected.is_empty());
+
+        let result = full_sync(server.config(), vault.path()).await;
+        assert_eq!(result.merged, 1);
+        assert_eq!(read(vault.path(), "a.md"), "ONE\ntwo\nTHREE\n");
+        assert_eq!(server.file("a.md").unwrap(), b"ONE\ntwo\nTHREE\n");
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_edits_keep_markers_local() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "a.md", "one\ntwo\n");
+        sync(server.config(), vault.path()).await.unwrap();
+
+        edit(vault.path(), "a.md", "one\nlocal\n");
+        server.put_file("a.md", b"one\nremote\n");
+
+        let result = full_sync(server.config(), vault.path()).await;
+        assert_eq!(result.conflicts, 1);
+        let local = read(vault.path(), "a.md");
+        assert!(local.contains("local") && local.contains("remote"));
+        assert_eq!(server.file("a.md").unwrap(), b"one\nremote\n");
+
+        // 未解决的冲突标记不会被上传
+        server.clear_requests();
+        sync(server.config(), vault.path()).await.unwrap();
+        assert!(server.requests("PUT").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deletions_propagate() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "a.md", "alpha");
+        write(vault.path(), "b.md", "beta");
+        let config = WebDAVConfig {
+            remote_delete_mode: RemoteDeleteMode::VaultTrash,
+            ..server.config()
+        };
+        sync(config.clone(), vault.path()).await.unwrap();
+
+        // 本地删除 -> 远程删除
+        fs::remove_file(vault.path().join("a.md")).unwrap();
+        let result = sync(config.clone(), vault.path()).await.unwrap();
+        assert_eq!(result.deleted, 1);
+        assert!(server.file("a.md").is_none());
+
+        // 远程删除 -> 本地移入 vault 回收站
+        server.remove("b.md");
+        let result = sync(config.clone(), vault.path()).await.unwrap();
+        assert_eq!(result.trashed, vec!["b.md".to_string()]);
+        assert!(!vault.path().join("b.md").exists());
+        let trash = fs::read_dir(vault.path().join(".lumina-trash")).unwrap().next().unwrap().unwrap();
+        assert_eq!(read(&trash.path(), "b.md"), "beta");
+    }
+
+    #[tokio::test]
+    async fn test_remote_deletion_reuploaded_by_default() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "a.md", "alpha");
+        sync(server.config(), vault.path()).await.unwrap();
+
+        server.remove("a.md");
+        let result = sync(server.config(), vault.path()).await.unwrap();
+        assert_eq!(result.uploaded, 1);
+        assert_eq!(server.file("a.md").unwrap(), b"alpha");
+        assert_eq!(read(vault.path(), "a.md"), "alpha");
+    }
+
+    #[tokio::test]
+    async fn test_renames_on_both_sides() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "a.md", "alpha");
+        write(vault.path(), "b.md", "beta");
+        sync(server.config(), vault.path()).await.unwrap();
+
+        // 本地重命名，远程由其他设备重新上传到新位置 (ETag 改变，需比较内容)
+        fs::rename(vault.path().join("a.md"), vault.path().join("renamed.md")).unwrap();
+        server.remove("b.md");
+        server.put_file("moved/b.md", b"beta");
+
+        let mut engine = engine(server.config(), vault.path());
+        let plan = engine.compute_sync_plan().await.unwrap();
+        assert_eq!(plan.move_count, 2);
+        let others: Vec<&str> = plan.items.iter()
+            .filter(|i| i.action != SyncAction::Move)
+            .map(|i| i.path.as_str())
+            .collect();
+        assert_eq!(others, vec!["moved"]);
+
+        server.clear_requests();
+        let result = engine.execute_sync(&plan).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.moved, 2);
+        assert!(server.requests("PUT").is_empty());
+        assert_eq!(server.requests("MOVE"), vec!["a.md"]);
+        assert!(server.file("a.md").is_none());
+        assert_eq!(server.file("renamed.md").unwrap(), b"alpha");
+        assert!(!vault.path().join("b.md").exists());
+        assert_eq!(read(vault.path(), "moved/b.md"), "beta");
+
+        // 同步记录已迁移到新路径
+        let result = sync(server.config(), vault.path()).await.unwrap();
+        assert!(result.affected.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_remote_move_keeps_etag() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "notes/big.pdf", &"x".repeat(4096));
+        sync(server.config(), vault.path()).await.unwrap();
+
+        // 另一台设备通过 MOVE 移动，ETag 不变，无需下载
+        WebDAVClient::new(server.config())
+            .unwrap()
+            .move_to("notes/big.pdf", "big.pdf")
+            .await
+            .unwrap();
+        server.clear_requests();
+
+        let result = sync(server.config(), vault.path()).await.unwrap();
+        assert_eq!(result.moved, 1);
+        assert_eq!(result.affected[0].moved_from.as_deref(), Some("notes/big.pdf"));
+        assert!(server.requests("GET").is_empty());
+        assert!(!vault.path().join("notes/big.pdf").exists());
+        assert_eq!(fs::metadata(vault.path().join("big.pdf")).unwrap().len(), 4096);
+    }
+
+    #[tokio::test]
+    async fn test_modified_rename_not_treated_as_move() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "a.md", "alpha");
+        sync(server.config(), vault.path()).await.unwrap();
+
+        fs::remove_file(vault.path().join("a.md")).unwrap();
+        write(vault.path(), "b.md", "bravo");
+
+        let result = sync(server.config(), vault.path()).await.unwrap();
+        assert_eq!(result.moved, 0);
+        assert_eq!(result.uploaded, 1);
+        assert_eq!(result.deleted, 1);
+        assert!(server.file("a.md").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_sync_resumes() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        for name in ["a.md", "b.md", "c.md"] {
+            write(vault.path(), name, name);
+        }
+
+        // 第二个文件开始时请求取消，当前文件完成后停止
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        let progress: ProgressSink = Arc::new(move |p: SyncProgress| {
+            if p.stage == SyncStage::Syncing && p.processed == 1 {
+                flag.store(true, Ordering::SeqCst);
+            }
+        });
+        let result = engine(server.config(), vault.path())
+            .with_progress(progress)
+            .with_cancel(cancel)
+            .quick_sync()
+            .await
+            .unwrap();
+        assert!(result.cancelled);
+        assert!(!result.success);
+        assert_eq!(result.uploaded, 2);
+
+        let root = vault.path().to_string_lossy().to_string();
+        assert_eq!(SyncEngine::pending_plan(&root).unwrap().items.len(), 3);
+
+        let result = engine(server.config(), vault.path())
+            .resume_sync()
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.uploaded, 1);
+        assert!(SyncEngine::pending_plan(&root).is_none());
+
+        // 每个文件只上传一次
+        let mut puts = server.requests("PUT");
+        puts.sort();
+        assert_eq!(puts, vec!["a.md", "b.md", "c.md"]);
+    }
+
+    #[tokio::test]
+    async fn test_each_item_checkpointed() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        for name in ["a.md", "b.md", "c.md"] {
+            write(vault.path(), name, name);
+        }
+
+        // 每个条目开始时，之前完成的条目都已落盘 (模拟此时退出后重新加载)
+        let config = server.config();
+        let root = vault.path().to_path_buf();
+        let persisted = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = persisted.clone();
+        let progress: ProgressSink = Arc::new(move |p: SyncProgress| {
+            if p.stage == SyncStage::Syncing {
+                let mut reloaded = engine(config.clone(), &root);
+                reloaded.load_state().unwrap();
+                let records = reloaded.state.map_or(0, |s| s.file_records.len());
+                seen.lock().unwrap().push((p.processed, records));
+            }
+        });
+        let result = engine(server.config(), vault.path())
+            .with_progress(progress)
+            .quick_sync()
+            .await
+            .unwrap();
+        assert_eq!(result.uploaded, 3);
+        assert_eq!(*persisted.lock().unwrap(), vec![(0, 0), (1, 1), (2, 2)]);
+
+        // 完成后断点日志合并进状态文件
+        assert!(!vault.path().join(".lumina-sync/checkpoints.jsonl").exists());
+        let mut reloaded = engine(server.config(), vault.path());
+        reloaded.load_state().unwrap();
+        assert_eq!(reloaded.state.unwrap().file_records.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_failed_upload_retried_next_sync() {
+        let server = TestServer::start().await;
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "a.md", "alpha");
+        write(vault.path(), "b.md", "beta");
+
+        server.fail("PUT", "b.md");
+        let result = sync(server.config(), vault.path()).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.uploaded, 1);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].path, "b.md");
+
+        server.clear_failures();
+        server.clear_requests();
+        let result = sync(server.config(), vault.path()).await.unwrap();
+        assert!(result.success);
+        assert_eq!(server.requests("PUT"), vec!["b.md"]);
+        assert_eq!(server.file("b.md").unwrap(), b"beta");
+    }
+
+    /// 两台设备通过同一远程端往返同步：新增、重命名、双方编辑合并
+    async fn exercise_backend(config: WebDAVConfig) {
+        let device_a = tempfile::tempdir().unwrap();
+        let device_b = tempfile::tempdir().unwrap();
+        write(device_a.path(), "notes/a.md", "one\ntwo\nthree\n");
+        write(device_a.path(), "b.md", "beta");
+
+        assert!(sync(config.clone(), device_a.path()).await.unwrap().success);
+        let result = sync(config.clone(), device_b.path()).await.unwrap();
+        assert!(result.success);
+        assert_eq!(read(device_b.path(), "notes/a.md"), "one\ntwo\nthree\n");
+
+        fs::rename(device_b.path().join("b.md"), device_b.path().join("renamed.md")).unwrap();
+        assert_eq!(sync(config.clone(), device_b.path()).await.unwrap().moved, 1);
+        assert_eq!(sync(config.clone(), device_a.path()).await.unwrap().moved, 1);
+        assert_eq!(read(device_a.path(), "renamed.md"), "beta");
+        assert!(!device_a.path().join("b.md").exists());
+
+        edit(device_a.path(), "notes/a.md", "ONE\ntwo\nthree\n");
+        sync(config.clone(), device_a.path()).await.unwrap();
+        edit(device_b.path(), "notes/a.md", "one\ntwo\nTHREE\n");
+        let result = full_sync(config.clone(), device_b.path()).await;
+        assert_eq!(result.merged, 1);
+        sync(config.clone(), device_a.path()).await.unwrap();
+        assert_eq!(read(device_a.path(), "notes/a.md"), "ONE\ntwo\nTHREE\n");
+    }
+
+    #[tokio::test]
+    async fn test_sync_through_s3() {
+        let server = S3TestServer::start().await;
+        exercise_backend(server.config()).await;
+        assert!(server.object("notes/a.md").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_sync_through_local_folder() {
+        let remote = tempfile::tempdir().unwrap();
+        let config = WebDAVConfig {
+            backend: StorageBackend::Local { path: remote.path().to_string_lossy().to_string() },
+            remote_base_path: "/vault".to_string(),
+            ..WebDAVConfig::default()
+        };
+        exercise_backend(config.clone()).await;
+        assert!(remote.path().join("vault/renamed.md").is_file());
+
+        // 目录不可用 (如 U 盘未插入) 时同步失败，而不是当作空的远程端
+        let vault = tempfile::tempdir().unwrap();
+        write(vault.path(), "a.md", "alpha");
+        let missing = WebDAVConfig {
+            backend: StorageBackend::Local { path: remote.path().join("unmounted").to_string_lossy().to_string() },
+            ..config
+        };
+        assert!(sync(missing, vault.path()).await.is_err());
+    }
 }
@@ -0,0 +1,892 @@
+//! 测试用的进程内 WebDAV / S3 服务器
+//!
+//! 基于 tokio TcpListener 的最小实现，数据保存在内存中。
+//! WebDAV 支持 PROPFIND (Depth 0/1，可选 infinity)、GET、PUT、MKCOL、DELETE、MOVE 和 ETag；
+//! 测试可以直接修改服务器内容来模拟其他设备、让指定请求失败来模拟中断，
+//! 要求 Basic / Digest / Bearer 认证，并检查收到的请求。S3 部分模拟 MinIO 的 path-style 对象 API
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use super::auth::parse_challenges;
+use super::types::{S3Config, StorageBackend, WebDAVConfig};
+
+/// 服务器上的资源
+#[derive(Debug, Clone)]
+enum Resource {
+    Dir,
+    File {
+        data: Vec<u8>,
+        etag: String,
+        modified: chrono::DateTime<chrono::Utc>,
+    },
+}
+
+/// 服务器状态
+#[derive(Default)]
+struct Shared {
+    files: Mutex<BTreeMap<String, Resource>>,
+    /// 收到的请求 (方法, 相对路径)
+    requests: Mutex<Vec<(String, String)>>,
+    /// 是否允许 PROPFIND Depth: infinity
+    infinity: AtomicBool,
+    /// ETag 计数器，每次写入递增
+    version: AtomicU64,
+    /// 失败的请求 (方法, 相对路径, 状态码)
+    failures: Mutex<Vec<(String, String, u16)>>,
+    /// 要求的认证方式
+    auth: Mutex<TestAuth>,
+    /// 当前的 Digest nonce 序号
+    nonce: AtomicU64,
+}
+
+/// 测试服务器要求的认证方式 (用户名 user，密码 pass)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum TestAuth {
+    /// 不检查认证
+    #[default]
+    None,
+    Basic,
+    /// Digest (MD5，qop=auth)
+    Digest,
+    /// Bearer token
+    Bearer(String),
+}
+
+impl Shared {
+    fn new_file(&self, data: Vec<u8>) -> Resource {
+        let version = self.version.fetch_add(1, Ordering::SeqCst) + 1;
+        Resource::File {
+            data,
+            etag: format!("\"v{}\"", version),
+            modified: chrono::Utc::now(),
+        }
+    }
+}
+
+type Store = Arc<Shared>;
+
+/// 运行中的测试服务器，drop 时停止
+pub struct TestServer {
+    url: String,
+    store: Store,
+    task: JoinHandle<()>,
+}
+
+/// 远程根目录 (对应 WebDAVConfig::remote_base_path)
+const BASE: &str = "vault";
+
+impl TestServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/dav", listener.local_addr().unwrap());
+
+        let store: Store = Arc::new(Shared::default());
+        {
+            let mut files = store.files.lock().unwrap();
+            files.insert(String::new(), Resource::Dir);
+            files.insert("dav".to_string(), Resource::Dir);
+            files.insert(format!("dav/{}", BASE), Resource::Dir);
+        }
+
+        let task = {
+            let store = store.clone();
+            serve(listener, Arc::new(move |request| handle(request, &store)))
+        };
+
+        Self { url, store, task }
+    }
+
+    /// 指向该服务器的配置
+    pub fn config(&self) -> WebDAVConfig {
+        WebDAVConfig {
+            server_url: self.url.clone(),
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            remote_base_path: format!("/{}", BASE),
+            ..WebDAVConfig::default()
+        }
+    }
+
+    /// 允许 PROPFIND Depth: infinity (默认与许多服务器一样拒绝)
+    pub fn set_depth_infinity(&self, enabled: bool) {
+        self.store.infinity.store(enabled, Ordering::SeqCst);
+    }
+
+    /// 读取远程文件原始内容 (相对于远程根目录)
+    pub fn file(&self, path: &str) -> Option<Vec<u8>> {
+        match self.store.files.lock().unwrap().get(&full_path(path)) {
+            Some(Resource::File { data, .. }) => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    /// 直接写入远程文件 (模拟其他设备)，自动创建上级目录
+    pub fn put_file(&self, path: &str, content: &[u8]) {
+        let full = full_path(path);
+        let mut files = self.store.files.lock().unwrap();
+        let mut parent = self::parent(&full);
+        while !parent.is_empty() && !files.contains_key(parent) {
+            files.insert(parent.to_string(), Resource::Dir);
+            parent = self::parent(parent);
+        }
+        files.insert(full, self.store.new_file(content.to_vec()));
+    }
+
+    /// 直接删除远程文件或目录 (模拟其他设备)
+    pub fn remove(&self, path: &str) {
+        let full = full_path(path);
+        let prefix = format!("{}/", full);
+        self.store
+            .files
+            .lock()
+            .unwrap()
+            .retain(|k, _| *k != full && !k.starts_with(&prefix));
+    }
+
+    /// 远程根目录下的所有路径 (含目录)
+    pub fn paths(&self) -> Vec<String> {
+        let prefix = format!("{}/", full_path(""));
+        self.store
+            .files
+            .lock()
+            .unwrap()
+            .keys()
+            .filter_map(|k| k.strip_prefix(&prefix).map(|p| p.to_string()))
+            .collect()
+    }
+
+    /// 指定方法的请求所涉及的路径 (相对于远程根目录)
+    pub fn requests(&self, method: &str) -> Vec<String> {
+        let prefix = full_path("");
+        self.store
+            .requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(m, _)| m == method)
+            .map(|(_, p)| p.strip_prefix(&prefix).unwrap_or(p).trim_start_matches('/').to_string())
+            .collect()
+    }
+
+    /// 清空请求记录
+    pub fn clear_requests(&self) {
+        self.store.requests.lock().unwrap().clear();
+    }
+
+    /// 让指定方法和路径的请求返回 503 (模拟网络中断)，直到 clear_failures
+    pub fn fail(&self, method: &str, path: &str) {
+        self.fail_with(method, path, 503);
+    }
+
+    /// 让指定方法和路径的请求返回给定状态码 (如 403)
+    pub fn fail_with(&self, method: &str, path: &str, status: u16) {
+        self.store
+            .failures
+            .lock()
+            .unwrap()
+            .push((method.to_string(), full_path(path), status));
+    }
+
+    /// 设置要求的认证方式
+    pub fn set_auth(&self, auth: TestAuth) {
+        *self.store.auth.lock().unwrap() = auth;
+    }
+
+    /// 使当前 Digest nonce 过期 (之后的请求收到 stale=true 质询)
+    pub fn expire_nonce(&self) {
+        self.store.nonce.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// 恢复正常响应
+    pub fn clear_failures(&self) {
+        self.store.failures.lock().unwrap().clear();
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn full_path(path: &str) -> String {
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        format!("dav/{}", BASE)
+    } else {
+        format!("dav/{}/{}", BASE, path)
+    }
+}
+
+/// 已解析的请求
+struct Request {
+    method: String,
+    /// 已解码、去掉首尾 `/` 的路径
+    path: String,
+    /// 已解码、只去掉开头 `/` 的路径 (S3 key 区分末尾的 `/`)
+    raw_path: String,
+    /// 已解码的查询参数
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn query(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// 响应
+struct Response {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+}
+
+type Handler = Arc<dyn Fn(&Request) -> Response + Send + Sync>;
+
+/// 每个连接处理一个请求
+fn serve(listener: TcpListener, handler: Handler) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(handle_connection(stream, handler.clone()));
+        }
+    })
+}
+
+async fn handle_connection(mut stream: TcpStream, handler: Handler) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+    let response = handler(&request);
+
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nConnection: close\r\n",
+        response.status,
+        reason(response.status)
+    );
+    // HEAD 响应自行给出 Content-Length
+    if !response.headers.iter().any(|(name, _)| *name == "Content-Length") {
+        head.push_str(&format!("Content-Length: {}\r\n", response.body.len()));
+    }
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(&response.body).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let path = urlencoding::decode(path).ok()?;
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter_map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            Some((urlencoding::decode(k).ok()?.into_owned(), urlencoding::decode(v).ok()?.into_owned()))
+        })
+        .collect();
+
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+
+    let content_length = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let mut body = buf[header_end + 4..].to_vec();
+    while body.len() < content_length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+
+    Some(Request {
+        method,
+        path: path.trim_matches('/').to_string(),
+        raw_path: path.trim_start_matches('/').to_string(),
+        query,
+        headers,
+        body,
+    })
+}
+
+/// 校验认证，失败时返回带质询的 401
+fn check_auth(request: &Request, store: &Store) -> Option<Response> {
+    let auth = store.auth.lock().unwrap().clone();
+    let nonce = format!("nonce-{}", store.nonce.load(Ordering::SeqCst));
+    let header = request.header("Authorization").unwrap_or("");
+    let unauthorized = |challenge: String| Response {
+        status: 401,
+        headers: vec![("WWW-Authenticate", challenge)],
+        body: Vec::new(),
+    };
+
+    match auth {
+        TestAuth::None => None,
+        TestAuth::Basic => {
+            let expected = format!("Basic {}", STANDARD.encode("user:pass"));
+            (header != expected).then(|| unauthorized(r#"Basic realm="lumina""#.to_string()))
+        }
+        TestAuth::Bearer(token) => {
+            (header != format!("Bearer {}", token)).then(|| unauthorized(r#"Bearer realm="lumina""#.to_string()))
+        }
+        TestAuth::Digest => {
+            let challenge = |stale: bool| {
+                format!(
+                    r#"Digest realm="lumina", qop="auth", algorithm=MD5, nonce="{}", opaque="op"{}"#,
+                    nonce,
+                    if stale { ", stale=true" } else { "" }
+                )
+            };
+            let params = parse_challenges(header);
+            let Some(digest) = params.first().filter(|c| c.scheme == "digest") else {
+                return Some(unauthorized(challenge(false)));
+            };
+            let param = |name: &str| digest.param(name).unwrap_or("").to_string();
+            if param("nonce") != nonce {
+                return Some(unauthorized(challenge(true)));
+            }
+
+            let md5 = |data: String| {
+                Md5::digest(data.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect::<String>()
+            };
+            let ha1 = md5("user:lumina:pass".to_string());
+            let ha2 = md5(format!("{}:{}", request.method, param("uri")));
+            let expected = md5(format!(
+                "{}:{}:{}:{}:auth:{}",
+                ha1,
+                nonce,
+                param("nc"),
+                param("cnonce"),
+                ha2
+            ));
+            let valid = param("username") == "user"
+                && param("opaque") == "op"
+                && param("uri").starts_with("/dav")
+                && param("response") == expected;
+            (!valid).then(|| unauthorized(challenge(false)))
+        }
+    }
+}
+
+fn handle(request: &Request, store: &Store) -> Response {
+    store
+        .requests
+        .lock()
+        .unwrap()
+        .push((request.method.clone(), request.path.clone()));
+
+    if let Some(response) = check_auth(request, store) {
+        return response;
+    }
+
+    let failing = store
+        .failures
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(method, path, _)| *method == request.method && *path == request.path)
+        .map(|(_, _, status)| *status);
+    if let Some(status) = failing {
+        return Response::status(status);
+    }
+
+    let mut files = store.files.lock().unwrap();
+    let path = request.path.clone();
+
+    match request.method.as_str() {
+        "PROPFIND" => {
+            let depth = request.header("Depth").unwrap_or("infinity");
+            let infinity = depth.eq_ignore_ascii_case("infinity");
+            if infinity && !store.infinity.load(Ordering::SeqCst) {
+                return Response::status(403);
+            }
+            let Some(resource) = files.get(&path) else {
+                return Response::status(404);
+            };
+
+            let mut xml = String::from(r#"<?xml version="1.0" encoding="utf-8"?><d:multistatus xmlns:d="DAV:">"#);
+            xml.push_str(&propfind_entry(&path, resource));
+            if depth == "1" || infinity {
+                for (child, resource) in descendants(&files, &path, infinity) {
+                    xml.push_str(&propfind_entry(&child, resource));
+                }
+            }
+            xml.push_str("</d:multistatus>");
+
+            Response {
+                status: 207,
+                headers: vec![("Content-Type", "application/xml; charset=utf-8".to_string())],
+                body: xml.into_bytes(),
+            }
+        }
+        "GET" => match files.get(&path) {
+            Some(Resource::File { data, etag, .. }) => Response {
+                status: 200,
+                headers: vec![("ETag", etag.clone())],
+                body: data.clone(),
+            },
+            _ => Response::status(404),
+        },
+        "PUT" => {
+            if !matches!(files.get(parent(&path)), Some(Resource::Dir)) {
+                return Response::status(409);
+            }
+            if matches!(files.get(&path), Some(Resource::Dir)) {
+                return Response::status(405);
+            }
+            let file = store.new_file(request.body.clone());
+            let Resource::File { etag, .. } = &file else { unreachable!() };
+            let etag = etag.clone();
+            let existed = files.insert(path, file).is_some();
+            Response {
+                status: if existed { 204 } else { 201 },
+                headers: vec![("ETag", etag)],
+                body: Vec::new(),
+            }
+        }
+        "MKCOL" => {
+            if files.contains_key(&path) {
+                return Response::status(405);
+            }
+            if !matches!(files.get(parent(&path)), Some(Resource::Dir)) {
+                return Response::status(409);
+            }
+            files.insert(path, Resource::Dir);
+            Response::status(201)
+        }
+        "MOVE" => {
+            // Destination 为完整 URL，只取路径部分
+            let Some(destination) = request
+                .header("Destination")
+                .and_then(|d| d.split_once("://"))
+                .and_then(|(_, rest)| rest.find('/').map(|i| rest[i..].to_string()))
+                .and_then(|d| urlencoding::decode(&d).ok().map(|d| d.trim_matches('/').to_string()))
+            else {
+                return Response::status(400);
+            };
+            if !files.contains_key(&path) {
+                return Response::status(404);
+            }
+            if !matches!(files.get(parent(&destination)), Some(Resource::Dir)) {
+                return Response::status(409);
+            }
+            let overwrite = !request.header("Overwrite").is_some_and(|o| o.eq_ignore_ascii_case("F"));
+            let existed = files.contains_key(&destination);
+            if existed && !overwrite {
+                return Response::status(412);
+            }
+
+            // 与 Apache mod_dav 一样移动后 ETag 不变
+            let prefix = format!("{}/", path);
+            let moved: Vec<String> = files
+                .keys()
+                .filter(|k| **k == path || k.starts_with(&prefix))
+                .cloned()
+                .collect();
+            let dest_prefix = format!("{}/", destination);
+            files.retain(|k, _| *k != destination && !k.starts_with(&dest_prefix));
+            for key in moved {
+                let resource = files.remove(&key).unwrap();
+                files.insert(format!("{}{}", destination, &key[path.len()..]), resource);
+            }
+            Response::status(if existed { 204 } else { 201 })
+        }
+        "DELETE" => {
+            if files.remove(&path).is_none() {
+                return Response::status(404);
+            }
+            let prefix = format!("{}/", path);
+            files.retain(|k, _| !k.starts_with(&prefix));
+            Response::status(204)
+        }
+        _ => Response::status(405),
+    }
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map(|(p, _)| p).unwrap_or("")
+}
+
+/// 子条目 (recursive 为 true 时包含所有后代)
+fn descendants<'a>(
+    files: &'a BTreeMap<String, Resource>,
+    path: &str,
+    recursive: bool,
+) -> impl Iterator<Item = (String, &'a Resource)> {
+    let prefix = if path.is_empty() { String::new() } else { format!("{}/", path) };
+    files
+        .iter()
+        .filter(move |(k, _)| {
+            !k.is_empty()
+                && k.starts_with(&prefix)
+                && (recursive || !k[prefix.len()..].contains('/'))
+        })
+        .map(|(k, v)| (k.clone(), v))
+}
+
+fn propfind_entry(path: &str, resource: &Resource) -> String {
+    let href: String = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| format!("/{}", urlencoding::encode(s)))
+        .collect();
+
+    let props = match resource {
+        Resource::Dir => "<d:resourcetype><d:collection/></d:resourcetype>".to_string(),
+        Resource::File { data, etag, modified } => format!(
+            "<d:resourcetype/><d:getcontentlength>{}</d:getcontentlength>\
+             <d:getetag>{}</d:getetag><d:getlastmodified>{}</d:getlastmodified>",
+            data.len(),
+            etag.replace('"', "&quot;"),
+            modified.format("%a, %d %b %Y %H:%M:%S GMT")
+        ),
+    };
+    let href = match resource {
+        Resource::Dir => format!("{}/", href),
+        Resource::File { .. } => href,
+    };
+
+    format!(
+        "<d:response><d:href>{}</d:href><d:propstat><d:prop>{}</d:prop>\
+         <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+        href, props
+    )
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        207 => "Multi-Status",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        412 => "Precondition Failed",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+/// S3 存储桶名
+const BUCKET: &str = "lumina";
+
+/// 存储的对象
+struct S3Object {
+    data: Vec<u8>,
+    etag: String,
+    modified: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Default)]
+struct S3Shared {
+    /// 对象 key -> 对象
+    objects: Mutex<BTreeMap<String, S3Object>>,
+    /// ListObjectsV2 每页最多返回的条目数
+    page_size: AtomicUsize,
+}
+
+/// 运行中的 S3 测试服务器 (MinIO 替身)，drop 时停止
+pub struct S3TestServer {
+    endpoint: String,
+    shared: Arc<S3Shared>,
+    task: JoinHandle<()>,
+}
+
+impl S3TestServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let shared = Arc::new(S3Shared {
+            page_size: AtomicUsize::new(1000),
+            ..S3Shared::default()
+        });
+
+        let task = {
+            let shared = shared.clone();
+            serve(listener, Arc::new(move |request| handle_s3(request, &shared)))
+        };
+
+        Self { endpoint, shared, task }
+    }
+
+    /// 指向该服务器的配置 (对象位于 `vault/` 前缀下)
+    pub fn config(&self) -> WebDAVConfig {
+        WebDAVConfig {
+            remote_base_path: format!("/{}", BASE),
+            backend: StorageBackend::S3(S3Config {
+                endpoint: self.endpoint.clone(),
+                bucket: BUCKET.to_string(),
+                region: "us-east-1".to_string(),
+                access_key: "minioadmin".to_string(),
+                secret_key: "minioadmin".to_string(),
+            }),
+            ..WebDAVConfig::default()
+        }
+    }
+
+    /// 列表分页大小
+    pub fn set_page_size(&self, size: usize) {
+        self.shared.page_size.store(size, Ordering::SeqCst);
+    }
+
+    /// 读取对象内容 (相对于远程根目录)
+    pub fn object(&self, path: &str) -> Option<Vec<u8>> {
+        self.shared
+            .objects
+            .lock()
+            .unwrap()
+            .get(&format!("{}/{}", BASE, path))
+            .map(|o| o.data.clone())
+    }
+
+    /// 所有对象 key (含前缀)
+    pub fn keys(&self) -> Vec<String> {
+        self.shared.objects.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+impl Drop for S3TestServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn s3_error(status: u16, code: &str) -> Response {
+    Response {
+        status,
+        headers: vec![("Content-Type", "application/xml".to_string())],
+        body: format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?><Error><Code>{}</Code></Error>", code)
+            .into_bytes(),
+    }
+}
+
+fn s3_object(data: Vec<u8>) -> S3Object {
+    // 与 S3 单次上传一样，ETag 只取决于内容
+    let hash = format!("{:x}", Sha256::digest(&data));
+    S3Object {
+        etag: format!("\"{}\"", &hash[..32]),
+        data,
+        modified: chrono::Utc::now(),
+    }
+}
+
+fn handle_s3(request: &Request, shared: &S3Shared) -> Response {
+    // 不重新计算签名，只检查签名格式和负载哈希
+    let signed = request
+        .header("Authorization")
+        .is_some_and(|a| a.starts_with("AWS4-HMAC-SHA256 Credential=minioadmin/"))
+        && request.header("x-amz-date").is_some()
+        && request.header("x-amz-content-sha256")
+            == Some(format!("{:x}", Sha256::digest(&request.body)).as_str());
+    if !signed {
+        return s3_error(403, "AccessDenied");
+    }
+
+    let (bucket, key) = request.raw_path.split_once('/').unwrap_or((request.raw_path.as_str(), ""));
+    if bucket != BUCKET {
+        return s3_error(404, "NoSuchBucket");
+    }
+    let mut objects = shared.objects.lock().unwrap();
+
+    match (request.method.as_str(), key) {
+        ("GET", "") if request.query("list-type") == Some("2") => {
+            let prefix = request.query("prefix").unwrap_or("");
+            let delimiter = request.query("delimiter");
+            let after = request.query("continuation-token").unwrap_or("");
+            let max_keys = request
+                .query("max-keys")
+                .and_then(|m| m.parse().ok())
+                .unwrap_or(1000)
+                .min(shared.page_size.load(Ordering::SeqCst));
+
+            let mut contents = String::new();
+            let mut prefixes: Vec<String> = Vec::new();
+            let mut count = 0;
+            let mut last = None;
+            let mut truncated = false;
+
+            for (key, object) in objects.range::<str, _>((std::ops::Bound::Excluded(after), std::ops::Bound::Unbounded)) {
+                if !key.starts_with(prefix) {
+                    continue;
+                }
+                let common = delimiter.and_then(|d| {
+                    key[prefix.len()..].find(d).map(|i| key[..prefix.len() + i + d.len()].to_string())
+                });
+                if let Some(common) = &common {
+                    if prefixes.contains(common) {
+                        last = Some(key.clone());
+                        continue;
+                    }
+                }
+                if count == max_keys {
+                    truncated = true;
+                    break;
+                }
+                count += 1;
+                last = Some(key.clone());
+                match common {
+                    Some(common) => prefixes.push(common),
+                    None => contents.push_str(&format!(
+                        "<Contents><Key>{}</Key><LastModified>{}</LastModified><ETag>{}</ETag>\
+                         <Size>{}</Size><StorageClass>STANDARD</StorageClass></Contents>",
+                        xml_escape(key),
+                        object.modified.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                        object.etag.replace('"', "&quot;"),
+                        object.data.len()
+                    )),
+                }
+            }
+
+            let mut xml = format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+                 <ListBucketResult xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\
+                 <Name>{}</Name><Prefix>{}</Prefix><KeyCount>{}</KeyCount><MaxKeys>{}</MaxKeys>\
+                 <IsTruncated>{}</IsTruncated>",
+                BUCKET,
+                xml_escape(prefix),
+                count,
+                max_keys,
+                truncated
+            );
+            if truncated {
+                if let Some(last) = last {
+                    xml.push_str(&format!("<NextContinuationToken>{}</NextContinuationToken>", xml_escape(&last)));
+                }
+            }
+            xml.push_str(&contents);
+            for common in prefixes {
+                xml.push_str(&format!("<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>", xml_escape(&common)));
+            }
+            xml.push_str("</ListBucketResult>");
+
+            Response {
+                status: 200,
+                headers: vec![("Content-Type", "application/xml".to_string())],
+                body: xml.into_bytes(),
+            }
+        }
+        ("GET", key) => match objects.get(key) {
+            Some(object) => Response {
+                status: 200,
+                headers: vec![("ETag", object.etag.clone())],
+                body: object.data.clone(),
+            },
+            None => s3_error(404, "NoSuchKey"),
+        },
+        ("HEAD", key) => match objects.get(key) {
+            Some(object) => Response {
+                status: 200,
+                headers: vec![
+                    ("Content-Length", object.data.len().to_string()),
+                    ("ETag", object.etag.clone()),
+                    ("Last-Modified", object.modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+                ],
+                body: Vec::new(),
+            },
+            None => Response::status(404),
+        },
+        ("PUT", key) if !key.is_empty() => {
+            let object = match request.header("x-amz-copy-source") {
+                Some(source) => {
+                    let source = urlencoding::decode(source).map(|s| s.into_owned()).unwrap_or_default();
+                    let source = source.trim_start_matches('/');
+                    let Some(source) = source.strip_prefix(&format!("{}/", BUCKET)).and_then(|k| objects.get(k)) else {
+                        return s3_error(404, "NoSuchKey");
+                    };
+                    s3_object(source.data.clone())
+                }
+                None => s3_object(request.body.clone()),
+            };
+            let etag = object.etag.clone();
+            let is_copy = request.header("x-amz-copy-source").is_some();
+            objects.insert(key.to_string(), object);
+
+            if is_copy {
+                Response {
+                    status: 200,
+                    headers: vec![("Content-Type", "application/xml".to_string())],
+                    body: format!("<CopyObjectResult><ETag>{}</ETag></CopyObjectResult>", etag.replace('"', "&quot;"))
+                        .into_bytes(),
+                }
+            } else {
+                Response {
+                    status: 200,
+                    headers: vec![("ETag", etag)],
+                    body: Vec::new(),
+                }
+            }
+        }
+        ("DELETE", key) if !key.is_empty() => {
+            objects.remove(key);
+            Response::status(204)
+        }
+        _ => s3_error(400, "InvalidRequest"),
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -11,14 +11,29 @@ pub struct WebDAVConfig {
     pub server_url: String,
     /// 用户名
     pub username: String,
-    /// 密码 (考虑后续改为加密存储)
+    /// 密码 (考虑后续改为加密存储)；Bearer 认证时为 token
     pub password: String,
+    /// 认证方式 (默认自动：先用 Basic，服务器要求 Digest 时切换)
+    #[serde(default)]
+    pub auth: AuthMethod,
     /// 远程根目录 (如 /notes)
     pub remote_base_path: String,
     /// 是否启用自动同步
     pub auto_sync: bool,
     /// 自动同步间隔 (秒)
     pub sync_interval_secs: u64,
+    /// 远程删除的处理方式 (默认不传播，重新上传)
+    #[serde(default)]
+    pub remote_delete_mode: RemoteDeleteMode,
+    /// 端到端加密 (None 表示以明文上传)
+    #[serde(default)]
+    pub encryption: Option<EncryptionConfig>,
+    /// 同步过滤规则 (另可在 vault 根目录放置 `.luminasyncignore`)
+    #[serde(default)]
+    pub rules: SyncRules,
+    /// 存储后端 (默认 WebDAV；remote_base_path 对所有后端都表示根目录下的子目录)
+    #[serde(default)]
+    pub backend: StorageBackend,
 }
 
 impl Default for WebDAVConfig {
@@ -27,13 +42,161 @@ impl Default for WebDAVConfig {
             server_url: String::new(),
             username: String::new(),
             password: String::new(),
+            auth: AuthMethod::default(),
             remote_base_path: "/".to_string(),
             auto_sync: false,
             sync_interval_secs: 300, // 5 分钟
+            remote_delete_mode: RemoteDeleteMode::default(),
+            encryption: None,
+            rules: SyncRules::default(),
+            backend: StorageBackend::default(),
         }
     }
 }
 
+/// 命名的同步配置 (如 "家里 NAS"、"公司服务器")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncProfile {
+    /// 配置 ID (新建时留空，由后端生成)
+    #[serde(default)]
+    pub id: String,
+    /// 显示名称
+    pub name: String,
+    pub config: WebDAVConfig,
+}
+
+/// 某个 vault 的全部同步配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VaultProfiles {
+    /// 当前使用的配置 ID
+    #[serde(default)]
+    pub active: Option<String>,
+    #[serde(default)]
+    pub profiles: Vec<SyncProfile>,
+}
+
+/// WebDAV 认证方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// 按服务器的 WWW-Authenticate 质询选择 Basic 或 Digest
+    #[default]
+    Auto,
+    /// 只使用 HTTP Basic
+    Basic,
+    /// 只使用 HTTP Digest (RFC 7616，支持 MD5 与 SHA-256)
+    Digest,
+    /// Bearer token / 应用令牌 (使用 password 字段)
+    Bearer,
+}
+
+/// 存储后端
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// WebDAV 服务器 (使用 server_url、username、password)
+    #[default]
+    WebDav,
+    /// S3 兼容对象存储 (AWS S3、MinIO 等)
+    S3(S3Config),
+    /// 本地或挂载的目录 (如 U 盘、网络共享)
+    Local { path: String },
+}
+
+/// S3 兼容存储配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct S3Config {
+    /// 服务端点 (如 https://s3.amazonaws.com 或 http://nas:9000)，使用 path-style 寻址
+    pub endpoint: String,
+    /// 存储桶
+    pub bucket: String,
+    /// 区域 (MinIO 通常为 us-east-1)
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+/// 远程文件被删除后本地的处理方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum RemoteDeleteMode {
+    /// 本地优先：重新上传本地文件
+    #[default]
+    Reupload,
+    /// 移入系统回收站
+    SystemTrash,
+    /// 移入 vault 内的 `.lumina-trash` 目录
+    VaultTrash,
+}
+
+/// 端到端加密设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    /// 用于派生密钥的口令 (只在本地使用，不会上传)
+    pub passphrase: String,
+    /// 是否加密文件名 (仅在首次创建远程加密清单时生效，之后以清单为准)
+    #[serde(default)]
+    pub encrypt_names: bool,
+}
+
+/// 同步过滤规则
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncRules {
+    /// 只同步匹配的文件 (glob，为空表示全部)
+    pub include: Vec<String>,
+    /// 排除匹配的文件/目录 (glob)
+    pub exclude: Vec<String>,
+    /// 单个文件大小上限 (字节)
+    pub max_file_size: Option<u64>,
+    /// 只同步这些扩展名 (为空表示全部)
+    pub allowed_extensions: Vec<String>,
+    /// 不同步这些扩展名
+    pub blocked_extensions: Vec<String>,
+}
+
+/// 路径被排除的原因
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExcludeReason {
+    /// 内置规则 (隐藏文件、node_modules 等)
+    Builtin { rule: String },
+    /// `.luminasyncignore` 中的规则
+    IgnoreFile { pattern: String },
+    /// 配置中的 exclude glob
+    ExcludeGlob { pattern: String },
+    /// 不匹配任何 include glob
+    NotIncluded,
+    /// 超过大小上限
+    TooLarge { size: u64, limit: u64 },
+    /// 扩展名不在允许列表中或在禁止列表中
+    FileType { extension: String },
+}
+
+/// 被同步规则排除的路径
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcludedPath {
+    /// 相对路径
+    pub path: String,
+    /// 是否为目录 (目录被排除时其下内容不再单独列出)
+    pub is_dir: bool,
+    /// 所在位置
+    pub side: SyncSide,
+    /// 排除原因
+    pub reason: ExcludeReason,
+}
+
+/// 本地或远程
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SyncSide {
+    Local,
+    Remote,
+}
+
 /// 远程文件/目录信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteEntry {
@@ -81,6 +244,11 @@ pub enum SyncAction {
     DeleteLocal,
     /// 冲突 - 需要用户决定
     Conflict,
+    /// 冲突已通过三方合并自动解决 (仅作为执行结果出现)
+    Merged,
+    /// 移动/重命名：`moved_from` 移到 `path`。本地已在新位置时移动远程 (MOVE)，
+    /// 否则在本地重命名
+    Move,
     /// 无需操作
     Skip,
 }
@@ -98,6 +266,9 @@ pub struct SyncPlanItem {
     pub remote: Option<RemoteEntry>,
     /// 原因说明
     pub reason: String,
+    /// 移动前的路径 (仅 Move)
+    #[serde(default)]
+    pub moved_from: Option<String>,
 }
 
 /// 同步计划
@@ -111,6 +282,9 @@ pub struct SyncPlan {
     pub download_count: usize,
     /// 冲突数量
     pub conflict_count: usize,
+    /// 移动/重命名数量
+    #[serde(default)]
+    pub move_count: usize,
 }
 
 /// 同步进度
@@ -128,6 +302,22 @@ pub struct SyncProgress {
     pub error: Option<String>,
 }
 
+impl SyncProgress {
+    /// 创建指定阶段的进度
+    pub fn new(stage: SyncStage) -> Self {
+        Self {
+            stage,
+            total: 0,
+            processed: 0,
+            current_file: None,
+            error: None,
+        }
+    }
+}
+
+/// 进度回调 (由命令层转发为前端事件)
+pub type ProgressSink = std::sync::Arc<dyn Fn(SyncProgress) + Send + Sync>;
+
 /// 同步阶段
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum SyncStage {
@@ -145,6 +335,8 @@ pub enum SyncStage {
     Syncing,
     /// 完成
     Completed,
+    /// 已取消 (未完成的条目可恢复)
+    Cancelled,
     /// 错误
     Error,
 }
@@ -154,6 +346,9 @@ pub enum SyncStage {
 pub struct SyncResult {
     /// 是否成功
     pub success: bool,
+    /// 是否被取消
+    #[serde(default)]
+    pub cancelled: bool,
     /// 上传成功数
     pub uploaded: usize,
     /// 下载成功数
@@ -162,12 +357,60 @@ pub struct SyncResult {
     pub deleted: usize,
     /// 冲突数
     pub conflicts: usize,
+    /// 自动合并数
+    #[serde(default)]
+    pub merged: usize,
+    /// 移动/重命名数
+    #[serde(default)]
+    pub moved: usize,
+    /// 因远程删除而移入回收站的本地路径
+    #[serde(default)]
+    pub trashed: Vec<String>,
+    /// 本次实际变更的路径
+    #[serde(default)]
+    pub affected: Vec<AffectedPath>,
     /// 错误列表
     pub errors: Vec<SyncError>,
     /// 同步耗时 (毫秒)
     pub duration_ms: u64,
 }
 
+/// 同步中发生变更的路径
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AffectedPath {
+    /// 相对路径
+    pub path: String,
+    /// 实际执行的动作
+    pub action: SyncAction,
+    /// 移动前的路径 (仅 Move)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moved_from: Option<String>,
+}
+
+/// 一次同步的历史记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncHistoryEntry {
+    /// 开始时间 (Unix 时间戳，秒)
+    pub started_at: u64,
+    /// 同步结果 (同步未能完成时为 None)
+    pub result: Option<SyncResult>,
+    /// 导致同步中止的错误
+    pub error: Option<String>,
+}
+
+/// 文件的历史版本快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteVersion {
+    /// 版本 ID (按时间排序)
+    pub id: String,
+    /// 文件相对路径
+    pub path: String,
+    /// 快照时间 (Unix 时间戳，秒)
+    pub created_at: u64,
+    /// 文件大小 (字节)
+    pub size: u64,
+}
+
 /// 同步错误
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncError {
@@ -180,7 +423,7 @@ pub struct SyncError {
 }
 
 /// 同步状态记录 (用于增量同步)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SyncState {
     /// 上次同步时间 (Unix 时间戳)
     pub last_sync: u64,
@@ -199,4 +442,7 @@ pub struct FileRecord {
     pub remote_mtime: u64,
     /// 上次同步时的 ETag
     pub etag: Option<String>,
+    /// 上次同步时的内容哈希 (SHA-256，十六进制)
+    #[serde(default)]
+    pub content_hash: Option<String>,
 }
@@ -1,5 +1,4 @@
 import { useEffect, useCallback, useState } from "react";
-import { open } from "@tauri-apps/plugin-dialog";
 import { Sidebar } from "@/components/layout/Sidebar";
 import { RightPanel } from "@/components/layout/RightPanel";
 import { ResizeHandle } from "@/components/toolbar/ResizeHandle";
@@ -7,9 +6,13 @@ import { Ribbon } from "@/components/layout/Ribbon";
 import { KnowledgeGraph } from "@/components/effects/KnowledgeGraph";
 import { Editor } from "@/editor/Editor";
 import { SplitEditor } from "@/components/layout/SplitEditor";
-import { useFileStore } from "@/stores/useFileStore";
+import { useFileStore, whenVaultReady } from "@/stores/useFileStore";
 import { useUIStore } from "@/stores/useUIStore";
 import { useNoteIndexStore } from "@/stores/useNoteIndexStore";
+import { useWebDAVStore } from "@/stores/useWebDAVStore";
+import { stopWebDAVAutoSync } from "@/services/webdav";
+import { InterruptedSyncBanner } from "@/components/settings/InterruptedSyncBanner";
+import { SaveConflictBanner } from "@/components/layout/SaveConflictBanner";
 import { useRAGStore } from "@/stores/useRAGStore";
 import { FolderOpen, Sparkles, PanelLeftClose, PanelRightClose, PanelLeft, PanelRight, Globe, ChevronDown } from "lucide-react";
 import { useLocaleStore } from "@/stores/useLocaleStore";
@@ -25,7 +28,7 @@ import { PDFViewer } from "@/components/pdf";
 import { BrowserView } from "@/components/browser";
 import { FlashcardView } from "@/components/flashcard";
 import { useAIStore } from "@/stores/useAIStore";
-import { saveFile } from "@/lib/tauri";
+import { saveFile, type FsEvent, type TreeChange } from "@/lib/tauri";
 import { TitleBar } from "@/components/layout/TitleBar";
 import { VoiceInputBall } from "@/components/ai/VoiceInputBall";
 import { enableDebugLogger } from "@/lib/debugLogger";
@@ -122,10 +125,17 @@ interface BrowserNewTabEventPayload {
 // 避免在 React 严格模式和 HMR 下重复注册浏览器新标签事件监听
 let browserNewTabListenerRegistered = false;
 
+// 串行执行文件监听的启动/停止，保证旧 vault 的停止先于新 vault 的启动
+let fileWatcherQueue: Promise<unknown> = Promise.resolve();
+function queueFileWatcherOp(op: () => Promise<void>): Promise<void> {
+  const next = fileWatcherQueue.then(op);
+  fileWatcherQueue = next.catch(() => {});
+  return next;
+}
+
 function App() {
-  const { vaultPath, setVaultPath, currentFile, save, createNewFile, tabs, activeTabIndex, fileTree, refreshFileTree, openAIMainTab } = useFileStore();
+  const { vaultPath, setVaultPath, currentFile, save, createNewFile, tabs, activeTabIndex, sidebarTree, refreshFileTree, applyTreeChanges, openAIMainTab } = useFileStore();
   const { pendingDiff } = useAIStore();
-  const { buildIndex } = useNoteIndexStore();
   const { initialize: initializeRAG, config: ragConfig } = useRAGStore();
   
   // Get active tab
@@ -145,7 +155,7 @@ function App() {
   
   // 启动时自动加载保存的工作空间
   useEffect(() => {
-    if (vaultPath && fileTree.length === 0 && !isLoadingVault) {
+    if (vaultPath && sidebarTree.length === 0 && !isLoadingVault) {
       setIsLoadingVault(true);
       refreshFileTree().finally(() => setIsLoadingVault(false));
     }
@@ -156,27 +166,58 @@ function App() {
     if (!vaultPath) return;
     
     let unlisten: (() => void) | null = null;
+    let unlistenLinks: (() => void) | null = null;
+    let unlistenChanges: (() => void) | null = null;
     let debounceTimer: ReturnType<typeof setTimeout> | null = null;
+    const pending: TreeChange[] = [];
+    
+    let disposed = false;
     
     const setupWatcher = async () => {
       try {
-        const { listen } = await import("@tauri-apps/api/event");
-        const { startFileWatcher } = await import("@/lib/tauri");
-        
-        // 启动后端文件监听
-        await startFileWatcher(vaultPath);
+        // 启动后端文件监听（事件只发送给当前窗口），同步入队以保证先于本次清理时的停止
+        const started = queueFileWatcherOp(async () => {
+          await whenVaultReady();
+          const { startFileWatcher } = await import("@/lib/tauri");
+          await startFileWatcher(vaultPath);
+        });
+        const { getCurrentWebviewWindow } = await import("@tauri-apps/api/webviewWindow");
+        await started;
         console.log("[FileWatcher] Started watching:", vaultPath);
-        
-        // 监听文件变化事件（带防抖）
-        unlisten = await listen("fs:change", (event) => {
-          console.log("[FileWatcher] File changed:", event.payload);
-          
-          // 防抖：500ms 内多次变化只刷新一次
+
+        // 打开全文索引并增量同步，之后由文件监听保持更新（在监听启动后同步，不会漏掉期间的修改）
+        const { openSearchIndex } = await import("@/lib/tauri");
+        openSearchIndex(vaultPath)
+          .catch((error) => console.warn("[SearchIndex] Failed to open:", error));
+
+        // 打开后端链接索引，链接变化时刷新反向链接与图谱
+        useNoteIndexStore.getState().buildIndex(vaultPath)
+          .catch((error) => console.warn("[NoteIndex] Failed to open:", error));
+        const stopLinks = await getCurrentWebviewWindow().listen("links:changed", () => {
+          useNoteIndexStore.getState().refresh()
+            .catch((error) => console.warn("[NoteIndex] Failed to refresh:", error));
+        });
+        if (disposed) stopLinks();
+        else unlistenLinks = stopLinks;
+
+        // 监听文件树增量变化（合并 100ms 内的变化后一次应用，无需重新列出整个 vault）
+        // 使用窗口级监听，避免收到其他窗口的监听器事件
+        const stop = await getCurrentWebviewWindow().listen<TreeChange>("fs:tree", (event) => {
+          pending.push(event.payload);
           if (debounceTimer) clearTimeout(debounceTimer);
           debounceTimer = setTimeout(() => {
-            refreshFileTree();
-          }, 500);
+            applyTreeChanges(pending.splice(0));
+          }, 100);
         });
+        if (disposed) stop();
+        else unlisten = stop;
+
+        // 其他窗口或外部程序改动的文件：重命名时更新标签页路径，内容变化时重新加载
+        const stopChanges = await getCurrentWebviewWindow().listen<FsEvent[]>("fs:change", (event) => {
+          useFileStore.getState().applyFileEvents(event.payload);
+        });
+        if (disposed) stopChanges();
+        else unlistenChanges = stopChanges;
       } catch (error) {
         console.warn("[FileWatcher] Failed to start:", error);
       }
@@ -185,10 +226,18 @@ function App() {
     setupWatcher();
     
     return () => {
+      disposed = true;
       if (unlisten) unlisten();
+      if (unlistenLinks) unlistenLinks();
+      if (unlistenChanges) unlistenChanges();
       if (debounceTimer) clearTimeout(debounceTimer);
+      // 切换 vault 或卸载时停止旧的监听
+      queueFileWatcherOp(async () => {
+        const { stopFileWatcher } = await import("@/lib/tauri");
+        await stopFileWatcher(vaultPath);
+      }).catch((error) => console.warn("[FileWatcher] Failed to stop:", error));
     };
-  }, [vaultPath, refreshFileTree]);
+  }, [vaultPath, applyTreeChanges]);
 
   // 监听后端触发的浏览器新标签事件（window.open）
   useEffect(() => {
@@ -232,22 +281,27 @@ function App() {
     splitView,
   } = useUIStore();
 
-  // Build note index when file tree changes
-  useEffect(() => {
-    if (fileTree.length > 0) {
-      buildIndex(fileTree);
-    }
-  }, [fileTree, buildIndex]);
-
   // Initialize RAG system when vault is opened (if enabled and configured)
   useEffect(() => {
     if (vaultPath && ragConfig.enabled && ragConfig.embeddingApiKey) {
-      initializeRAG(vaultPath).catch((error) => {
+      whenVaultReady().then(() => initializeRAG(vaultPath)).catch((error) => {
         console.warn("[RAG] Failed to initialize:", error);
       });
     }
   }, [vaultPath, ragConfig.enabled, ragConfig.embeddingApiKey, initializeRAG]);
 
+  // 加载 vault 的同步配置（开启 auto_sync 时启动后台自动同步，否则提示恢复被中断的同步），关闭或切换 vault 时停止
+  useEffect(() => {
+    if (!vaultPath) return;
+    whenVaultReady()
+      .then(() => useWebDAVStore.getState().loadProfiles(vaultPath))
+      .then(() => useWebDAVStore.getState().checkInterruptedSync(vaultPath))
+      .catch((error) => console.warn("[WebDAV] Failed to load sync profiles:", error));
+    return () => {
+      stopWebDAVAutoSync().catch((error) => console.warn("[WebDAV] Failed to stop auto sync:", error));
+    };
+  }, [vaultPath]);
+
   // 全局鼠标拖拽处理：模拟从文件树拖拽文件创建双链
   useEffect(() => {
     let dragIndicator: HTMLDivElement | null = null;
@@ -365,13 +419,11 @@ function App() {
 
   // Open folder dialog
   const handleOpenVault = useCallback(async () => {
-    const selected = await open({
-      directory: true,
-      multiple: false,
-      title: "选择笔记文件夹",
-    });
+    // 由后端弹出系统对话框并确认所选目录
+    const { pickVaultRoot } = await import("@/lib/tauri");
+    const selected = await pickVaultRoot("选择笔记文件夹");
 
-    if (selected && typeof selected === "string") {
+    if (selected) {
       setVaultPath(selected);
     }
   }, [setVaultPath]);
@@ -525,6 +577,8 @@ function App() {
 
       {/* Main content - switches between Editor, Graph, Split, Diff, VideoNote and AI Chat based on state */}
       <main className="flex-1 flex flex-col overflow-hidden min-w-0">
+        <InterruptedSyncBanner />
+        <SaveConflictBanner />
         {pendingDiff ? (
           // Show diff view when there's a pending AI edit
           <DiffViewWrapper />
@@ -28,24 +28,24 @@ export const GetBacklinksTool: ToolExecutor = {
     }
 
     try {
-      const { getBacklinks, noteIndex, buildIndex } = useNoteIndexStore.getState();
-      const { fileTree } = useFileStore.getState();
+      const { getBacklinks, resolveNote, buildIndex, vaultPath } = useNoteIndexStore.getState();
+      const workspacePath = useFileStore.getState().vaultPath;
 
-      // 如果索引为空，先建立索引
-      if (noteIndex.size === 0 && fileTree.length > 0) {
-        await buildIndex(fileTree);
+      // 如果索引尚未打开，先打开后端链接索引
+      if (!vaultPath && workspacePath) {
+        await buildIndex(workspacePath);
       }
 
-      // 获取反向链接
-      // 尝试多种匹配方式：完整名称、不带 .md 的名称
+      // 按路径、笔记名或别名查找目标笔记
       const cleanName = noteName.replace(/\.md$/, "");
-      let backlinks = getBacklinks(cleanName);
-
-      // 如果没有找到，尝试从路径中提取名称
-      if (backlinks.length === 0 && noteName.includes("/")) {
-        const baseName = noteName.split("/").pop()?.replace(/\.md$/, "") || "";
-        backlinks = getBacklinks(baseName);
+      const notePath = resolveNote(cleanName);
+      if (!notePath) {
+        return {
+          success: true,
+          content: `Note "${cleanName}" not found.`,
+        };
       }
+      const backlinks = await getBacklinks(notePath);
 
       if (backlinks.length === 0) {
         return {
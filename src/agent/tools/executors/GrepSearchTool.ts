@@ -5,7 +5,7 @@
 
 import { ToolExecutor, ToolResult, ToolContext } from "../../types";
 import { useFileStore } from "@/stores/useFileStore";
-import { readFile, FileEntry } from "@/lib/tauri";
+import { searchVault, SearchResults } from "@/lib/tauri";
 import { toolMsg } from "./messages";
 
 interface GrepMatch {
@@ -38,9 +38,8 @@ export const GrepSearchTool: ToolExecutor = {
     }
 
     try {
-      // 获取文件树
-      const { fileTree } = useFileStore.getState();
-      if (!fileTree || fileTree.length === 0) {
+      const vaultPath = context.workspacePath || useFileStore.getState().vaultPath;
+      if (!vaultPath) {
         return {
           success: false,
           content: "",
@@ -48,83 +47,32 @@ export const GrepSearchTool: ToolExecutor = {
         };
       }
 
-      // 构建搜索模式
-      let pattern: RegExp;
+      // 后端遍历笔记库（遵循 .luminaignore），只搜索 Markdown 文件
+      let results: SearchResults;
       try {
-        if (isRegex) {
-          pattern = new RegExp(query, caseSensitive ? "g" : "gi");
-        } else {
-          // 转义特殊字符
-          const escaped = query.replace(/[.*+?^${}()|[\]\\]/g, "\\$&");
-          pattern = new RegExp(escaped, caseSensitive ? "g" : "gi");
-        }
+        results = await searchVault(vaultPath, query, {
+          regex: isRegex,
+          case_sensitive: caseSensitive,
+          include: ["**/*.md"],
+          directory: directory?.replace(/\\/g, "/"),
+          max_results: limit,
+        });
       } catch (e) {
         return {
           success: false,
           content: "",
-          error: `Regex error: ${e instanceof Error ? e.message : "invalid regex"}`,
+          error: `Regex error: ${e instanceof Error ? e.message : String(e)}`,
         };
       }
 
-      // 获取所有文件
-      const allFiles: { path: string; relativePath: string }[] = [];
-      const flattenTree = (entries: FileEntry[], basePath: string = "") => {
-        for (const entry of entries) {
-          if (entry.is_dir && entry.children) {
-            flattenTree(entry.children, basePath);
-          } else if (!entry.is_dir && entry.name.endsWith(".md")) {
-            // 计算相对路径
-            const relativePath = entry.path.replace(context.workspacePath, "").replace(/^[/\\]/, "");
-            
-            // 如果指定了目录，过滤
-            if (directory) {
-              const normalizedDir = directory.replace(/\\/g, "/");
-              const normalizedPath = relativePath.replace(/\\/g, "/");
-              if (!normalizedPath.startsWith(normalizedDir)) {
-                continue;
-              }
-            }
-            
-            allFiles.push({
-              path: entry.path,
-              relativePath,
-            });
-          }
-        }
-      };
-      flattenTree(fileTree);
-
-      // 搜索所有文件
-      const matches: GrepMatch[] = [];
-      
-      for (const file of allFiles) {
-        if (matches.length >= limit) break;
-
-        try {
-          const content = await readFile(file.path);
-          const lines = content.split("\n");
-
-          for (let i = 0; i < lines.length; i++) {
-            if (matches.length >= limit) break;
-
-            const line = lines[i];
-            pattern.lastIndex = 0;
-            const match = pattern.exec(line);
-
-            if (match) {
-              matches.push({
-                path: file.relativePath,
-                line: i + 1,
-                content: line.trim().slice(0, 200),
-                matchText: match[0],
-              });
-            }
-          }
-        } catch (error) {
-          // 跳过无法读取的文件
-          continue;
-        }
-      }
+      const matches: GrepMatch[] = results.files.flatMap((file) =>
+        file.matches.map((m) => ({
+          path: file.relative_path,
+          line: m.line,
+          content: m.text.trim().slice(0, 200),
+          matchText: m.text.slice(m.ranges[0][0], m.ranges[0][1]),
+        }))
+      );
 
       if (matches.length === 0) {
         return {
@@ -138,7 +86,7 @@ export const GrepSearchTool: ToolExecutor = {
         return `${i + 1}. **${m.path}**:${m.line}\n   \`${m.content}\``;
       }).join("\n\n");
 
-      const truncatedNote = matches.length >= limit 
+      const truncatedNote = results.truncated
         ? `\n\n(Results truncated, showing first ${limit})` 
         : "";
 
@@ -10,6 +10,8 @@ interface DiffLine {
 }
 
 interface DiffViewProps {
+  /** Header title (defaults to the AI edit preview) */
+  title?: string;
   fileName: string;
   original: string;
   modified: string;
@@ -192,6 +194,7 @@ function SourceDiffView({ diffLines }: { diffLines: DiffLine[] }) {
 }
 
 export function DiffView({ 
+  title = "AI 修改预览",
   fileName, 
   original, 
   modified, 
@@ -221,7 +224,7 @@ export function DiffView({
         <div className="h-12 border-b border-border flex items-center justify-between px-4 bg-yellow-500/10">
           <div className="flex items-center gap-3">
             <FileText size={16} className="text-yellow-600" />
-            <span className="font-medium text-sm">AI 修改预览</span>
+            <span className="font-medium text-sm">{title}</span>
             <span className="text-xs text-muted-foreground">{fileName}</span>
           </div>
           <div className="flex items-center gap-2">
@@ -252,7 +255,7 @@ export function DiffView({
       <div className="h-12 border-b border-border flex items-center justify-between px-4 bg-yellow-500/10">
         <div className="flex items-center gap-3">
           <FileText size={16} className="text-yellow-600" />
-          <span className="font-medium text-sm">AI 修改预览</span>
+          <span className="font-medium text-sm">{title}</span>
           <span className="text-xs text-muted-foreground">{fileName}</span>
           <span className="text-xs px-2 py-0.5 rounded bg-[hsl(var(--diff-add-bg)/0.3)] text-[hsl(var(--diff-add-text))]">+{stats.added}</span>
           <span className="text-xs px-2 py-0.5 rounded bg-[hsl(var(--diff-remove-bg)/0.3)] text-[hsl(var(--diff-remove-text))]">-{stats.removed}</span>
@@ -1,5 +1,6 @@
 import { useState, useEffect, useRef, useCallback, useMemo } from "react";
 import { useFileStore } from "@/stores/useFileStore";
+import { useNoteIndexStore } from "@/stores/useNoteIndexStore";
 import { useLocaleStore } from "@/stores/useLocaleStore";
 import {
   ZoomIn,
@@ -75,6 +76,11 @@ export function extractWikiLinks(content: string): string[] {
   return [...new Set(links)]; // Remove duplicates
 }
 
+// 路径比较时统一分隔符
+function normalizePath(path: string): string {
+  return path.replace(/\\/g, "/");
+}
+
 // Physics Engine
 const PhysicsEngine = {
   init: (nodes: GraphNode[], width: number, height: number): GraphNode[] => {
@@ -212,6 +218,7 @@ interface GraphCache {
   nodes: GraphNode[];
   edges: GraphEdge[];
   fileTreeHash: string; // 用于检测文件树是否变化
+  linkIndexTime: number; // 链接索引更新时间，链接变化后重建
   timestamp: number;
 }
 
@@ -229,7 +236,8 @@ export function KnowledgeGraph({ className = "", isolatedNode }: KnowledgeGraphP
   const nodesRef = useRef<GraphNode[]>([]);
   const edgesRef = useRef<GraphEdge[]>([]);
 
-  const { fileTree, currentFile, openFile, openIsolatedGraphTab } = useFileStore();
+  const { vaultPath, fileTree, currentFile, openFile, openIsolatedGraphTab } = useFileStore();
+  const lastIndexTime = useNoteIndexStore((state) => state.lastIndexTime);
   const { t } = useLocaleStore();
 
   const [selectedNode, setSelectedNode] = useState<GraphNode | null>(null);
@@ -305,6 +313,7 @@ export function KnowledgeGraph({ className = "", isolatedNode }: KnowledgeGraphP
     const nodes: GraphNode[] = [];
     const edges: GraphEdge[] = [];
     const nodeMap = new Map<string, GraphNode>();
+    const pathToNode = new Map<string, GraphNode>(); // 规范化路径 -> 文件节点
     const folderColorMap = new Map<string, { folderColor: string; fileColor: string }>(); // 文件夹路径 -> 颜色对
     let colorIndex = 0;
 
@@ -379,6 +388,7 @@ export function KnowledgeGraph({ className = "", isolatedNode }: KnowledgeGraphP
           };
           nodes.push(fileNode);
           nodeMap.set(nodeName.toLowerCase(), fileNode);
+          pathToNode.set(normalizePath(entry.path), fileNode);
 
           // 创建文件到父文件夹的父子关系边
           if (parentPath) {
@@ -394,35 +404,30 @@ export function KnowledgeGraph({ className = "", isolatedNode }: KnowledgeGraphP
 
     processEntries(fileTree, null, 0);
 
-    // 读取文件内容，提取双链
-    const { readFile } = await import("@/lib/tauri");
-    
-    for (const node of nodes) {
-      if (node.isFolder) continue; // 跳过文件夹
-      
+    // 从后端链接索引获取双链（已解析别名、标题和 Markdown 链接）
+    if (vaultPath) {
       try {
-        const content = await readFile(node.path);
-        const links = extractWikiLinks(content);
-        
-        for (const linkName of links) {
-          const targetNode = nodeMap.get(linkName.toLowerCase());
-          if (targetNode && targetNode.id !== node.id && !targetNode.isFolder) {
-            // 检查双链边是否已存在
-            const exists = edges.some(
-              (e) =>
-                e.type === 'link' &&
-                ((e.source === node.id && e.target === targetNode.id) ||
-                (e.source === targetNode.id && e.target === node.id))
-            );
-            if (!exists) {
-              edges.push({ source: node.id, target: targetNode.id, type: 'link' });
-              node.connections++;
-              targetNode.connections++;
-            }
+        const { getLinkGraph } = await import("@/lib/tauri");
+        const graph = await getLinkGraph(vaultPath);
+        for (const link of graph.edges) {
+          const node = pathToNode.get(normalizePath(link.source));
+          const targetNode = pathToNode.get(normalizePath(link.target));
+          if (!node || !targetNode) continue;
+          // 检查双链边是否已存在
+          const exists = edges.some(
+            (e) =>
+              e.type === 'link' &&
+              ((e.source === node.id && e.target === targetNode.id) ||
+              (e.source === targetNode.id && e.target === node.id))
+          );
+          if (!exists) {
+            edges.push({ source: node.id, target: targetNode.id, type: 'link' });
+            node.connections++;
+            targetNode.connections++;
           }
         }
       } catch (error) {
-        // Skip files that can't be read
+        // 链接索引尚未打开时只显示文件节点
       }
     }
 
@@ -431,19 +436,20 @@ export function KnowledgeGraph({ className = "", isolatedNode }: KnowledgeGraphP
       nodes,
       edges,
       fileTreeHash: computeFileTreeHash(fileTree),
+      linkIndexTime: lastIndexTime,
       timestamp: Date.now(),
     };
 
     applyGraphData(nodes, edges, showFolders);
   // eslint-disable-next-line react-hooks/exhaustive-deps
-  }, [fileTree, applyGraphData]); // showFolders 变化时不需要重建，只需要 loadGraph 重新应用缓存
+  }, [vaultPath, fileTree, lastIndexTime, applyGraphData]); // showFolders 变化时不需要重建，只需要 loadGraph 重新应用缓存
 
   // 使用缓存或构建新图
   const loadGraph = useCallback(async () => {
     const currentHash = computeFileTreeHash(fileTree);
     
     // 如果有缓存且文件树没变，直接使用缓存
-    if (graphCache && graphCache.fileTreeHash === currentHash) {
+    if (graphCache && graphCache.fileTreeHash === currentHash && graphCache.linkIndexTime === lastIndexTime) {
       console.log("[Graph] Using cached data");
       applyGraphData(graphCache.nodes, graphCache.edges, showFolders);
       return;
@@ -453,7 +459,7 @@ export function KnowledgeGraph({ className = "", isolatedNode }: KnowledgeGraphP
     console.log("[Graph] Building new graph...");
     await buildGraph();
   // eslint-disable-next-line react-hooks/exhaustive-deps
-  }, [fileTree, buildGraph, applyGraphData]); // showFolders 由专门的 effect 处理
+  }, [fileTree, lastIndexTime, buildGraph, applyGraphData]); // showFolders 由专门的 effect 处理
 
   // Build graph on mount and when file tree changes
   useEffect(() => {
@@ -1,7 +1,7 @@
 import { useState, useEffect, useRef, useCallback } from "react";
 import { useFileStore } from "@/stores/useFileStore";
 import { extractWikiLinks } from "./KnowledgeGraph";
-import { readFile } from "@/lib/tauri";
+import { useNoteIndexStore } from "@/stores/useNoteIndexStore";
 
 interface LocalNode {
   id: string;
@@ -32,6 +32,7 @@ export function LocalGraph({ className = "" }: LocalGraphProps) {
   const edgesRef = useRef<LocalEdge[]>([]);
 
   const { fileTree, currentFile, openFile, currentContent } = useFileStore();
+  const { getBacklinks, lastIndexTime } = useNoteIndexStore();
   
   const [dimensions, setDimensions] = useState({ width: 200, height: 150 });
   const [hoverNode, setHoverNode] = useState<string | null>(null);
@@ -125,52 +126,37 @@ export function LocalGraph({ className = "" }: LocalGraphProps) {
       }
     }
 
-    // 3. 扫描反向链接（引用当前笔记的其他笔记）
-    const scanBacklinks = async (entries: typeof fileTree) => {
-      for (const entry of entries) {
-        if (entry.is_dir && entry.children) {
-          await scanBacklinks(entry.children);
-        } else if (!entry.is_dir && entry.name.endsWith('.md') && entry.path !== currentFile) {
-          try {
-            const fileContent = await readFile(entry.path);
-            const links = extractWikiLinks(fileContent);
-            
-            if (links.some(l => l.toLowerCase() === currentName.toLowerCase())) {
-              const backLinkName = entry.name.replace('.md', '');
-              
-              if (!nodeMap.has(backLinkName.toLowerCase())) {
-                const node: LocalNode = {
-                  id: backLinkName,
-                  label: backLinkName,
-                  path: entry.path,
-                  x: 0,
-                  y: 0,
-                  vx: 0,
-                  vy: 0,
-                  isCurrent: false,
-                  isBacklink: true,
-                };
-                nodes.push(node);
-                nodeMap.set(backLinkName.toLowerCase(), node);
-              } else {
-                // 如果节点已存在（作为出链），标记它也是反向链接
-                const existingNode = nodeMap.get(backLinkName.toLowerCase());
-                if (existingNode) existingNode.isBacklink = true;
-              }
-              
-              // 添加反向边（从其他笔记指向当前笔记）
-              if (!edges.some(e => e.source === backLinkName && e.target === currentName)) {
-                edges.push({ source: backLinkName, target: currentName });
-              }
-            }
-          } catch {
-            // 忽略读取失败的文件
-          }
-        }
+    // 3. 反向链接（引用当前笔记的其他笔记），来自后端链接索引
+    const backlinks = await getBacklinks(currentFile).catch(() => []);
+    for (const backlink of backlinks) {
+      if (backlink.path === currentFile) continue;
+      const backLinkName = backlink.name;
+
+      if (!nodeMap.has(backLinkName.toLowerCase())) {
+        const node: LocalNode = {
+          id: backLinkName,
+          label: backLinkName,
+          path: backlink.path,
+          x: 0,
+          y: 0,
+          vx: 0,
+          vy: 0,
+          isCurrent: false,
+          isBacklink: true,
+        };
+        nodes.push(node);
+        nodeMap.set(backLinkName.toLowerCase(), node);
+      } else {
+        // 如果节点已存在（作为出链），标记它也是反向链接
+        const existingNode = nodeMap.get(backLinkName.toLowerCase());
+        if (existingNode) existingNode.isBacklink = true;
       }
-    };
 
-    await scanBacklinks(fileTree);
+      // 添加反向边（从其他笔记指向当前笔记）
+      if (!edges.some(e => e.source === backLinkName && e.target === currentName)) {
+        edges.push({ source: backLinkName, target: currentName });
+      }
+    }
 
     // 4. 初始化节点位置（环形分布）
     const otherNodes = nodes.filter(n => !n.isCurrent);
@@ -185,7 +171,7 @@ export function LocalGraph({ className = "" }: LocalGraphProps) {
 
     nodesRef.current = nodes;
     edgesRef.current = edges;
-  }, [currentFile, currentContent, fileTree, findFilePath, getCurrentFileName]);
+  }, [currentFile, currentContent, fileTree, findFilePath, getCurrentFileName, getBacklinks, lastIndexTime]);
 
   // 当前文件或内容变化时重建图谱
   useEffect(() => {
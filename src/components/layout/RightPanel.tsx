@@ -3,7 +3,7 @@ import { useUIStore } from "@/stores/useUIStore";
 import { useAIStore } from "@/stores/useAIStore";
 import { useAgentStore } from "@/stores/useAgentStore";
 import { useFileStore } from "@/stores/useFileStore";
-import { useNoteIndexStore } from "@/stores/useNoteIndexStore";
+import { useNoteIndexStore, Backlink } from "@/stores/useNoteIndexStore";
 import { useRAGStore } from "@/stores/useRAGStore";
 import { useLocaleStore } from "@/stores/useLocaleStore";
 import { getFileName } from "@/lib/utils";
@@ -56,17 +56,32 @@ function parseHeadings(content: string): HeadingItem[] {
 function BacklinksView() {
   const { t } = useLocaleStore();
   const { currentFile, openFile } = useFileStore();
-  const { getBacklinks, isIndexing } = useNoteIndexStore();
+  const { getBacklinks, isIndexing, lastIndexTime } = useNoteIndexStore();
+  const [backlinks, setBacklinks] = useState<Backlink[]>([]);
   
   const currentFileName = useMemo(() => {
     if (!currentFile) return "";
     return getFileName(currentFile);
   }, [currentFile]);
   
-  const backlinks = useMemo(() => {
-    if (!currentFileName) return [];
-    return getBacklinks(currentFileName);
-  }, [currentFileName, getBacklinks]);
+  // 链接索引更新后重新查询
+  useEffect(() => {
+    if (!currentFile) {
+      setBacklinks([]);
+      return;
+    }
+    let cancelled = false;
+    getBacklinks(currentFile)
+      .then((links) => {
+        if (!cancelled) setBacklinks(links);
+      })
+      .catch(() => {
+        if (!cancelled) setBacklinks([]);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [currentFile, getBacklinks, lastIndexTime]);
   
   if (!currentFile) {
     return (
@@ -0,0 +1,91 @@
+/**
+ * 保存冲突提示
+ * 当前文件在打开后被外部修改时显示（不阻塞编辑，期间暂停该文件的自动保存），
+ * 可保留当前修改、采用磁盘上的版本，或与磁盘版本三方合并并对比确认
+ */
+
+import { useState } from "react";
+import { AlertTriangle, GitMerge } from "lucide-react";
+import { useFileStore } from "@/stores/useFileStore";
+import { mergeText, type MergeResult } from "@/lib/tauri";
+import { DiffView } from "@/components/effects/DiffView";
+
+export function SaveConflictBanner() {
+  const { currentFile, currentContent, saveConflicts, fileBases, resolveSaveConflict } = useFileStore();
+  const [merged, setMerged] = useState<(MergeResult & { path: string }) | null>(null);
+
+  const conflict = currentFile ? saveConflicts[currentFile] : undefined;
+  if (!currentFile || !conflict) return null;
+
+  const path = currentFile;
+  const name = path.split(/[/\\]/).pop() || path;
+
+  const resolve = (content: string | null) => {
+    setMerged(null);
+    resolveSaveConflict(path, content).catch((error) => {
+      console.error("Failed to resolve save conflict:", error);
+      alert(`保存失败: ${error}`);
+    });
+  };
+
+  // 以上次读取/保存的内容为基线，合并当前编辑与磁盘上的修改
+  const handleMerge = async () => {
+    try {
+      const base = fileBases[path] ?? conflict.content;
+      const result = await mergeText(base, currentContent, conflict.content);
+      setMerged({ ...result, path });
+    } catch (error) {
+      console.error("Failed to merge:", error);
+    }
+  };
+
+  return (
+    <>
+      <div className="flex items-center gap-2 px-3 py-2 text-xs border-b border-border bg-yellow-500/10">
+        <AlertTriangle size={14} className="text-yellow-500 shrink-0" />
+        <span className="flex-1 truncate">
+          "{name}" 已在外部被修改，该文件的自动保存已暂停
+        </span>
+        <button
+          onClick={handleMerge}
+          className="flex items-center gap-1 px-2 py-1 rounded bg-primary/80 hover:bg-primary text-primary-foreground"
+        >
+          <GitMerge size={12} />
+          合并
+        </button>
+        <button
+          onClick={() => resolve(currentContent)}
+          className="px-2 py-1 rounded bg-muted hover:bg-accent"
+        >
+          保留我的修改
+        </button>
+        <button
+          onClick={() => resolve(null)}
+          className="px-2 py-1 rounded bg-muted hover:bg-accent"
+        >
+          使用磁盘版本
+        </button>
+      </div>
+
+      {merged?.path === path && (
+        <div className="fixed inset-0 z-50 flex p-8 bg-black/40">
+          <div className="flex-1 flex flex-col overflow-hidden rounded-lg border border-border shadow-xl bg-background">
+            {merged.conflicts > 0 && (
+              <div className="px-4 py-2 text-xs border-b border-border bg-red-500/10">
+                仍有 {merged.conflicts} 处修改重叠，已用冲突标记标出，接受后可在编辑器中处理
+              </div>
+            )}
+            <DiffView
+              title="合并预览（磁盘版本 → 合并结果）"
+              fileName={name}
+              original={conflict.content}
+              modified={merged.content}
+              onAccept={() => resolve(merged.content)}
+              onReject={() => setMerged(null)}
+            />
+          </div>
+        </div>
+      )}
+    </>
+  );
+}
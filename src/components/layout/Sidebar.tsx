@@ -2,10 +2,10 @@ import { useState, useCallback, useEffect } from "react";
 import { useFileStore } from "@/stores/useFileStore";
 import { useRAGStore } from "@/stores/useRAGStore";
 import { useLocaleStore } from "@/stores/useLocaleStore";
-import { FileEntry, deleteFile, renameFile, createFile, createDir, exists, openNewWindow, saveFile } from "@/lib/tauri";
+import { FileEntry, deleteFile, renameFile, createFile, createDir, exists, openNewWindow, saveFile, pickVaultRoot } from "@/lib/tauri";
 import { invoke } from "@tauri-apps/api/core";
-import { open } from "@tauri-apps/plugin-dialog";
 import { cn, getFileName } from "@/lib/utils";
+import { findEntry } from "@/lib/fileTree";
 import { ContextMenu, MenuItem, menuItems } from "../toolbar/ContextMenu";
 import {
   ChevronRight,
@@ -45,7 +45,7 @@ interface CreatingState {
 
 export function Sidebar() {
   const { t } = useLocaleStore();
-  const { vaultPath, fileTree, currentFile, openFile, refreshFileTree, isLoadingTree, closeFile, openDatabaseTab, openPDFTab, tabs, activeTabIndex } =
+  const { vaultPath, sidebarTree, currentFile, openFile, refreshFileTree, refreshPaths, loadDirectory, isLoadingTree, closeFile, openDatabaseTab, openPDFTab, tabs, activeTabIndex } =
     useFileStore();
   const { config: ragConfig, isIndexing: ragIsIndexing, indexStatus, rebuildIndex, cancelIndex } = useRAGStore();
   const { setRightPanelTab, splitView } = useUIStore();
@@ -87,13 +87,13 @@ export function Sidebar() {
     
     try {
       await saveFile(filePath, content);
-      await refreshFileTree();
+      await refreshPaths([filePath]);
       openFile(filePath);
     } catch (error) {
       console.error("Failed to create quick note:", error);
       alert(t.file.createQuickNoteFailed);
     }
-  }, [vaultPath, refreshFileTree, openFile]);
+  }, [vaultPath, refreshPaths, openFile]);
   
   // Context menu state
   const [contextMenu, setContextMenu] = useState<ContextMenuState | null>(null);
@@ -110,6 +110,16 @@ export function Sidebar() {
   // 展开的文件夹路径集合
   const [expandedPaths, setExpandedPaths] = useState<Set<string>>(new Set());
 
+  // 展开的文件夹按需加载下一层
+  useEffect(() => {
+    for (const path of expandedPaths) {
+      const entry = findEntry(sidebarTree, path);
+      if (entry?.is_dir && !entry.children) {
+        loadDirectory(path);
+      }
+    }
+  }, [expandedPaths, sidebarTree, loadDirectory]);
+
   // 当前是否激活了 AI 主对话标签
   const isAIMainActive = tabs[activeTabIndex]?.type === "ai-chat";
 
@@ -141,13 +151,10 @@ export function Sidebar() {
   // Handle open folder
   const handleOpenFolder = useCallback(async () => {
     try {
-      const selected = await open({
-        directory: true,
-        multiple: false,
-        title: t.file.selectWorkingDir,
-      });
+      // 由后端弹出系统对话框并确认所选目录
+      const selected = await pickVaultRoot(t.file.selectWorkingDir);
       
-      if (selected && typeof selected === "string") {
+      if (selected) {
         useFileStore.getState().setVaultPath(selected);
       }
     } catch (error) {
@@ -187,11 +194,11 @@ export function Sidebar() {
       if (currentFile === entry.path) {
         closeFile();
       }
-      refreshFileTree();
+      refreshPaths([entry.path]);
     } catch (error) {
       console.error("Delete failed:", error);
     }
-  }, [currentFile, closeFile, refreshFileTree]);
+  }, [currentFile, closeFile, refreshPaths]);
 
   // Handle rename
   const handleStartRename = useCallback((entry: FileEntry) => {
@@ -222,7 +229,7 @@ export function Sidebar() {
     
     try {
       await renameFile(renamingPath, newPath);
-      refreshFileTree();
+      refreshPaths([renamingPath, newPath]);
       
       // 更新标签页中的路径和名称（如果文件在标签页中打开）
       const { updateTabPath } = useFileStore.getState();
@@ -232,7 +239,7 @@ export function Sidebar() {
       alert(t.file.renameFailed);
     }
     setRenamingPath(null);
-  }, [renamingPath, renameValue, refreshFileTree, currentFile, openFile]);
+  }, [renamingPath, renameValue, refreshPaths, currentFile, openFile]);
 
   // Handle copy path
   const handleCopyPath = useCallback(async (path: string) => {
@@ -368,11 +375,11 @@ export function Sidebar() {
     try {
       if (creating.type === "file") {
         await createFile(fullPath);
-        await refreshFileTree();
+        await refreshPaths([fullPath]);
         openFile(fullPath);
       } else {
         await createDir(fullPath);
-        await refreshFileTree();
+        await refreshPaths([fullPath]);
       }
     } catch (error) {
       console.error("Create failed:", error);
@@ -380,7 +387,7 @@ export function Sidebar() {
     }
 
     setCreating(null);
-  }, [creating, createValue, refreshFileTree, openFile]);
+  }, [creating, createValue, refreshPaths, openFile]);
 
   // 取消创建
   const handleCreateCancel = useCallback(() => {
@@ -592,12 +599,12 @@ export function Sidebar() {
             level={0}
           />
         )}
-        {fileTree.length === 0 && !creating ? (
+        {sidebarTree.length === 0 && !creating ? (
           <div className="px-4 py-8 text-center text-muted-foreground text-sm">
             {t.file.emptyFolder}
           </div>
         ) : (
-          fileTree.map((entry) => (
+          sidebarTree.map((entry) => (
             <FileTreeItem
               key={entry.path}
               entry={entry}
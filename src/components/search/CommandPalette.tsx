@@ -3,7 +3,7 @@ import { useFileStore } from "@/stores/useFileStore";
 import { useUIStore } from "@/stores/useUIStore";
 import { useBrowserStore } from "@/stores/useBrowserStore";
 import { useLocaleStore } from "@/stores/useLocaleStore";
-import { FileEntry } from "@/lib/tauri";
+import { FileEntry, IndexHit, querySearchIndex } from "@/lib/tauri";
 import { cn, getFileName } from "@/lib/utils";
 import {
   Search,
@@ -45,6 +45,7 @@ export function CommandPalette({ isOpen, mode, onClose, onModeChange }: CommandP
   const { t } = useLocaleStore();
   const [query, setQuery] = useState("");
   const [selectedIndex, setSelectedIndex] = useState(0);
+  const [searchHits, setSearchHits] = useState<IndexHit[]>([]);
   const inputRef = useRef<HTMLInputElement>(null);
   const listRef = useRef<HTMLDivElement>(null);
 
@@ -188,6 +189,31 @@ export function CommandPalette({ isOpen, mode, onClose, onModeChange }: CommandP
     },
   ], [t, onClose, createNewFile, onModeChange, toggleLeftSidebar, toggleRightSidebar, toggleTheme, isDarkMode, openGraphTab, isGraphOpen, vaultPath]);
 
+  // 内容搜索：查询后端全文索引
+  useEffect(() => {
+    if (mode !== "search") return;
+    const q = query.trim();
+    if (!q || !vaultPath) {
+      setSearchHits([]);
+      return;
+    }
+    let cancelled = false;
+    const timer = setTimeout(() => {
+      querySearchIndex(vaultPath, q, 20)
+        .then((hits) => {
+          if (!cancelled) setSearchHits(hits);
+        })
+        .catch((error) => {
+          console.warn("[SearchIndex] Query failed:", error);
+          if (!cancelled) setSearchHits([]);
+        });
+    }, 100);
+    return () => {
+      cancelled = true;
+      clearTimeout(timer);
+    };
+  }, [mode, query, vaultPath]);
+
   // Filter items based on query and mode
   const filteredItems = useMemo(() => {
     const q = query.toLowerCase().trim();
@@ -208,8 +234,12 @@ export function CommandPalette({ isOpen, mode, onClose, onModeChange }: CommandP
       ).slice(0, 20);
     }
     
+    if (mode === "search") {
+      return searchHits;
+    }
+    
     return [];
-  }, [mode, query, commands, allFiles]);
+  }, [mode, query, commands, allFiles, searchHits]);
 
   // Reset selection when filter changes
   useEffect(() => {
@@ -235,6 +265,18 @@ export function CommandPalette({ isOpen, mode, onClose, onModeChange }: CommandP
         onClose();
         openFile(file.path);
       }
+    } else if (mode === "search") {
+      const hit = filteredItems[index] as IndexHit;
+      if (hit) {
+        onClose();
+        openFile(hit.path);
+        // Dispatch event for editor to scroll to line
+        setTimeout(() => {
+          window.dispatchEvent(
+            new CustomEvent("search-jump-to", { detail: { line: hit.line } })
+          );
+        }, 100);
+      }
     }
   }, [mode, filteredItems, onClose, openFile]);
 
@@ -262,6 +304,8 @@ export function CommandPalette({ isOpen, mode, onClose, onModeChange }: CommandP
         // Switch between modes
         if (mode === "command") {
           onModeChange("file");
+        } else if (mode === "file") {
+          onModeChange("search");
         } else {
           onModeChange("command");
         }
@@ -324,6 +368,17 @@ export function CommandPalette({ isOpen, mode, onClose, onModeChange }: CommandP
               >
                 {t.commandPalette.files}
               </button>
+              <button
+                onClick={() => onModeChange("search")}
+                className={cn(
+                  "px-2 py-1 rounded transition-colors",
+                  mode === "search" 
+                    ? "bg-primary/20 text-primary" 
+                    : "text-muted-foreground hover:text-foreground"
+                )}
+              >
+                {t.commandPalette.contents}
+              </button>
             </div>
           </div>
 
@@ -365,6 +420,32 @@ export function CommandPalette({ isOpen, mode, onClose, onModeChange }: CommandP
                       )}
                     </button>
                   );
+                } else if (mode === "search") {
+                  const hit = item as IndexHit;
+                  return (
+                    <button
+                      key={`${hit.path}:${hit.line}`}
+                      data-index={index}
+                      onClick={() => executeItem(index)}
+                      className={cn(
+                        "w-full flex items-center gap-3 px-4 py-2.5 text-left transition-colors",
+                        index === selectedIndex 
+                          ? "bg-accent text-accent-foreground" 
+                          : "hover:bg-muted"
+                      )}
+                    >
+                      <FileText size={16} className="text-muted-foreground shrink-0" />
+                      <div className="flex-1 min-w-0">
+                        <div className="text-sm font-medium truncate">
+                          {getFileName(hit.relative_path)}
+                          <span className="ml-2 text-xs text-muted-foreground font-mono">:{hit.line}</span>
+                        </div>
+                        <div className="text-xs text-muted-foreground truncate">
+                          {hit.snippet}
+                        </div>
+                      </div>
+                    </button>
+                  );
                 } else {
                   const file = item as FileItem;
                   return (
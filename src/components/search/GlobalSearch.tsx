@@ -1,7 +1,7 @@
-import { useState, useEffect, useCallback, useMemo, useRef } from "react";
+import { useState, useEffect, useCallback, useRef } from "react";
 import { useFileStore } from "@/stores/useFileStore";
 import { useBrowserStore } from "@/stores/useBrowserStore";
-import { FileEntry, readFile } from "@/lib/tauri";
+import { searchVault, SearchMatchEvent } from "@/lib/tauri";
 import { cn, getFileName } from "@/lib/utils";
 import { Search, X, FileText, Loader2, Replace, ChevronDown, ChevronRight } from "lucide-react";
 
@@ -18,6 +18,9 @@ interface SearchMatch {
   matchEnd: number;
 }
 
+/** 匹配行数上限，超出后停止搜索 */
+const MAX_RESULTS = 2000;
+
 interface GlobalSearchProps {
   isOpen: boolean;
   onClose: () => void;
@@ -32,9 +35,12 @@ export function GlobalSearch({ isOpen, onClose }: GlobalSearchProps) {
   const [expandedFiles, setExpandedFiles] = useState<Set<string>>(new Set());
   const [useRegex, setUseRegex] = useState(false);
   const [caseSensitive, setCaseSensitive] = useState(false);
+  const [truncated, setTruncated] = useState(false);
   const inputRef = useRef<HTMLInputElement>(null);
+  const searchIdRef = useRef(0);
+  const unlistenRef = useRef<(() => void) | null>(null);
   
-  const { fileTree, openFile } = useFileStore();
+  const { vaultPath, openFile } = useFileStore();
   const { hideAllWebViews, showAllWebViews } = useBrowserStore();
 
   // 弹窗打开时隐藏 WebView，关闭时恢复
@@ -56,89 +62,72 @@ export function GlobalSearch({ isOpen, onClose }: GlobalSearchProps) {
     }
   }, [isOpen]);
 
-  // Flatten file tree to get all file paths
-  const allFiles = useMemo(() => {
-    const files: { path: string; name: string }[] = [];
-    const flatten = (entries: FileEntry[]) => {
-      for (const entry of entries) {
-        if (entry.is_dir && entry.children) {
-          flatten(entry.children);
-        } else if (!entry.is_dir) {
-          files.push({ path: entry.path, name: getFileName(entry.name) });
-        }
-      }
-    };
-    flatten(fileTree);
-    return files;
-  }, [fileTree]);
+  // 结束上一次搜索的事件监听
+  const stopListening = useCallback(() => {
+    unlistenRef.current?.();
+    unlistenRef.current = null;
+  }, []);
 
-  // Search function
+  useEffect(() => stopListening, [stopListening]);
+
+  // Search function: the backend walks the vault and streams files with matches
   const performSearch = useCallback(async () => {
-    if (!query.trim()) {
-      setResults([]);
+    const searchId = String(++searchIdRef.current);
+    stopListening();
+    setResults([]);
+    setTruncated(false);
+    if (!query.trim() || !vaultPath) {
+      setIsSearching(false);
       return;
     }
 
     setIsSearching(true);
-    const searchResults: SearchResult[] = [];
+    const { getCurrentWebviewWindow } = await import("@tauri-apps/api/webviewWindow");
+    const unlisten = await getCurrentWebviewWindow().listen<SearchMatchEvent>("search:match", (event) => {
+      if (event.payload.search_id !== searchId) return;
+      const file = event.payload.file;
+      const result: SearchResult = {
+        path: file.path,
+        name: getFileName(file.path),
+        matches: file.matches.flatMap((m) =>
+          m.ranges.map(([start, end]) => ({
+            line: m.line,
+            content: m.text,
+            matchStart: start,
+            matchEnd: end,
+          }))
+        ),
+      };
+      setResults(prev => [...prev, result]);
+      // Auto-expand files with matches
+      setExpandedFiles(prev => new Set([...prev, file.path]));
+    });
+    // 事件可能晚于命令返回到达，监听保留到下一次搜索或关闭
+    if (searchIdRef.current !== Number(searchId)) {
+      unlisten();
+      return;
+    }
+    unlistenRef.current = unlisten;
 
     try {
-      // Build search pattern
-      let pattern: RegExp;
-      try {
-        if (useRegex) {
-          pattern = new RegExp(query, caseSensitive ? "g" : "gi");
-        } else {
-          const escaped = query.replace(/[.*+?^${}()|[\]\\]/g, "\\$&");
-          pattern = new RegExp(escaped, caseSensitive ? "g" : "gi");
-        }
-      } catch {
-        // Invalid regex
-        setIsSearching(false);
-        return;
-      }
-
-      // Search through all files
-      for (const file of allFiles) {
-        try {
-          const content = await readFile(file.path);
-          const lines = content.split("\n");
-          const matches: SearchMatch[] = [];
-
-          lines.forEach((line, lineIndex) => {
-            let match;
-            pattern.lastIndex = 0;
-            while ((match = pattern.exec(line)) !== null) {
-              matches.push({
-                line: lineIndex + 1,
-                content: line.trim(),
-                matchStart: match.index,
-                matchEnd: match.index + match[0].length,
-              });
-              // Prevent infinite loop for zero-length matches
-              if (match[0].length === 0) break;
-            }
-          });
-
-          if (matches.length > 0) {
-            searchResults.push({
-              path: file.path,
-              name: file.name,
-              matches,
-            });
-            // Auto-expand files with matches
-            setExpandedFiles(prev => new Set([...prev, file.path]));
-          }
-        } catch (error) {
-          console.error(`Error reading ${file.path}:`, error);
-        }
+      const stats = await searchVault(
+        vaultPath,
+        query,
+        { regex: useRegex, case_sensitive: caseSensitive, max_results: MAX_RESULTS },
+        searchId
+      );
+      if (searchIdRef.current === Number(searchId)) {
+        setTruncated(stats.truncated);
       }
-
-      setResults(searchResults);
+    } catch (error) {
+      // Invalid regex
+      console.error("Search failed:", error);
     } finally {
-      setIsSearching(false);
+      if (searchIdRef.current === Number(searchId)) {
+        setIsSearching(false);
+      }
     }
-  }, [query, allFiles, useRegex, caseSensitive]);
+  }, [query, vaultPath, useRegex, caseSensitive, stopListening]);
 
   // Debounced search
   useEffect(() => {
@@ -272,7 +261,7 @@ export function GlobalSearch({ isOpen, onClose }: GlobalSearchProps) {
 
         {/* Results */}
         <div className="flex-1 overflow-y-auto">
-          {isSearching ? (
+          {isSearching && results.length === 0 ? (
             <div className="flex items-center justify-center py-8 text-muted-foreground">
               <Loader2 size={20} className="animate-spin mr-2" />
               搜索中...
@@ -346,7 +335,10 @@ export function GlobalSearch({ isOpen, onClose }: GlobalSearchProps) {
         {/* Footer */}
         <div className="p-2 border-t border-border text-xs text-muted-foreground">
           {results.length > 0 ? (
-            <span>{results.length} 个文件，{totalMatches} 个匹配</span>
+            <span>
+              {results.length} 个文件，{totalMatches} 个匹配
+              {truncated && "（结果过多，已截断）"}
+            </span>
           ) : (
             <span>Ctrl+Shift+F 打开全局搜索</span>
           )}
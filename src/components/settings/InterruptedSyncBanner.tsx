@@ -0,0 +1,37 @@
+/**
+ * 中断同步提示
+ * 打开 vault 时若上次同步被中断（取消或退出），提示用户继续完成
+ */
+
+import { useWebDAVStore } from '@/stores/useWebDAVStore';
+import { useFileStore } from '@/stores/useFileStore';
+import { CloudOff, X } from 'lucide-react';
+
+export function InterruptedSyncBanner() {
+  const { vaultPath } = useFileStore();
+  const { interruptedPlan, resumeSync, dismissInterruptedSync } = useWebDAVStore();
+
+  if (!interruptedPlan || !vaultPath) return null;
+
+  return (
+    <div className="flex items-center gap-2 px-3 py-2 text-xs border-b border-border bg-yellow-500/10">
+      <CloudOff size={14} className="text-yellow-500 shrink-0" />
+      <span className="flex-1 truncate">
+        The last WebDAV sync was interrupted with {interruptedPlan.items.length} items left.
+      </span>
+      <button
+        onClick={() => resumeSync(vaultPath)}
+        className="px-2 py-1 rounded bg-primary/80 hover:bg-primary text-primary-foreground"
+      >
+        Resume
+      </button>
+      <button
+        onClick={dismissInterruptedSync}
+        className="p-1 rounded hover:bg-accent text-muted-foreground"
+        title="Dismiss"
+      >
+        <X size={12} />
+      </button>
+    </div>
+  );
+}
@@ -6,6 +6,7 @@
 import { useState, useEffect } from 'react';
 import { useWebDAVStore, useSyncStatusText } from '@/stores/useWebDAVStore';
 import { useFileStore } from '@/stores/useFileStore';
+import { onWebDAVProgress, type AuthMethod } from '@/services/webdav';
 import {
   Cloud,
   CloudOff,
@@ -19,6 +20,7 @@ import {
   Upload,
   Download,
   Trash2,
+  MoveRight,
 } from 'lucide-react';
 
 interface WebDAVSettingsProps {
@@ -31,16 +33,26 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
   
   const {
     config,
+    profiles,
+    activeProfileId,
     isConnected,
     connectionError,
     lastSyncResult,
     lastSyncTime,
     pendingSyncPlan,
+    syncProgress,
     setConfig,
+    resetConfig,
+    loadProfiles,
+    saveProfile,
+    selectProfile,
+    deleteProfile,
     testConnection,
     computeSyncPlan,
     executeSync,
     quickSync,
+    cancelSync,
+    setSyncProgress,
     clearError,
   } = useWebDAVStore();
 
@@ -51,11 +63,13 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
     server_url: config.server_url,
     username: config.username,
     password: config.password,
+    auth: config.auth ?? 'auto',
     remote_base_path: config.remote_base_path,
     auto_sync: config.auto_sync,
     sync_interval_secs: config.sync_interval_secs,
   });
   
+  const [profileName, setProfileName] = useState('Default');
   const [showPassword, setShowPassword] = useState(false);
   const [isTesting, setIsTesting] = useState(false);
   const [isSyncing, setIsSyncing] = useState(false);
@@ -67,22 +81,58 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
       server_url: config.server_url,
       username: config.username,
       password: config.password || '',
+      auth: config.auth ?? 'auto',
       remote_base_path: config.remote_base_path,
       auto_sync: config.auto_sync,
       sync_interval_secs: config.sync_interval_secs,
     });
   }, [config]);
 
+  // 切换 vault 时加载该 vault 的配置
+  useEffect(() => {
+    if (vaultPath) {
+      loadProfiles(vaultPath);
+    }
+  }, [vaultPath, loadProfiles]);
+
+  // 订阅后端推送的同步进度（手动同步与自动同步）
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    let disposed = false;
+    onWebDAVProgress((progress) => setSyncProgress(progress))
+      .then((stop) => {
+        if (disposed) stop();
+        else unlisten = stop;
+      })
+      .catch((error) => console.warn('[WebDAV] Failed to listen for progress:', error));
+    return () => {
+      disposed = true;
+      if (unlisten) unlisten();
+    };
+  }, [setSyncProgress]);
+
+  useEffect(() => {
+    const current = profiles.find((p) => p.id === activeProfileId);
+    setProfileName(current?.name ?? (profiles.length > 0 ? 'New profile' : 'Default'));
+  }, [profiles, activeProfileId]);
+
+  // 保存表单到当前配置（新配置则创建）
+  const persistProfile = async () => {
+    if (!vaultPath) return false;
+    setConfig(formData);
+    return saveProfile(vaultPath, profileName.trim() || 'Default');
+  };
+
   // 测试连接
   const handleTestConnection = async () => {
     setIsTesting(true);
     clearError();
     
-    // 先保存配置
-    setConfig(formData);
-    
     try {
-      await testConnection();
+      // 先保存配置
+      if (await persistProfile()) {
+        await testConnection(vaultPath!);
+      }
     } finally {
       setIsTesting(false);
     }
@@ -92,7 +142,7 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
   const handlePreviewSync = async () => {
     if (!vaultPath) return;
     
-    setConfig(formData);
+    if (!(await persistProfile())) return;
     await computeSyncPlan(vaultPath);
     setShowPlan(true);
   };
@@ -102,9 +152,9 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
     if (!vaultPath) return;
     
     setIsSyncing(true);
-    setConfig(formData);
     
     try {
+      if (!(await persistProfile())) return;
       if (pendingSyncPlan) {
         await executeSync(vaultPath, pendingSyncPlan);
       } else {
@@ -121,15 +171,19 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
     if (!vaultPath) return;
     
     setIsSyncing(true);
-    setConfig(formData);
     
     try {
+      if (!(await persistProfile())) return;
       await quickSync(vaultPath);
     } finally {
       setIsSyncing(false);
     }
   };
 
+  // 正在运行的同步（包括后台自动同步）
+  const syncRunning = isSyncing || ['Connecting', 'ScanningRemote', 'ScanningLocal', 'ComputingDiff', 'Syncing']
+    .includes(syncProgress.stage);
+
   // 格式化时间
   const formatTime = (timestamp: number | null) => {
     if (!timestamp) return 'Never';
@@ -190,6 +244,49 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
 
       {/* 配置表单 */}
       <div className="space-y-4">
+        {/* 同步配置（每个 vault 可保存多个） */}
+        <div className="space-y-1.5">
+          <label className="text-xs text-muted-foreground">Profile</label>
+          <div className="flex gap-2">
+            <select
+              value={activeProfileId ?? ''}
+              onChange={(e) => {
+                if (!e.target.value) {
+                  resetConfig();
+                } else if (vaultPath) {
+                  selectProfile(vaultPath, e.target.value);
+                }
+              }}
+              disabled={!vaultPath}
+              className={`${inputClass} w-1/2`}
+            >
+              {profiles.map((profile) => (
+                <option key={profile.id} value={profile.id}>
+                  {profile.name}
+                </option>
+              ))}
+              <option value="">New profile…</option>
+            </select>
+            <input
+              type="text"
+              value={profileName}
+              onChange={(e) => setProfileName(e.target.value)}
+              placeholder="Home NAS"
+              className={inputClass}
+            />
+            {activeProfileId && (
+              <button
+                type="button"
+                onClick={() => vaultPath && deleteProfile(vaultPath, activeProfileId)}
+                className="p-2 hover:bg-red-500/20 rounded-lg"
+                title="Delete profile"
+              >
+                <Trash2 size={14} className="text-red-400" />
+              </button>
+            )}
+          </div>
+        </div>
+
         {/* 服务器 URL */}
         <div className="space-y-1.5">
           <label className="text-xs text-muted-foreground">Server URL</label>
@@ -215,13 +312,15 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
             />
           </div>
           <div className="space-y-1.5">
-            <label className="text-xs text-muted-foreground">Password</label>
+            <label className="text-xs text-muted-foreground">
+              {formData.auth === 'bearer' ? 'Token' : 'Password'}
+            </label>
             <div className="relative">
               <input
                 type={showPassword ? 'text' : 'password'}
                 value={formData.password}
                 onChange={(e) => setFormData({ ...formData, password: e.target.value })}
-                placeholder="••••••••"
+                placeholder={activeProfileId ? 'Unchanged' : '••••••••'}
                 className={`${inputClass} pr-10`}
               />
               <button
@@ -239,6 +338,21 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
           </div>
         </div>
 
+        {/* 认证方式 */}
+        <div className="space-y-1.5">
+          <label className="text-xs text-muted-foreground">Authentication</label>
+          <select
+            value={formData.auth}
+            onChange={(e) => setFormData({ ...formData, auth: e.target.value as AuthMethod })}
+            className={inputClass}
+          >
+            <option value="auto">Automatic (Basic or Digest)</option>
+            <option value="basic">Basic</option>
+            <option value="digest">Digest</option>
+            <option value="bearer">Bearer token / app token</option>
+          </select>
+        </div>
+
         {/* 远程路径 */}
         <div className="space-y-1.5">
           <label className="text-xs text-muted-foreground">Remote Path</label>
@@ -283,7 +397,7 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
       <div className="flex flex-wrap gap-2">
         <button
           onClick={handleTestConnection}
-          disabled={isTesting || !formData.server_url}
+          disabled={isTesting || !formData.server_url || !vaultPath}
           className={`${buttonClass} bg-white/10 hover:bg-white/20`}
         >
           {isTesting ? (
@@ -316,6 +430,36 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
         </button>
       </div>
 
+      {/* 同步进度 */}
+      {syncRunning && (
+        <div className="space-y-2 p-3 rounded-lg bg-white/5 border border-white/10">
+          <div className="flex items-center gap-2 text-xs">
+            <Loader2 size={12} className="animate-spin shrink-0" />
+            <span className="flex-1">
+              {statusText}
+              {syncProgress.total > 0 && ` (${Math.round((syncProgress.processed / syncProgress.total) * 100)}%)`}
+            </span>
+            <button
+              onClick={cancelSync}
+              className="px-2 py-1 rounded hover:bg-white/10 text-muted-foreground hover:text-foreground"
+            >
+              Cancel
+            </button>
+          </div>
+          {syncProgress.total > 0 && (
+            <div className="h-1 rounded-full bg-white/10 overflow-hidden">
+              <div
+                className="h-full bg-primary transition-all"
+                style={{ width: `${Math.min(100, (syncProgress.processed / syncProgress.total) * 100)}%` }}
+              />
+            </div>
+          )}
+          {syncProgress.current_file && (
+            <p className="text-xs text-muted-foreground truncate">{syncProgress.current_file}</p>
+          )}
+        </div>
+      )}
+
       {/* 同步计划预览 */}
       {showPlan && pendingSyncPlan && (
         <div className="space-y-3 p-4 rounded-lg bg-white/5 border border-white/10">
@@ -338,6 +482,12 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
               <Download size={12} className="text-green-400" />
               {pendingSyncPlan.download_count} to download
             </span>
+            {pendingSyncPlan.move_count > 0 && (
+              <span className="flex items-center gap-1">
+                <MoveRight size={12} className="text-purple-400" />
+                {pendingSyncPlan.move_count} to move
+              </span>
+            )}
             {pendingSyncPlan.conflict_count > 0 && (
               <span className="flex items-center gap-1 text-yellow-400">
                 <AlertCircle size={12} />
@@ -358,6 +508,7 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
                   {item.action === 'DeleteRemote' && <Trash2 size={10} className="text-red-400" />}
                   {item.action === 'DeleteLocal' && <Trash2 size={10} className="text-orange-400" />}
                   {item.action === 'Conflict' && <AlertCircle size={10} className="text-yellow-400" />}
+                  {item.action === 'Move' && <MoveRight size={10} className="text-purple-400" />}
                   <span className="truncate flex-1">{item.path}</span>
                   <span className="text-muted-foreground">{item.reason}</span>
                 </div>
@@ -263,6 +263,7 @@ export default {
     searchPlaceholder: 'Search note contents...',
     commands: 'Commands',
     files: 'Files',
+    contents: 'Contents',
     noResults: 'No matches found',
     select: 'Select',
     confirm: 'Confirm',
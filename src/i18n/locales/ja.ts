@@ -330,6 +330,7 @@ export default {
     searchPlaceholder: 'ノート内容を検索...',
     commands: 'コマンド',
     files: 'ファイル',
+    contents: '内容',
     noResults: '一致する項目がありません',
     select: '選択',
     confirm: '確認',
@@ -263,6 +263,7 @@ export default {
     searchPlaceholder: '搜索笔记内容...',
     commands: '命令',
     files: '文件',
+    contents: '内容',
     noResults: '没有找到匹配项',
     select: '选择',
     confirm: '确认',
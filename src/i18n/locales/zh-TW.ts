@@ -330,6 +330,7 @@ export default {
     searchPlaceholder: '搜尋筆記內容...',
     commands: '命令',
     files: '檔案',
+    contents: '內容',
     noResults: '沒有找到匹配項',
     select: '選擇',
     confirm: '確認',
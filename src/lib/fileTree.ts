@@ -0,0 +1,179 @@
+/**
+ * File tree patching - apply watcher "fs:tree" changes without relisting the vault
+ *
+ * Lazily loaded trees mark directories whose contents haven't been listed yet
+ * with `children: null`.
+ */
+
+import type { FileEntry, TreeChange } from "./tauri";
+import { dirname } from "./path";
+
+function normalize(path: string): string {
+  return path.replace(/\\/g, "/").replace(/\/+$/, "");
+}
+
+/**
+ * Sort like the backend: directories first, then case-insensitive by name
+ */
+function sortEntries(entries: FileEntry[]): FileEntry[] {
+  return entries.sort((a, b) => {
+    if (a.is_dir !== b.is_dir) return a.is_dir ? -1 : 1;
+    const left = a.name.toLowerCase();
+    const right = b.name.toLowerCase();
+    return left < right ? -1 : left > right ? 1 : 0;
+  });
+}
+
+function removeEntry(entries: FileEntry[], path: string): FileEntry[] {
+  let changed = false;
+  const result: FileEntry[] = [];
+  for (const entry of entries) {
+    if (normalize(entry.path) === path) {
+      changed = true;
+      continue;
+    }
+    if (entry.children) {
+      const children = removeEntry(entry.children, path);
+      if (children !== entry.children) {
+        changed = true;
+        result.push({ ...entry, children, child_count: children.length });
+        continue;
+      }
+    }
+    result.push(entry);
+  }
+  return changed ? result : entries;
+}
+
+/**
+ * Children of a directory that hasn't been loaded yet in a lazy tree
+ */
+function unloadedChildren(entry: FileEntry): FileEntry[] | null {
+  return (entry.child_count ?? 0) > 0 ? null : [];
+}
+
+/**
+ * Insert or replace `entry` in the directory `parent`.
+ * Returns null when the parent is not part of the tree; in a lazy tree a parent
+ * that hasn't been loaded leaves the tree unchanged.
+ */
+function upsertEntry(
+  entries: FileEntry[],
+  parent: string,
+  root: string,
+  entry: FileEntry,
+  lazy: boolean
+): FileEntry[] | null {
+  if (parent === root) {
+    const path = normalize(entry.path);
+    const existing = entries.find((e) => normalize(e.path) === path);
+    let children: FileEntry[] | null = null;
+    if (entry.is_dir) {
+      // Keep already loaded children; new directories start empty (or unloaded)
+      if (existing?.is_dir) children = existing.children ?? (lazy ? null : []);
+      else children = lazy ? unloadedChildren(entry) : [];
+    }
+    const next: FileEntry = { ...entry, children };
+    return sortEntries([...entries.filter((e) => e !== existing), next]);
+  }
+
+  for (let i = 0; i < entries.length; i++) {
+    const candidate = entries[i];
+    if (!candidate.is_dir) continue;
+    const candidatePath = normalize(candidate.path);
+    if (parent !== candidatePath && !parent.startsWith(candidatePath + "/")) continue;
+    if (!candidate.children) return lazy ? entries : null;
+
+    const children = upsertEntry(candidate.children, parent, candidatePath, entry, lazy);
+    if (!children) return null;
+    if (children === candidate.children) return entries;
+    const updated = [...entries];
+    updated[i] = { ...candidate, children, child_count: children.length };
+    return updated;
+  }
+  return lazy ? entries : null;
+}
+
+/**
+ * Find the entry for `path` among the loaded parts of the tree
+ */
+export function findEntry(tree: FileEntry[], path: string): FileEntry | undefined {
+  const target = normalize(path);
+  for (const entry of tree) {
+    const entryPath = normalize(entry.path);
+    if (entryPath === target) return entry;
+    if (entry.children && target.startsWith(entryPath + "/")) return findEntry(entry.children, path);
+  }
+  return undefined;
+}
+
+/**
+ * Replace the contents of the directory `dirPath` with freshly listed `children`.
+ * Leaves the tree unchanged when the directory isn't part of it.
+ */
+export function setChildren(tree: FileEntry[], dirPath: string, children: FileEntry[]): FileEntry[] {
+  const target = normalize(dirPath);
+  let changed = false;
+  const result = tree.map((entry) => {
+    const entryPath = normalize(entry.path);
+    if (entryPath === target && entry.is_dir) {
+      changed = true;
+      return { ...entry, children, child_count: children.length };
+    }
+    if (entry.children && target.startsWith(entryPath + "/")) {
+      const updated = setChildren(entry.children, dirPath, children);
+      if (updated !== entry.children) {
+        changed = true;
+        return { ...entry, children: updated };
+      }
+    }
+    return entry;
+  });
+  return changed ? result : tree;
+}
+
+/**
+ * Paths of the loaded directories, parents before their children
+ */
+export function loadedDirectories(tree: FileEntry[]): string[] {
+  const paths: string[] = [];
+  for (const entry of tree) {
+    if (entry.is_dir && entry.children) {
+      paths.push(entry.path);
+      paths.push(...loadedDirectories(entry.children));
+    }
+  }
+  return paths;
+}
+
+/**
+ * Apply a watcher change to the tree rooted at `rootPath`.
+ * Returns null when the change can't be applied locally (e.g. a directory with
+ * contents was moved in, or the ignore rules changed) and the caller should relist.
+ * A `lazy` tree only needs relisting when the ignore rules changed: directories
+ * moved in are added unloaded, and changes inside unloaded directories are skipped.
+ */
+export function applyTreeChange(
+  tree: FileEntry[],
+  rootPath: string,
+  change: TreeChange,
+  lazy: boolean = false
+): FileEntry[] | null {
+  if (change.type === "Reset") {
+    return null;
+  }
+  if (change.type === "Removed") {
+    return removeEntry(tree, normalize(change.path));
+  }
+
+  const { entry } = change;
+  const path = normalize(entry.path);
+  const root = normalize(rootPath);
+  const parent = dirname(path);
+  // Watcher only reports the directory itself, not what's inside it
+  if (!lazy && entry.is_dir && (entry.child_count ?? 0) > 0 && !findEntry(tree, path)) {
+    return null;
+  }
+
+  return upsertEntry(tree, parent, root, entry, lazy);
+}
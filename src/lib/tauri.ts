@@ -10,28 +10,167 @@ export interface FileEntry {
   path: string;
   is_dir: boolean;
   isDirectory?: boolean; // Alias
+  /** Size in bytes (0 for directories) */
+  size?: number;
+  /** Last modification time in Unix milliseconds */
+  modified?: number | null;
+  /** Creation time in Unix milliseconds */
+  created?: number | null;
+  /** Number of visible children, for directories */
+  child_count?: number | null;
   children: FileEntry[] | null;
 }
 
+/**
+ * One page of a single-level directory listing
+ */
+export interface DirListing {
+  entries: FileEntry[];
+  /** Number of visible entries in the directory, across all pages */
+  total: number;
+}
+
+/**
+ * Incremental file tree update ("fs:tree" event)
+ */
+export type TreeChange =
+  | { type: "Upsert"; entry: FileEntry }
+  | { type: "Removed"; path: string }
+  | { type: "Reset" };
+
+/**
+ * File content with the version token it was read at
+ */
+export interface VersionedContent {
+  content: string;
+  version: string;
+}
+
+/**
+ * Error returned by save_file when the file changed on disk since it was read
+ */
+export interface SaveConflict {
+  kind: "conflict";
+  message: string;
+  path: string;
+  /** Version of the content currently on disk */
+  version: string;
+  /** Content currently on disk */
+  content: string;
+}
+
+export function isSaveConflict(error: unknown): error is SaveConflict {
+  return typeof error === "object" && error !== null && (error as SaveConflict).kind === "conflict";
+}
+
+/**
+ * Set the vault opened in this window. File commands reject paths outside
+ * open vaults (resolving ".." and symlinks) unless allow-listed.
+ * A folder not approved before is confirmed in a native dialog; rejects if the
+ * user declines or the folder is a filesystem root or the home directory.
+ */
+export async function setVaultRoot(path: string): Promise<void> {
+  return invoke("set_vault_root", { path });
+}
+
+/**
+ * Pick a vault folder in a native dialog and open it in this window
+ * Returns null if the dialog was cancelled.
+ */
+export async function pickVaultRoot(title?: string): Promise<string | null> {
+  return invoke("pick_vault_root", { title });
+}
+
+/**
+ * Let the user pick a file (or folder) outside the vault in a native dialog
+ * and allow file commands on it and everything below it.
+ * Returns the picked path, null if the dialog was cancelled.
+ */
+export async function allowExternalPath(options?: {
+  directory?: boolean;
+  defaultPath?: string;
+  title?: string;
+}): Promise<string | null> {
+  return invoke("allow_external_path", {
+    directory: options?.directory,
+    defaultPath: options?.defaultPath,
+    title: options?.title,
+  });
+}
+
 /**
  * Read file content from disk
  */
 export async function readFile(path: string): Promise<string> {
-  return invoke<string>("read_file", { path });
+  return (await readFileVersioned(path)).content;
+}
+
+/**
+ * Read file content along with its version token (pass it back to saveFile)
+ */
+export async function readFileVersioned(path: string): Promise<VersionedContent> {
+  return invoke<VersionedContent>("read_file", { path });
+}
+
+/**
+ * Write options: `backup` keeps the previous content as a hidden `.<name>.bak`
+ */
+export interface WriteOptions {
+  backup?: boolean;
+}
+
+/**
+ * Save options: with `expectedVersion` the save fails with a SaveConflict
+ * when the file changed on disk since it was read
+ */
+export interface SaveOptions extends WriteOptions {
+  expectedVersion?: string;
+}
+
+/**
+ * Save file content to disk (atomic: a crash never leaves a truncated file).
+ * Returns the new version token.
+ */
+export async function saveFile(
+  path: string,
+  content: string,
+  options?: SaveOptions
+): Promise<string> {
+  return invoke<string>("save_file", {
+    path,
+    content,
+    backup: options?.backup,
+    expectedVersion: options?.expectedVersion,
+  });
 }
 
 /**
- * Save file content to disk
+ * Result of a three-way merge
  */
-export async function saveFile(path: string, content: string): Promise<void> {
-  return invoke("save_file", { path, content });
+export interface MergeResult {
+  /** Merged content, with conflict markers where both sides changed the same lines */
+  content: string;
+  /** Number of conflicting blocks */
+  conflicts: number;
+}
+
+/**
+ * Three-way merge of the editor content (`local`) with the version on disk
+ * (`remote`), based on the content the editor loaded (`base`)
+ */
+export async function mergeText(base: string, local: string, remote: string): Promise<MergeResult> {
+  return invoke<MergeResult>("merge_text", { base, local, remote });
 }
 
 /**
  * Write binary file to disk (for images, etc.)
  */
-export async function writeBinaryFile(path: string, data: Uint8Array): Promise<void> {
-  return invoke("write_binary_file", { path, data: Array.from(data) });
+export async function writeBinaryFile(
+  path: string,
+  data: Uint8Array,
+  options?: WriteOptions
+): Promise<void> {
+  return invoke("write_binary_file", { path, data: Array.from(data), backup: options?.backup });
 }
 
 /**
@@ -42,12 +181,32 @@ export async function readBinaryFileBase64(path: string): Promise<string> {
 }
 
 /**
- * List directory contents (recursive, .md files only)
+ * List directory contents (recursive, honoring the vault's .luminaignore rules)
  */
 export async function listDirectory(path: string): Promise<FileEntry[]> {
   return invoke<FileEntry[]>("list_directory", { path });
 }
 
+/**
+ * List a single directory level, one page at a time (directories carry child_count).
+ * Ignore rules come from vaultPath (defaults to path itself).
+ */
+export async function listDirectoryPage(
+  path: string,
+  offset?: number,
+  limit?: number,
+  vaultPath?: string
+): Promise<DirListing> {
+  return invoke<DirListing>("list_directory_page", { path, vaultPath, offset, limit });
+}
+
+/**
+ * Get metadata for a single file or directory
+ */
+export async function statFile(path: string, vaultPath?: string): Promise<FileEntry> {
+  return invoke<FileEntry>("stat_file", { path, vaultPath });
+}
+
 /**
  * Create a new file
  */
@@ -136,9 +295,228 @@ export async function openNewWindow(): Promise<void> {
 }
 
 /**
- * Start file system watcher for a directory
- * Emits "fs:change" events when files are created, modified, or deleted
+ * File change reported by the watcher ("fs:change" carries a debounced batch)
+ */
+export type FsEvent =
+  | { type: "Created"; path: string }
+  | { type: "Modified"; path: string }
+  | { type: "Deleted"; path: string }
+  | { type: "Renamed"; old_path: string; new_path: string };
+
+/**
+ * Watcher options; omitted fields use the backend defaults
+ * (notes, databases, PDFs, images and canvases; 200ms debounce)
+ */
+export interface WatchOptions {
+  /** Extensions without the dot (e.g. "pdf", "db.json"); empty reports every file */
+  extensions?: string[];
+  debounce_ms?: number;
+}
+
+/**
+ * Start file system watcher for a directory, owned by the current window
+ * Emits "fs:change" with batches of FsEvent (renames paired, writes made by this
+ * window suppressed), and "fs:tree" events (TreeChange) for incremental file tree updates.
+ * Events are sent only to this window: listen with getCurrentWebviewWindow().listen.
+ * The watcher stops with stopFileWatcher or when the window closes.
+ */
+export async function startFileWatcher(watchPath: string, options?: WatchOptions): Promise<void> {
+  return invoke("start_file_watcher", { watchPath, options });
+}
+
+/**
+ * Stop the current window's watcher for a directory (all of its watchers when omitted)
+ */
+export async function stopFileWatcher(watchPath?: string): Promise<void> {
+  return invoke("stop_file_watcher", { watchPath });
+}
+
+/**
+ * Vault search options; omitted fields use the backend defaults
+ * (case-insensitive literal search, no context, at most 500 matching lines)
+ */
+export interface SearchOptions {
+  regex?: boolean;
+  case_sensitive?: boolean;
+  /** Globs relative to the vault (e.g. "**\/*.md"); a file must match one of them */
+  include?: string[];
+  exclude?: string[];
+  /** Only search below this directory (relative to the vault) */
+  directory?: string;
+  context_lines?: number;
+  /** Maximum matching lines (0 for no limit) */
+  max_results?: number;
+}
+
+export interface LineMatch {
+  /** 1-based line number */
+  line: number;
+  text: string;
+  /** [start, end) of each match in `text` (JavaScript string indices) */
+  ranges: [number, number][];
+  before: string[];
+  after: string[];
+}
+
+export interface FileMatches {
+  path: string;
+  relative_path: string;
+  matches: LineMatch[];
+}
+
+export interface SearchResults {
+  /** Empty when results were streamed */
+  files: FileMatches[];
+  total_matches: number;
+  searched_files: number;
+  /** The result limit was reached */
+  truncated: boolean;
+}
+
+/** Payload of "search:match" events */
+export interface SearchMatchEvent {
+  search_id: string;
+  file: FileMatches;
+}
+
+/**
+ * Search file contents in the vault (respects .luminaignore, skips binary files)
+ * With streamId, each file with matches is emitted to the current window as
+ * "search:match" while the search runs, and the returned files are empty.
+ */
+export async function searchVault(
+  vaultPath: string,
+  query: string,
+  options?: SearchOptions,
+  streamId?: string
+): Promise<SearchResults> {
+  return invoke("search_vault", { vaultPath, query, options, streamId });
+}
+
+/**
+ * Full-text index hit, best first
+ */
+export interface IndexHit {
+  path: string;
+  relative_path: string;
+  /** 1-based line of the best matching line */
+  line: number;
+  snippet: string;
+  score: number;
+}
+
+export interface IndexStats {
+  total_files: number;
+  updated_files: number;
+  removed_files: number;
+}
+
+/**
+ * Open the vault's persistent full-text index and bring it up to date
+ * The vault's file watcher keeps it current afterwards.
+ */
+export async function openSearchIndex(vaultPath: string): Promise<IndexStats> {
+  return invoke("open_search_index", { vaultPath });
+}
+
+/**
+ * Rebuild the vault's full-text index from scratch
+ */
+export async function rebuildSearchIndex(vaultPath: string): Promise<IndexStats> {
+  return invoke("rebuild_search_index", { vaultPath });
+}
+
+/**
+ * Query the full-text index: all words must match, "quoted phrases" and
+ * prefix* terms are supported, Chinese words match without spaces
+ */
+export async function querySearchIndex(
+  vaultPath: string,
+  query: string,
+  limit?: number
+): Promise<IndexHit[]> {
+  return invoke("query_search_index", { vaultPath, query, limit });
+}
+
+/**
+ * A link as written in a note, with its resolved target
+ */
+export interface NoteLink {
+  /** Note containing the link */
+  source: string;
+  /** Linked file, null if it doesn't exist */
+  target_path: string | null;
+  kind: "wiki" | "markdown";
+  /** Note name or path as written (empty for a link within the same note) */
+  target: string;
+  heading: string | null;
+  block: string | null;
+  display: string | null;
+  embed: boolean;
+  /** 1-based line number */
+  line: number;
+  /** The trimmed line containing the link */
+  context: string;
+}
+
+export interface UnresolvedLink extends NoteLink {
+  reason: "note" | "heading" | "block";
+}
+
+export interface GraphNode {
+  path: string;
+  relative_path: string;
+  /** File name without .md */
+  name: string;
+  aliases: string[];
+  tags: string[];
+}
+
+export interface GraphEdge {
+  source: string;
+  target: string;
+  /** Number of links from source to target */
+  count: number;
+}
+
+export interface LinkGraph {
+  nodes: GraphNode[];
+  edges: GraphEdge[];
+}
+
+/**
+ * Open the vault's link index and parse notes changed since the last sync
+ * The vault's file watcher keeps it current and emits "links:changed".
+ * Returns the number of notes.
+ */
+export async function openNoteIndex(vaultPath: string): Promise<number> {
+  return invoke("open_note_index", { vaultPath });
+}
+
+/**
+ * Links to the note at path ([[wikilinks]], aliases and Markdown links)
+ */
+export async function getBacklinks(vaultPath: string, path: string): Promise<NoteLink[]> {
+  return invoke("get_backlinks", { vaultPath, path });
+}
+
+/**
+ * Links written in the note at path
+ */
+export async function getOutgoingLinks(vaultPath: string, path: string): Promise<NoteLink[]> {
+  return invoke("get_outgoing_links", { vaultPath, path });
+}
+
+/**
+ * Links to missing notes, headings or blocks
+ */
+export async function getUnresolvedLinks(vaultPath: string): Promise<UnresolvedLink[]> {
+  return invoke("get_unresolved_links", { vaultPath });
+}
+
+/**
+ * All notes as nodes and the links between them as edges
  */
-export async function startFileWatcher(watchPath: string): Promise<void> {
-  return invoke("start_file_watcher", { watchPath });
+export async function getLinkGraph(vaultPath: string): Promise<LinkGraph> {
+  return invoke("get_link_graph", { vaultPath });
 }
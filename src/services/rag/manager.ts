@@ -292,7 +292,7 @@ export class RAGManager {
           }
         } else if (item.path.endsWith(".md")) {
           try {
-            const content = await invoke<string>("read_file", { path: item.path });
+            const { content } = await invoke<{ content: string }>("read_file", { path: item.path });
             // 获取文件修改时间 (简化处理，使用当前时间)
             const modified = Date.now();
             files.push({ path: item.path, content, modified });
@@ -4,11 +4,18 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import type {
+  ExcludedPath,
+  NoteVersion,
+  SyncHistoryEntry,
+  SyncProfile,
+  VaultProfiles,
   WebDAVConfig,
   RemoteEntry,
   LocalFileInfo,
   SyncPlan,
+  SyncProgress,
   SyncResult,
 } from './types';
 
@@ -16,172 +23,123 @@ export * from './types';
 
 /**
  * WebDAV 服务类
- * 提供所有 WebDAV 操作的封装
+ * 按 vault 和配置 ID 调用后端，凭据只保存在后端
  */
 export class WebDAVService {
-  private config: WebDAVConfig | null = null;
+  private vaultPath: string | null = null;
+  private profileId: string | null = null;
 
   /**
-   * 设置配置
+   * 选择 vault 和配置（profileId 为 null 时使用 vault 的当前配置）
    */
-  setConfig(config: WebDAVConfig): void {
-    this.config = config;
+  use(vaultPath: string, profileId: string | null = null): void {
+    this.vaultPath = vaultPath;
+    this.profileId = profileId;
   }
 
   /**
-   * 获取当前配置
+   * 检查是否已选择 vault
    */
-  getConfig(): WebDAVConfig | null {
-    return this.config;
+  isConfigured(): boolean {
+    return this.vaultPath !== null;
   }
 
-  /**
-   * 检查是否已配置
-   */
-  isConfigured(): boolean {
-    return this.config !== null && this.config.server_url.length > 0;
+  private target(): { vaultPath: string; profileId: string | null } {
+    if (!this.vaultPath) {
+      throw new Error('WebDAV not configured');
+    }
+    return { vaultPath: this.vaultPath, profileId: this.profileId };
   }
 
   /**
    * 测试连接
    */
-  async testConnection(config?: WebDAVConfig): Promise<boolean> {
-    const cfg = config || this.config;
-    if (!cfg) {
-      throw new Error('WebDAV not configured');
-    }
-    return invoke<boolean>('webdav_test_connection', { config: cfg });
+  async testConnection(): Promise<boolean> {
+    return invoke<boolean>('webdav_test_profile', this.target());
   }
 
   /**
    * 列出远程目录
    */
   async listRemote(path: string = ''): Promise<RemoteEntry[]> {
-    if (!this.config) {
-      throw new Error('WebDAV not configured');
-    }
-    return invoke<RemoteEntry[]>('webdav_list_remote', {
-      config: this.config,
-      path,
-    });
+    return invoke<RemoteEntry[]>('webdav_list_remote', { ...this.target(), path });
   }
 
   /**
    * 列出所有远程文件（递归）
    */
   async listAllRemote(): Promise<RemoteEntry[]> {
-    if (!this.config) {
-      throw new Error('WebDAV not configured');
-    }
-    return invoke<RemoteEntry[]>('webdav_list_all_remote', {
-      config: this.config,
-    });
+    return invoke<RemoteEntry[]>('webdav_list_all_remote', this.target());
   }
 
   /**
    * 下载远程文件
    */
   async download(remotePath: string): Promise<string> {
-    if (!this.config) {
-      throw new Error('WebDAV not configured');
-    }
-    return invoke<string>('webdav_download', {
-      config: this.config,
-      remotePath,
-    });
+    return invoke<string>('webdav_download', { ...this.target(), remotePath });
   }
 
   /**
    * 上传文件到远程
    */
   async upload(remotePath: string, content: string): Promise<void> {
-    if (!this.config) {
-      throw new Error('WebDAV not configured');
-    }
-    return invoke('webdav_upload', {
-      config: this.config,
-      remotePath,
-      content,
-    });
+    return invoke('webdav_upload', { ...this.target(), remotePath, content });
   }
 
   /**
    * 在远程创建目录
    */
   async createDir(remotePath: string): Promise<void> {
-    if (!this.config) {
-      throw new Error('WebDAV not configured');
-    }
-    return invoke('webdav_create_dir', {
-      config: this.config,
-      remotePath,
-    });
+    return invoke('webdav_create_dir', { ...this.target(), remotePath });
   }
 
   /**
    * 删除远程文件/目录
    */
   async delete(remotePath: string): Promise<void> {
-    if (!this.config) {
-      throw new Error('WebDAV not configured');
-    }
-    return invoke('webdav_delete', {
-      config: this.config,
-      remotePath,
-    });
+    return invoke('webdav_delete', { ...this.target(), remotePath });
   }
 
   /**
    * 计算同步计划
    */
-  async computeSyncPlan(vaultPath: string): Promise<SyncPlan> {
-    if (!this.config) {
-      throw new Error('WebDAV not configured');
-    }
-    return invoke<SyncPlan>('webdav_compute_sync_plan', {
-      config: this.config,
-      vaultPath,
-    });
+  async computeSyncPlan(): Promise<SyncPlan> {
+    return invoke<SyncPlan>('webdav_compute_sync_plan', this.target());
   }
 
   /**
    * 执行同步
    */
-  async executeSync(vaultPath: string, plan: SyncPlan): Promise<SyncResult> {
-    if (!this.config) {
-      throw new Error('WebDAV not configured');
-    }
-    return invoke<SyncResult>('webdav_execute_sync', {
-      config: this.config,
-      vaultPath,
-      plan,
-    });
+  async executeSync(plan: SyncPlan): Promise<SyncResult> {
+    return invoke<SyncResult>('webdav_execute_sync', { ...this.target(), plan });
   }
 
   /**
    * 快速同步（跳过冲突）
    */
-  async quickSync(vaultPath: string): Promise<SyncResult> {
-    if (!this.config) {
-      throw new Error('WebDAV not configured');
-    }
-    return invoke<SyncResult>('webdav_quick_sync', {
-      config: this.config,
-      vaultPath,
-    });
+  async quickSync(): Promise<SyncResult> {
+    return invoke<SyncResult>('webdav_quick_sync', this.target());
+  }
+
+  /**
+   * 恢复上次被中断的同步，没有未完成计划时返回 null
+   */
+  async resumeSync(): Promise<SyncResult | null> {
+    return invoke<SyncResult | null>('webdav_resume_sync', this.target());
+  }
+
+  /**
+   * 预览同步规则排除的路径及原因（不执行同步）
+   */
+  async previewExclusions(): Promise<ExcludedPath[]> {
+    return invoke<ExcludedPath[]>('webdav_preview_exclusions', this.target());
   }
 
   /**
    * 扫描本地文件
    */
-  async scanLocal(vaultPath: string): Promise<LocalFileInfo[]> {
-    if (!this.config) {
-      throw new Error('WebDAV not configured');
-    }
-    return invoke<LocalFileInfo[]>('webdav_scan_local', {
-      config: this.config,
-      vaultPath,
-    });
+  async scanLocal(): Promise<LocalFileInfo[]> {
+    return invoke<LocalFileInfo[]>('webdav_scan_local', this.target());
   }
 }
 
@@ -193,10 +151,97 @@ export async function testWebDAVConnection(config: WebDAVConfig): Promise<boolea
   return invoke<boolean>('webdav_test_connection', { config });
 }
 
-export async function saveWebDAVConfig(config: WebDAVConfig): Promise<void> {
-  return invoke('webdav_set_config', { config });
+/**
+ * 列出 vault 的同步配置（不含密码等密钥）
+ */
+export async function listSyncProfiles(vaultPath: string): Promise<VaultProfiles> {
+  return invoke<VaultProfiles>('webdav_list_profiles', { vaultPath });
+}
+
+/**
+ * 新建（id 为空）或更新同步配置，密钥留空表示沿用已保存的值
+ */
+export async function saveSyncProfile(vaultPath: string, profile: SyncProfile): Promise<SyncProfile> {
+  return invoke<SyncProfile>('webdav_save_profile', { vaultPath, profile });
+}
+
+export async function deleteSyncProfile(vaultPath: string, profileId: string): Promise<void> {
+  return invoke('webdav_delete_profile', { vaultPath, profileId });
+}
+
+/**
+ * 切换 vault 当前使用的同步配置
+ */
+export async function setActiveSyncProfile(vaultPath: string, profileId: string): Promise<void> {
+  return invoke('webdav_set_active_profile', { vaultPath, profileId });
+}
+
+/**
+ * 测试已保存配置的连接
+ */
+export async function testSyncProfile(vaultPath: string, profileId?: string): Promise<boolean> {
+  return invoke<boolean>('webdav_test_profile', { vaultPath, profileId: profileId ?? null });
+}
+
+/**
+ * 启动后台自动同步（使用 vault 的当前配置，未开启 auto_sync 时返回 false）
+ * 进度通过 "webdav:progress" 事件推送
+ */
+export async function startWebDAVAutoSync(vaultPath: string): Promise<boolean> {
+  return invoke<boolean>('webdav_start_auto_sync', { vaultPath });
+}
+
+export async function stopWebDAVAutoSync(): Promise<void> {
+  return invoke('webdav_stop_auto_sync');
+}
+
+/**
+ * 取消正在运行的同步（当前文件完成后停止）
+ */
+export async function cancelWebDAVSync(): Promise<void> {
+  return invoke('webdav_cancel_sync');
+}
+
+/**
+ * 获取上次被中断的同步计划
+ */
+export async function getInterruptedWebDAVSync(vaultPath: string): Promise<SyncPlan | null> {
+  return invoke<SyncPlan | null>('webdav_get_interrupted_sync', { vaultPath });
+}
+
+/**
+ * 订阅同步进度（计算计划、执行同步、自动同步均会推送）
+ */
+export async function onWebDAVProgress(
+  handler: (progress: SyncProgress) => void
+): Promise<UnlistenFn> {
+  return listen<SyncProgress>('webdav:progress', (event) => handler(event.payload));
 }
 
-export async function loadWebDAVConfig(): Promise<WebDAVConfig | null> {
-  return invoke<WebDAVConfig | null>('webdav_get_config', {});
+/**
+ * 获取同步历史（最新的在前）
+ */
+export async function getWebDAVSyncHistory(
+  vaultPath: string,
+  limit?: number
+): Promise<SyncHistoryEntry[]> {
+  return invoke<SyncHistoryEntry[]>('webdav_get_sync_history', { vaultPath, limit });
+}
+
+/**
+ * 列出文件被同步覆盖前保存的历史版本
+ */
+export async function listNoteVersions(vaultPath: string, path: string): Promise<NoteVersion[]> {
+  return invoke<NoteVersion[]>('webdav_list_versions', { vaultPath, path });
+}
+
+/**
+ * 恢复文件的某个历史版本（当前内容会先保存为新版本）
+ */
+export async function restoreNoteVersion(
+  vaultPath: string,
+  path: string,
+  versionId: string
+): Promise<void> {
+  return invoke('webdav_restore_version', { vaultPath, path, versionId });
 }
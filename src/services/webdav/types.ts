@@ -9,14 +9,108 @@ export interface WebDAVConfig {
   server_url: string;
   /** 用户名 */
   username: string;
-  /** 密码 */
+  /** 密码（Bearer 认证时为 token） */
   password: string;
+  /** 认证方式（默认自动：先用 Basic，服务器要求 Digest 时切换） */
+  auth?: AuthMethod;
   /** 远程根目录 (如 /notes) */
   remote_base_path: string;
   /** 是否启用自动同步 */
   auto_sync: boolean;
   /** 自动同步间隔 (秒) */
   sync_interval_secs: number;
+  /** 远程删除的处理方式 (默认重新上传) */
+  remote_delete_mode?: RemoteDeleteMode;
+  /** 端到端加密 (未设置时以明文上传) */
+  encryption?: EncryptionConfig | null;
+  /** 同步过滤规则 (另可在 vault 根目录放置 .luminasyncignore) */
+  rules?: SyncRules;
+  /** 存储后端 (默认 WebDAV) */
+  backend?: StorageBackend;
+}
+
+/** 命名的同步配置（如“家里 NAS”、“公司服务器”） */
+export interface SyncProfile {
+  /** 配置 ID（新建时留空，由后端生成） */
+  id: string;
+  /** 显示名称 */
+  name: string;
+  /** 配置（后端返回时密码等密钥为空，保存时留空表示沿用已保存的值） */
+  config: WebDAVConfig;
+}
+
+/** 某个 vault 的全部同步配置 */
+export interface VaultProfiles {
+  /** 当前使用的配置 ID */
+  active: string | null;
+  profiles: SyncProfile[];
+}
+
+/** WebDAV 认证方式 */
+export type AuthMethod = 'auto' | 'basic' | 'digest' | 'bearer';
+
+/** 存储后端 */
+export type StorageBackend =
+  | { type: 'web_dav' }
+  | ({ type: 's3' } & S3Config)
+  | { type: 'local'; path: string };
+
+/** S3 兼容存储配置 (AWS S3、MinIO、R2 等) */
+export interface S3Config {
+  /** 服务端点 (如 https://s3.us-east-1.amazonaws.com) */
+  endpoint: string;
+  /** 存储桶 */
+  bucket: string;
+  /** 区域 (默认 us-east-1) */
+  region?: string;
+  access_key: string;
+  secret_key: string;
+}
+
+/** 同步过滤规则 */
+export interface SyncRules {
+  /** 只同步匹配的文件 (glob，为空表示全部) */
+  include: string[];
+  /** 排除匹配的文件/目录 (glob) */
+  exclude: string[];
+  /** 单个文件大小上限 (字节) */
+  max_file_size: number | null;
+  /** 只同步这些扩展名 (为空表示全部) */
+  allowed_extensions: string[];
+  /** 不同步这些扩展名 */
+  blocked_extensions: string[];
+}
+
+/** 路径被排除的原因 */
+export type ExcludeReason =
+  | { kind: 'builtin'; rule: string }
+  | { kind: 'ignore_file'; pattern: string }
+  | { kind: 'exclude_glob'; pattern: string }
+  | { kind: 'not_included' }
+  | { kind: 'too_large'; size: number; limit: number }
+  | { kind: 'file_type'; extension: string };
+
+/** 被同步规则排除的路径 */
+export interface ExcludedPath {
+  /** 相对路径 */
+  path: string;
+  /** 是否为目录 (目录被排除时其下内容不再单独列出) */
+  is_dir: boolean;
+  /** 所在位置 */
+  side: 'Local' | 'Remote';
+  /** 排除原因 */
+  reason: ExcludeReason;
+}
+
+/** 远程文件被删除后本地的处理方式 */
+export type RemoteDeleteMode = 'Reupload' | 'SystemTrash' | 'VaultTrash';
+
+/** 端到端加密设置 */
+export interface EncryptionConfig {
+  /** 用于派生密钥的口令 (只在本地使用，不会上传) */
+  passphrase: string;
+  /** 是否加密文件名 (仅首次创建远程加密清单时生效) */
+  encrypt_names: boolean;
 }
 
 /** 创建默认配置 */
@@ -28,6 +122,7 @@ export function createDefaultConfig(): WebDAVConfig {
     remote_base_path: '/',
     auto_sync: false,
     sync_interval_secs: 300,
+    remote_delete_mode: 'Reupload',
   };
 }
 
@@ -70,6 +165,8 @@ export type SyncAction =
   | 'DeleteRemote'
   | 'DeleteLocal'
   | 'Conflict'
+  | 'Merged'
+  | 'Move'
   | 'Skip';
 
 /** 同步计划条目 */
@@ -84,6 +181,8 @@ export interface SyncPlanItem {
   remote: RemoteEntry | null;
   /** 原因说明 */
   reason: string;
+  /** 移动前的路径 (仅 Move) */
+  moved_from?: string | null;
 }
 
 /** 同步计划 */
@@ -96,6 +195,8 @@ export interface SyncPlan {
   download_count: number;
   /** 冲突数量 */
   conflict_count: number;
+  /** 移动/重命名数量 */
+  move_count: number;
 }
 
 /** 同步阶段 */
@@ -107,6 +208,7 @@ export type SyncStage =
   | 'ComputingDiff'
   | 'Syncing'
   | 'Completed'
+  | 'Cancelled'
   | 'Error';
 
 /** 同步进度 */
@@ -123,6 +225,38 @@ export interface SyncProgress {
   error: string | null;
 }
 
+/** 同步中发生变更的路径 */
+export interface AffectedPath {
+  /** 相对路径 */
+  path: string;
+  /** 实际执行的动作 */
+  action: SyncAction;
+  /** 移动前的路径 (仅 Move) */
+  moved_from?: string;
+}
+
+/** 一次同步的历史记录 */
+export interface SyncHistoryEntry {
+  /** 开始时间 (Unix 时间戳，秒) */
+  started_at: number;
+  /** 同步结果 (同步未能完成时为 null) */
+  result: SyncResult | null;
+  /** 导致同步中止的错误 */
+  error: string | null;
+}
+
+/** 文件的历史版本快照 */
+export interface NoteVersion {
+  /** 版本 ID (按时间排序) */
+  id: string;
+  /** 文件相对路径 */
+  path: string;
+  /** 快照时间 (Unix 时间戳，秒) */
+  created_at: number;
+  /** 文件大小 (字节) */
+  size: number;
+}
+
 /** 同步错误 */
 export interface SyncError {
   /** 文件路径 */
@@ -137,6 +271,8 @@ export interface SyncError {
 export interface SyncResult {
   /** 是否成功 */
   success: boolean;
+  /** 是否被取消 */
+  cancelled: boolean;
   /** 上传成功数 */
   uploaded: number;
   /** 下载成功数 */
@@ -145,6 +281,14 @@ export interface SyncResult {
   deleted: number;
   /** 冲突数 */
   conflicts: number;
+  /** 自动合并数 */
+  merged: number;
+  /** 移动/重命名数 */
+  moved: number;
+  /** 因远程删除而移入回收站的本地路径 */
+  trashed: string[];
+  /** 本次实际变更的路径 */
+  affected: AffectedPath[];
   /** 错误列表 */
   errors: SyncError[];
   /** 同步耗时 (毫秒) */
@@ -1,6 +1,20 @@
 import { create } from "zustand";
 import { persist } from "zustand/middleware";
-import { FileEntry, listDirectory, readFile, saveFile, createFile } from "@/lib/tauri";
+import {
+  FileEntry,
+  FsEvent,
+  TreeChange,
+  listDirectory,
+  listDirectoryPage,
+  statFile,
+  setVaultRoot,
+  readFileVersioned,
+  saveFile,
+  createFile,
+  isSaveConflict,
+  SaveConflict,
+} from "@/lib/tauri";
+import { applyTreeChange, loadedDirectories, setChildren } from "@/lib/fileTree";
 import { VideoNoteFile, parseVideoNoteMd } from '@/types/videoNote';
 import { invoke } from '@tauri-apps/api/core';
 
@@ -55,7 +69,10 @@ export interface Tab {
 interface FileState {
   // Vault
   vaultPath: string | null;
+  // 完整文件树（快速打开、图谱、链接查找等使用），在后台加载
   fileTree: FileEntry[];
+  // 侧边栏文件树：逐层加载，尚未加载的文件夹 children 为 null
+  sidebarTree: FileEntry[];
 
   // Tabs
   tabs: Tab[];
@@ -71,6 +88,13 @@ interface FileState {
   redoStack: HistoryEntry[];
   lastSavedContent: string;
 
+  // Version tokens of open files (path -> version read from / written to disk)
+  fileVersions: Record<string, string>;
+  // Content of open files at that version, the base for merging a save conflict
+  fileBases: Record<string, string>;
+  // Saves rejected because the file changed on disk; autosave pauses until resolved
+  saveConflicts: Record<string, SaveConflict>;
+
   // Navigation history (browser-like back/forward)
   navigationHistory: string[];
   navigationIndex: number;
@@ -86,9 +110,14 @@ interface FileState {
   // Actions
   setVaultPath: (path: string) => Promise<void>;
   refreshFileTree: () => Promise<void>;
+  loadDirectory: (path: string) => Promise<void>;
+  refreshPaths: (paths: string[]) => Promise<void>;
+  applyTreeChanges: (changes: TreeChange[]) => void;
   openFile: (path: string, addToHistory?: boolean, forceReload?: boolean) => Promise<void>;
   updateContent: (content: string, source?: "user" | "ai", description?: string) => void;
   save: () => Promise<void>;
+  saveFileChecked: (path: string, content: string) => Promise<boolean>;
+  resolveSaveConflict: (path: string, content: string | null) => Promise<void>;
   closeFile: () => void;
   
   // Tab actions
@@ -99,6 +128,7 @@ interface FileState {
   reorderTabs: (fromIndex: number, toIndex: number) => void;
   togglePinTab: (index: number) => void;
   updateTabPath: (oldPath: string, newPath: string) => void;
+  applyFileEvents: (events: FsEvent[]) => void;
   
   // Create new file
   createNewFile: (fileName?: string) => Promise<void>;
@@ -135,6 +165,79 @@ interface FileState {
   clearVault: () => void;
 }
 
+// 后端确认当前 vault 根目录的请求，文件命令需在其完成后发出
+let vaultRootReady: Promise<void> = Promise.resolve();
+
+/**
+ * Resolves once the backend accepted the current vault as this window's root
+ */
+export function whenVaultReady(): Promise<void> {
+  return vaultRootReady;
+}
+
+// 侧边栏每次请求的目录条目数
+const TREE_PAGE_SIZE = 500;
+// 正在加载的侧边栏目录，避免重复请求
+const loadingDirs = new Set<string>();
+// 最近一次完整文件树请求，较早的结果到达时丢弃
+let fullTreeRequest = 0;
+
+/**
+ * 列出一层目录（分页请求直到取完），子文件夹标记为未加载
+ */
+async function listLevel(path: string, vaultPath: string): Promise<FileEntry[]> {
+  const entries: FileEntry[] = [];
+  let total = Infinity;
+  while (entries.length < total) {
+    const page = await listDirectoryPage(path, entries.length, TREE_PAGE_SIZE, vaultPath);
+    total = page.total;
+    if (page.entries.length === 0) break;
+    entries.push(...page.entries);
+  }
+  return entries.map((entry) =>
+    entry.is_dir ? { ...entry, children: (entry.child_count ?? 0) > 0 ? null : [] } : entry
+  );
+}
+
+/**
+ * 重新列出侧边栏：根目录和之前已加载的文件夹（保持展开状态不闪烁）
+ */
+async function listSidebar(vaultPath: string, previous: FileEntry[]): Promise<FileEntry[]> {
+  const loaded = loadedDirectories(previous);
+  const [top, ...levels] = await Promise.all([
+    listLevel(vaultPath, vaultPath),
+    ...loaded.map((path) => listLevel(path, vaultPath).catch(() => null)),
+  ]);
+  let tree = top;
+  loaded.forEach((path, i) => {
+    const children = levels[i];
+    if (children) tree = setChildren(tree, path, children);
+  });
+  return tree;
+}
+
+/**
+ * 在后台加载完整文件树，不阻塞侧边栏
+ */
+async function loadFullTree(vaultPath: string) {
+  const request = ++fullTreeRequest;
+  try {
+    const tree = await listDirectory(vaultPath);
+    if (request === fullTreeRequest && useFileStore.getState().vaultPath === vaultPath) {
+      useFileStore.setState({ fileTree: tree });
+    }
+  } catch (error) {
+    console.error("Failed to list vault:", error);
+  }
+}
+
+function without<T>(record: Record<string, T>, key: string): Record<string, T> {
+  if (!(key in record)) return record;
+  const next = { ...record };
+  delete next[key];
+  return next;
+}
+
 // 用户编辑的 debounce 时间（毫秒）
 const USER_EDIT_DEBOUNCE = 1000;
 let lastUserEditTime = 0;
@@ -145,6 +248,7 @@ export const useFileStore = create<FileState>()(
   // Initial state
   vaultPath: null,
   fileTree: [],
+  sidebarTree: [],
   
   // Tabs
   tabs: [],
@@ -161,6 +265,9 @@ export const useFileStore = create<FileState>()(
   undoStack: [],
   redoStack: [],
   lastSavedContent: "",
+  fileVersions: {},
+  fileBases: {},
+  saveConflicts: {},
 
   // Navigation history
   navigationHistory: [],
@@ -169,33 +276,104 @@ export const useFileStore = create<FileState>()(
 
   // Set vault path and load file tree
   setVaultPath: async (path: string) => {
-    set({ vaultPath: path, isLoadingTree: true });
+    set({ isLoadingTree: true });
     try {
-      const tree = await listDirectory(path);
-      set({ fileTree: tree, isLoadingTree: false });
+      // 后端文件命令只允许访问已打开的 vault，先确认根目录再切换（未批准的目录会弹出确认框）
+      const previous = vaultRootReady;
+      const ready = setVaultRoot(path);
+      vaultRootReady = ready.catch(() => previous);
+      await ready;
+      set({ vaultPath: path, fileTree: [], sidebarTree: [] });
+      const tree = await listLevel(path, path);
+      set({ sidebarTree: tree, isLoadingTree: false });
+      loadFullTree(path);
     } catch (error) {
       console.error("Failed to load vault:", error);
       set({ isLoadingTree: false });
     }
   },
 
-  // Refresh file tree
+  // Refresh file tree (the sidebar's loaded levels now, the full tree in the background)
   refreshFileTree: async () => {
     const { vaultPath } = get();
     if (!vaultPath) return;
 
     set({ isLoadingTree: true });
     try {
-      const tree = await listDirectory(vaultPath);
-      set({ fileTree: tree, isLoadingTree: false });
+      await whenVaultReady();
+      const tree = await listSidebar(vaultPath, get().sidebarTree);
+      if (get().vaultPath === vaultPath) set({ sidebarTree: tree });
+      set({ isLoadingTree: false });
+      loadFullTree(vaultPath);
     } catch (error) {
       console.error("Failed to refresh file tree:", error);
       set({ isLoadingTree: false });
     }
   },
 
+  // Load the contents of a sidebar directory (on expand)
+  loadDirectory: async (path: string) => {
+    const { vaultPath } = get();
+    if (!vaultPath || loadingDirs.has(path)) return;
+
+    loadingDirs.add(path);
+    try {
+      const children = await listLevel(path, vaultPath);
+      if (get().vaultPath === vaultPath) {
+        set({ sidebarTree: setChildren(get().sidebarTree, path, children) });
+      }
+    } catch (error) {
+      console.error("Failed to load directory:", error);
+    } finally {
+      loadingDirs.delete(path);
+    }
+  },
+
+  // Re-stat paths the app just created, renamed or deleted and patch the trees
+  refreshPaths: async (paths: string[]) => {
+    const { vaultPath } = get();
+    if (!vaultPath) return;
+
+    const changes = await Promise.all(
+      paths.map(async (path): Promise<TreeChange> => {
+        try {
+          return { type: "Upsert", entry: await statFile(path, vaultPath) };
+        } catch {
+          return { type: "Removed", path };
+        }
+      })
+    );
+    get().applyTreeChanges(changes);
+  },
+
+  // Patch the file trees from watcher events, relisting only when needed
+  applyTreeChanges: (changes: TreeChange[]) => {
+    const { vaultPath, fileTree, sidebarTree, refreshFileTree } = get();
+    if (!vaultPath) return;
+
+    let sidebar: FileEntry[] | null = sidebarTree;
+    let full: FileEntry[] | null = fileTree;
+    for (const change of changes) {
+      if (sidebar) sidebar = applyTreeChange(sidebar, vaultPath, change, true);
+      if (full) full = applyTreeChange(full, vaultPath, change);
+    }
+
+    if (!sidebar) {
+      refreshFileTree();
+      return;
+    }
+    if (sidebar !== sidebarTree) set({ sidebarTree: sidebar });
+    if (!full) {
+      loadFullTree(vaultPath);
+    } else if (full !== fileTree) {
+      set({ fileTree: full });
+    }
+  },
+
   // Open a file
   openFile: async (path: string, addToHistory: boolean = true, forceReload: boolean = false) => {
+    // 启动时恢复的标签页可能早于后端确认 vault
+    await whenVaultReady().catch(() => {});
     const { tabs, activeTabIndex, navigationHistory, navigationIndex } = get();
 
     // Normalize paths for comparison (handle Windows backslashes)
@@ -209,7 +387,7 @@ export const useFileStore = create<FileState>()(
       if (forceReload) {
         // 强制重新加载内容（Agent 编辑后使用）
         try {
-          const newContent = await readFile(path);
+          const { content: newContent, version } = await readFileVersioned(path);
           const updatedTabs = [...tabs];
           updatedTabs[existingTabIndex] = {
             ...updatedTabs[existingTabIndex],
@@ -223,6 +401,9 @@ export const useFileStore = create<FileState>()(
             currentContent: newContent,
             isDirty: false,
             lastSavedContent: newContent,
+            fileVersions: { ...get().fileVersions, [path]: version },
+            fileBases: { ...get().fileBases, [path]: newContent },
+            saveConflicts: without(get().saveConflicts, path),
           });
         } catch (error) {
           console.error("Failed to reload file:", error);
@@ -246,7 +427,7 @@ export const useFileStore = create<FileState>()(
 
     set({ isLoadingFile: true });
     try {
-      const content = await readFile(path);
+      const { content, version } = await readFileVersioned(path);
       const fileName = path.split(/[/\\]/).pop()?.replace(/\.md$/, "") || "未命名";
       
       // 创建新标签页
@@ -300,6 +481,9 @@ export const useFileStore = create<FileState>()(
         navigationHistory: newHistory,
         navigationIndex: newNavIndex,
         recentFiles: newRecentFiles,
+        fileVersions: { ...get().fileVersions, [path]: version },
+        fileBases: { ...get().fileBases, [path]: content },
+        saveConflicts: without(get().saveConflicts, path),
       });
     } catch (error) {
       console.error("Failed to open file:", error);
@@ -359,13 +543,19 @@ export const useFileStore = create<FileState>()(
     
     // 固定标签不能关闭
     if (tabToClose.isPinned) return;
+
+    // 有未处理的保存冲突时先切换过去让用户处理，避免丢失修改
+    if (get().saveConflicts[tabToClose.path]) {
+      get().switchTab(index);
+      return;
+    }
     
     // 如果要关闭的是当前标签页且有未保存的更改，先保存
     if (index === activeTabIndex && isDirty) {
       await get().save();
     } else if (tabs[index].isDirty) {
       // 非当前标签页但有未保存更改，也保存
-      await saveFile(tabs[index].path, tabs[index].content);
+      await get().saveFileChecked(tabs[index].path, tabs[index].content);
     }
     
     // 如果是网页标签页，关闭对应的 WebView
@@ -442,12 +632,13 @@ export const useFileStore = create<FileState>()(
     // 保存所有要关闭的标签页
     for (const tab of tabs) {
       if (tab.isDirty && tab.id !== targetTab.id && !tab.isPinned) {
-        await saveFile(tab.path, tab.content);
+        await get().saveFileChecked(tab.path, tab.content);
       }
     }
     
-    // 保留固定标签和当前标签
-    const remainingTabs = tabs.filter(tab => tab.isPinned || tab.id === targetTab.id);
+    // 保留固定标签、当前标签和有未处理保存冲突的标签
+    const { saveConflicts } = get();
+    const remainingTabs = tabs.filter(tab => tab.isPinned || tab.id === targetTab.id || saveConflicts[tab.path]);
     const newActiveIndex = remainingTabs.findIndex(t => t.id === targetTab.id);
     
     set({
@@ -468,12 +659,13 @@ export const useFileStore = create<FileState>()(
     // 保存所有要关闭的标签页
     for (const tab of tabs) {
       if (tab.isDirty && !tab.isPinned) {
-        await saveFile(tab.path, tab.content);
+        await get().saveFileChecked(tab.path, tab.content);
       }
     }
     
-    // 保留固定标签
-    const pinnedTabs = tabs.filter(tab => tab.isPinned);
+    // 保留固定标签和有未处理保存冲突的标签
+    const { saveConflicts } = get();
+    const pinnedTabs = tabs.filter(tab => tab.isPinned || saveConflicts[tab.path]);
     
     if (pinnedTabs.length === 0) {
       set({
@@ -523,10 +715,47 @@ export const useFileStore = create<FileState>()(
     if (currentFile === oldPath) {
       newState.currentFile = newPath;
     }
+
+    // 版本、合并基线和未处理的冲突跟随文件
+    const { fileVersions, fileBases, saveConflicts } = get();
+    const move = <T,>(record: Record<string, T>) =>
+      oldPath in record ? { ...without(record, oldPath), [newPath]: record[oldPath] } : record;
+    newState.fileVersions = move(fileVersions);
+    newState.fileBases = move(fileBases);
+    newState.saveConflicts = move(saveConflicts);
     
     set(newState);
   },
 
+  // 跟随其他窗口或外部程序的修改（本窗口自己的写入不会上报）：
+  // 重命名/移动的文件（含被移动文件夹中的文件）更新标签页路径，磁盘内容变化时重新加载未修改的标签页
+  applyFileEvents: (events: FsEvent[]) => {
+    const normalize = (p: string) => p.replace(/\\/g, "/");
+
+    for (const event of events) {
+      if (event.type === "Renamed") {
+        const from = normalize(event.old_path);
+        const to = normalize(event.new_path);
+        for (const tab of get().tabs) {
+          if (tab.type !== "file") continue;
+          const path = normalize(tab.path);
+          if (path === from) {
+            get().updateTabPath(tab.path, event.new_path);
+          } else if (path.startsWith(from + "/")) {
+            get().updateTabPath(tab.path, to + path.slice(from.length));
+          }
+        }
+      } else if (event.type === "Modified") {
+        const path = normalize(event.path);
+        const tab = get().tabs.find((t) => t.type === "file" && normalize(t.path) === path);
+        // 有未保存修改的标签页保留编辑内容，保存时按版本冲突处理
+        if (tab && !tab.isDirty && !(tab.path === get().currentFile && get().isDirty)) {
+          get().reloadFileIfOpen(tab.path);
+        }
+      }
+    }
+  },
+
   // 重新排序标签页
   reorderTabs: (fromIndex: number, toIndex: number) => {
     const { tabs, activeTabIndex } = get();
@@ -1207,7 +1436,7 @@ export const useFileStore = create<FileState>()(
 
   // 创建新文件
   createNewFile: async (fileName?: string) => {
-    const { vaultPath, refreshFileTree, openFile } = get();
+    const { vaultPath, refreshPaths, openFile } = get();
     if (!vaultPath) return;
     
     const separator = vaultPath.includes("\\") ? "\\" : "/";
@@ -1238,7 +1467,7 @@ export const useFileStore = create<FileState>()(
         }
       }
       
-      await refreshFileTree();
+      await refreshPaths([checkPath()]);
       await openFile(checkPath());
       return;
     }
@@ -1247,7 +1476,7 @@ export const useFileStore = create<FileState>()(
     const newPath = `${vaultPath}${separator}${name}.md`;
     try {
       await createFile(newPath);
-      await refreshFileTree();
+      await refreshPaths([newPath]);
       await openFile(newPath);
     } catch (error) {
       console.error("Create file failed:", error);
@@ -1378,14 +1607,76 @@ export const useFileStore = create<FileState>()(
 
     set({ isSaving: true });
     try {
-      await saveFile(currentFile, currentContent);
-      set({ isDirty: false, isSaving: false, lastSavedContent: currentContent });
+      if (await get().saveFileChecked(currentFile, currentContent)) {
+        set({ isDirty: false, isSaving: false, lastSavedContent: currentContent });
+      } else {
+        set({ isSaving: false });
+      }
     } catch (error) {
       console.error("Failed to save file:", error);
       set({ isSaving: false });
     }
   },
 
+  // 带版本检查的保存：文件在打开后被外部修改（其他编辑器、WebDAV 同步、其他窗口）时
+  // 记录冲突并暂停该文件的自动保存，由冲突提示条让用户保留、采用磁盘版本或合并。
+  // 返回 false 表示未保存
+  saveFileChecked: async (path: string, content: string) => {
+    if (get().saveConflicts[path]) return false;
+
+    try {
+      const version = await saveFile(path, content, { expectedVersion: get().fileVersions[path] });
+      set({
+        fileVersions: { ...get().fileVersions, [path]: version },
+        fileBases: { ...get().fileBases, [path]: content },
+      });
+      return true;
+    } catch (error) {
+      if (!isSaveConflict(error)) throw error;
+      set({ saveConflicts: { ...get().saveConflicts, [path]: error } });
+      return false;
+    }
+  },
+
+  // 处理保存冲突：content 为要写入的内容（当前编辑内容或合并结果），null 表示采用磁盘上的版本
+  resolveSaveConflict: async (path: string, content: string | null) => {
+    const conflict = get().saveConflicts[path];
+    if (!conflict) return;
+
+    if (content === null) {
+      set({
+        saveConflicts: without(get().saveConflicts, path),
+        fileVersions: { ...get().fileVersions, [path]: conflict.version },
+        fileBases: { ...get().fileBases, [path]: conflict.content },
+      });
+      await get().reloadFileIfOpen(path);
+      return;
+    }
+
+    let version: string;
+    try {
+      version = await saveFile(path, content, { expectedVersion: conflict.version });
+    } catch (error) {
+      // 处理期间磁盘上又有新的修改，以最新版本重新提示
+      if (!isSaveConflict(error)) throw error;
+      set({ saveConflicts: { ...get().saveConflicts, [path]: error } });
+      return;
+    }
+
+    const { tabs, currentFile } = get();
+    set({
+      saveConflicts: without(get().saveConflicts, path),
+      fileVersions: { ...get().fileVersions, [path]: version },
+      fileBases: { ...get().fileBases, [path]: content },
+      tabs: tabs.map((tab) =>
+        tab.type === "file" && tab.path === path ? { ...tab, content, isDirty: false } : tab
+      ),
+      ...(currentFile === path
+        ? { currentContent: content, lastSavedContent: content, isDirty: false }
+        : {}),
+    });
+  },
+
   // Close current file (now closes current tab)
   closeFile: () => {
     const { activeTabIndex } = get();
@@ -1428,6 +1719,7 @@ export const useFileStore = create<FileState>()(
     set({
       vaultPath: null,
       fileTree: [],
+      sidebarTree: [],
       tabs: [],
       activeTabIndex: -1,
       currentFile: null,
@@ -1449,7 +1741,12 @@ export const useFileStore = create<FileState>()(
     if (tabIndex === -1) return;
     
     try {
-      const newContent = await readFile(path);
+      const { content: newContent, version } = await readFileVersioned(path);
+      set({
+        fileVersions: { ...get().fileVersions, [path]: version },
+        fileBases: { ...get().fileBases, [path]: newContent },
+        saveConflicts: without(get().saveConflicts, path),
+      });
       const updatedTabs = tabs.map((tab, i) => 
         i === tabIndex ? { ...tab, content: newContent, isDirty: false } : tab
       );
@@ -1472,6 +1769,18 @@ export const useFileStore = create<FileState>()(
 }),
     {
       name: "lumina-workspace",
+      // 启动时恢复的 vault 也需先由后端确认；用户拒绝时不再打开它
+      onRehydrateStorage: () => (state) => {
+        const path = state?.vaultPath;
+        if (!path) return;
+        vaultRootReady = setVaultRoot(path);
+        vaultRootReady.catch((error) => {
+          console.warn("Vault not opened:", error);
+          if (useFileStore.getState().vaultPath === path) {
+            useFileStore.setState({ vaultPath: null, fileTree: [], sidebarTree: [] });
+          }
+        });
+      },
       partialize: (state) => ({
         vaultPath: state.vaultPath,  // 只持久化工作空间路径
         recentFiles: state.recentFiles, // 持久化最近文件列表
@@ -1,5 +1,5 @@
 import { create } from "zustand";
-import { FileEntry, readFile } from "@/lib/tauri";
+import { FileEntry, readFile, openNoteIndex, getLinkGraph, getBacklinks } from "@/lib/tauri";
 
 // Extract [[wikilinks]] from content
 export function extractWikiLinks(content: string): string[] {
@@ -12,23 +12,12 @@ export function extractWikiLinks(content: string): string[] {
   return [...new Set(links)];
 }
 
-// Extract #tags from content
-export function extractTags(content: string): string[] {
-  // Match #tag but not inside code blocks or URLs
-  const regex = /(?:^|\s)#([a-zA-Z\u4e00-\u9fa5][a-zA-Z0-9\u4e00-\u9fa5_-]*)/g;
-  const tags: string[] = [];
-  let match;
-  while ((match = regex.exec(content)) !== null) {
-    tags.push(match[1].toLowerCase());
-  }
-  return [...new Set(tags)];
-}
-
 // Note metadata for indexing
 export interface NoteIndex {
   path: string;
   name: string;
-  outgoingLinks: string[]; // [[links]] this note contains
+  aliases: string[];
+  outgoingLinks: string[]; // Names of the notes this note links to
   tags: string[];
   lastModified: number;
 }
@@ -49,12 +38,12 @@ export interface TagInfo {
 }
 
 interface NoteIndexState {
+  // Vault whose backend link index is open
+  vaultPath: string | null;
+
   // Index of all notes
   noteIndex: Map<string, NoteIndex>;
   
-  // Backlinks cache: targetName -> backlinks[]
-  backlinksCache: Map<string, Backlink[]>;
-  
   // All tags
   allTags: TagInfo[];
   
@@ -63,8 +52,10 @@ interface NoteIndexState {
   lastIndexTime: number;
   
   // Actions
-  buildIndex: (fileTree: FileEntry[]) => Promise<void>;
-  getBacklinks: (noteName: string) => Backlink[];
+  buildIndex: (vaultPath: string) => Promise<void>;
+  refresh: () => Promise<void>;
+  resolveNote: (name: string) => string | undefined;
+  getBacklinks: (notePath: string) => Promise<Backlink[]>;
   getTagFiles: (tag: string) => string[];
   searchContent: (query: string, files: FileEntry[]) => Promise<SearchResult[]>;
 }
@@ -84,119 +75,90 @@ export interface SearchMatch {
 }
 
 export const useNoteIndexStore = create<NoteIndexState>((set, get) => ({
+  vaultPath: null,
   noteIndex: new Map(),
-  backlinksCache: new Map(),
   allTags: [],
   isIndexing: false,
   lastIndexTime: 0,
 
-  buildIndex: async (fileTree: FileEntry[]) => {
-    set({ isIndexing: true });
-    
+  // 后端解析并维护链接索引（文件监听会保持更新），前端只保存图谱快照
+  buildIndex: async (vaultPath: string) => {
+    set({ vaultPath, isIndexing: true });
+    try {
+      await openNoteIndex(vaultPath);
+      if (get().vaultPath !== vaultPath) return;
+      await get().refresh();
+    } finally {
+      if (get().vaultPath === vaultPath) set({ isIndexing: false });
+    }
+  },
+
+  refresh: async () => {
+    const { vaultPath } = get();
+    if (!vaultPath) return;
+    const graph = await getLinkGraph(vaultPath);
+    if (get().vaultPath !== vaultPath) return;
+
+    const nameByPath = new Map(graph.nodes.map((node) => [node.path, node.name]));
+    const outgoing = new Map<string, string[]>();
+    for (const edge of graph.edges) {
+      const names = outgoing.get(edge.source) ?? [];
+      names.push(nameByPath.get(edge.target) ?? edge.target);
+      outgoing.set(edge.source, names);
+    }
+
     const noteIndex = new Map<string, NoteIndex>();
-    const backlinksMap = new Map<string, Backlink[]>();
-    const tagsMap = new Map<string, { count: number; files: string[] }>();
-    
-    // Flatten file tree
-    const allFiles: { path: string; name: string }[] = [];
-    const flattenTree = (entries: FileEntry[]) => {
-      for (const entry of entries) {
-        if (entry.is_dir && entry.children) {
-          flattenTree(entry.children);
-        } else if (!entry.is_dir && entry.name.endsWith(".md")) {
-          allFiles.push({ 
-            path: entry.path, 
-            name: entry.name.replace(".md", "") 
-          });
-        }
-      }
-    };
-    flattenTree(fileTree);
-    
-    // Build note name to path map for resolving links
-    const nameToPath = new Map<string, string>();
-    allFiles.forEach(f => {
-      nameToPath.set(f.name.toLowerCase(), f.path);
-    });
-    
-    // Index each file
-    for (const file of allFiles) {
-      try {
-        const content = await readFile(file.path);
-        const outgoingLinks = extractWikiLinks(content);
-        const tags = extractTags(content);
-        
-        // Store note index
-        noteIndex.set(file.path, {
-          path: file.path,
-          name: file.name,
-          outgoingLinks,
-          tags,
-          lastModified: Date.now(),
-        });
-        
-        // Build backlinks
-        const lines = content.split("\n");
-        for (const linkName of outgoingLinks) {
-          // Find the line containing this link
-          let contextLine = "";
-          let lineNum = 0;
-          for (let i = 0; i < lines.length; i++) {
-            if (lines[i].includes(`[[${linkName}`) || 
-                lines[i].toLowerCase().includes(`[[${linkName.toLowerCase()}`)) {
-              contextLine = lines[i].trim();
-              lineNum = i + 1;
-              break;
-            }
-          }
-          
-          const backlink: Backlink = {
-            path: file.path,
-            name: file.name,
-            context: contextLine,
-            line: lineNum,
-          };
-          
-          const normalizedLinkName = linkName.toLowerCase();
-          if (!backlinksMap.has(normalizedLinkName)) {
-            backlinksMap.set(normalizedLinkName, []);
-          }
-          backlinksMap.get(normalizedLinkName)!.push(backlink);
-        }
-        
-        // Build tags index
-        for (const tag of tags) {
-          if (!tagsMap.has(tag)) {
-            tagsMap.set(tag, { count: 0, files: [] });
-          }
-          const tagInfo = tagsMap.get(tag)!;
-          tagInfo.count++;
-          tagInfo.files.push(file.path);
-        }
-      } catch (error) {
-        console.error(`Failed to index ${file.path}:`, error);
+    const tagsMap = new Map<string, string[]>();
+    for (const node of graph.nodes) {
+      noteIndex.set(node.path, {
+        path: node.path,
+        name: node.name,
+        aliases: node.aliases,
+        outgoingLinks: outgoing.get(node.path) ?? [],
+        tags: node.tags,
+        lastModified: Date.now(),
+      });
+      for (const tag of node.tags) {
+        const files = tagsMap.get(tag) ?? [];
+        files.push(node.path);
+        tagsMap.set(tag, files);
       }
     }
-    
+
     // Convert tags map to sorted array
     const allTags: TagInfo[] = Array.from(tagsMap.entries())
-      .map(([tag, info]) => ({ tag, count: info.count, files: info.files }))
+      .map(([tag, files]) => ({ tag, count: files.length, files }))
       .sort((a, b) => b.count - a.count);
-    
-    set({
-      noteIndex,
-      backlinksCache: backlinksMap,
-      allTags,
-      isIndexing: false,
-      lastIndexTime: Date.now(),
-    });
-    
-    console.log(`[Index] Built index for ${noteIndex.size} notes, ${allTags.length} tags`);
+
+    set({ noteIndex, allTags, lastIndexTime: Date.now() });
+  },
+
+  // 按路径、笔记名或别名查找笔记（不区分大小写）
+  resolveNote: (name: string) => {
+    const { noteIndex } = get();
+    const query = name.replace(/\\/g, "/").replace(/\.md$/i, "").toLowerCase();
+    let byName: string | undefined;
+    for (const note of noteIndex.values()) {
+      const path = note.path.replace(/\\/g, "/").replace(/\.md$/i, "").toLowerCase();
+      if (path === query || path.endsWith(`/${query}`)) return note.path;
+      if (!byName && (note.name.toLowerCase() === query ||
+          note.aliases.some((alias) => alias.toLowerCase() === query))) {
+        byName = note.path;
+      }
+    }
+    return byName;
   },
 
-  getBacklinks: (noteName: string) => {
-    const { backlinksCache } = get();
-    return backlinksCache.get(noteName.toLowerCase()) || [];
+  getBacklinks: async (notePath: string) => {
+    const { vaultPath, noteIndex } = get();
+    if (!vaultPath) return [];
+    const links = await getBacklinks(vaultPath, notePath);
+    return links.map((link) => ({
+      path: link.source,
+      name: noteIndex.get(link.source)?.name ?? link.source.split(/[/\\]/).pop()!.replace(/\.md$/, ""),
+      context: link.context,
+      line: link.line,
+    }));
   },
 
   getTagFiles: (tag: string) => {
@@ -1,6 +1,7 @@
 /**
  * WebDAV 状态管理
- * 管理 WebDAV 连接配置、同步状态和操作
+ * 管理 WebDAV 同步配置、同步状态和操作
+ * 配置（含密码）由后端按 vault 保存，这里只持有编辑中的表单和配置 ID
  */
 
 import { create } from 'zustand';
@@ -8,17 +9,36 @@ import { persist } from 'zustand/middleware';
 import {
   WebDAVConfig,
   SyncPlan,
+  SyncProfile,
   SyncResult,
   SyncProgress,
+  cancelWebDAVSync,
   createDefaultConfig,
-  testWebDAVConnection,
+  deleteSyncProfile,
+  getInterruptedWebDAVSync,
+  listSyncProfiles,
+  saveSyncProfile,
+  setActiveSyncProfile,
+  startWebDAVAutoSync,
+  stopWebDAVAutoSync,
   webdavService,
 } from '@/services/webdav';
 
+// 按当前配置启动（开启 auto_sync 时）或停止后台自动同步
+function applyAutoSync(vaultPath: string, configured: boolean) {
+  const request = configured ? startWebDAVAutoSync(vaultPath) : stopWebDAVAutoSync();
+  request.catch((error) => {
+    console.error('[WebDAV] Failed to update auto sync:', error);
+  });
+}
+
 interface WebDAVState {
   // 配置
   config: WebDAVConfig;
   isConfigured: boolean;
+  profiles: SyncProfile[];
+  /** 正在编辑/使用的配置 ID，null 表示尚未保存的新配置 */
+  activeProfileId: string | null;
 
   // 连接状态
   isConnected: boolean;
@@ -32,10 +52,19 @@ interface WebDAVState {
   // 同步计划（预览）
   pendingSyncPlan: SyncPlan | null;
 
+  // 上次被中断、可恢复的同步计划
+  interruptedPlan: SyncPlan | null;
+
   // Actions
   setConfig: (config: Partial<WebDAVConfig>) => void;
   resetConfig: () => void;
-  testConnection: () => Promise<boolean>;
+  testConnection: (vaultPath: string) => Promise<boolean>;
+
+  // 配置管理
+  loadProfiles: (vaultPath: string) => Promise<void>;
+  saveProfile: (vaultPath: string, name: string) => Promise<boolean>;
+  selectProfile: (vaultPath: string, profileId: string) => Promise<void>;
+  deleteProfile: (vaultPath: string, profileId: string) => Promise<void>;
   
   // 同步操作
   computeSyncPlan: (vaultPath: string) => Promise<SyncPlan | null>;
@@ -43,6 +72,11 @@ interface WebDAVState {
   quickSync: (vaultPath: string) => Promise<SyncResult | null>;
   cancelSync: () => void;
 
+  // 中断恢复
+  checkInterruptedSync: (vaultPath: string) => Promise<void>;
+  resumeSync: (vaultPath: string) => Promise<SyncResult | null>;
+  dismissInterruptedSync: () => void;
+
   // 状态更新
   setSyncProgress: (progress: Partial<SyncProgress>) => void;
   clearError: () => void;
@@ -54,6 +88,8 @@ export const useWebDAVStore = create<WebDAVState>()(
       // 初始状态
       config: createDefaultConfig(),
       isConfigured: false,
+      profiles: [],
+      activeProfileId: null,
       isConnected: false,
       connectionError: null,
       syncProgress: {
@@ -66,41 +102,106 @@ export const useWebDAVStore = create<WebDAVState>()(
       lastSyncResult: null,
       lastSyncTime: null,
       pendingSyncPlan: null,
+      interruptedPlan: null,
 
       // 设置配置
       setConfig: (partialConfig) => {
-        set((state) => {
-          const newConfig = { ...state.config, ...partialConfig };
-          const isConfigured = newConfig.server_url.length > 0;
-          
-          // 更新服务层配置
-          if (isConfigured) {
-            webdavService.setConfig(newConfig);
-          }
-          
-          return {
-            config: newConfig,
-            isConfigured,
-            // 配置变更时重置连接状态
-            isConnected: false,
-            connectionError: null,
-          };
-        });
+        set((state) => ({
+          config: { ...state.config, ...partialConfig },
+          // 配置变更时重置连接状态
+          isConnected: false,
+          connectionError: null,
+        }));
       },
 
-      // 重置配置
+      // 新建配置（保存前不会写入后端）
       resetConfig: () => {
         set({
           config: createDefaultConfig(),
           isConfigured: false,
+          activeProfileId: null,
           isConnected: false,
           connectionError: null,
         });
       },
 
+      // 加载 vault 的配置列表，并切换到当前配置
+      loadProfiles: async (vaultPath) => {
+        try {
+          const { active, profiles } = await listSyncProfiles(vaultPath);
+          const current = profiles.find((p) => p.id === active) ?? null;
+          set({
+            profiles,
+            activeProfileId: current?.id ?? null,
+            config: current?.config ?? createDefaultConfig(),
+            isConfigured: current !== null,
+            isConnected: false,
+          });
+          applyAutoSync(vaultPath, current !== null);
+        } catch (error) {
+          const message = error instanceof Error ? error.message : String(error);
+          set({ connectionError: message });
+        }
+      },
+
+      // 保存当前表单（密码留空时沿用已保存的值）
+      saveProfile: async (vaultPath, name) => {
+        const { config, activeProfileId } = get();
+        try {
+          const saved = await saveSyncProfile(vaultPath, {
+            id: activeProfileId ?? '',
+            name,
+            config,
+          });
+          if (!activeProfileId) {
+            // 新建的配置设为当前配置
+            await setActiveSyncProfile(vaultPath, saved.id);
+          }
+          const { profiles } = await listSyncProfiles(vaultPath);
+          set({
+            profiles,
+            activeProfileId: saved.id,
+            // 保留表单中的密码，避免再次保存前清空输入框
+            config: { ...saved.config, password: config.password, encryption: config.encryption },
+            isConfigured: true,
+          });
+          // auto_sync 可能刚被开启或关闭
+          applyAutoSync(vaultPath, true);
+          return true;
+        } catch (error) {
+          const message = error instanceof Error ? error.message : String(error);
+          set({ connectionError: message });
+          return false;
+        }
+      },
+
+      // 切换当前配置
+      selectProfile: async (vaultPath, profileId) => {
+        try {
+          await setActiveSyncProfile(vaultPath, profileId);
+        } catch (error) {
+          const message = error instanceof Error ? error.message : String(error);
+          set({ connectionError: message });
+          return;
+        }
+        await get().loadProfiles(vaultPath);
+      },
+
+      // 删除配置
+      deleteProfile: async (vaultPath, profileId) => {
+        try {
+          await deleteSyncProfile(vaultPath, profileId);
+        } catch (error) {
+          const message = error instanceof Error ? error.message : String(error);
+          set({ connectionError: message });
+          return;
+        }
+        await get().loadProfiles(vaultPath);
+      },
+
       // 测试连接
-      testConnection: async () => {
-        const { config, isConfigured } = get();
+      testConnection: async (vaultPath) => {
+        const { activeProfileId, isConfigured } = get();
         
         if (!isConfigured) {
           set({ connectionError: 'WebDAV not configured' });
@@ -113,7 +214,8 @@ export const useWebDAVStore = create<WebDAVState>()(
         });
 
         try {
-          const success = await testWebDAVConnection(config);
+          webdavService.use(vaultPath, activeProfileId);
+          const success = await webdavService.testConnection();
           set({
             isConnected: success,
             connectionError: success ? null : 'Connection failed',
@@ -133,7 +235,7 @@ export const useWebDAVStore = create<WebDAVState>()(
 
       // 计算同步计划
       computeSyncPlan: async (vaultPath) => {
-        const { config, isConfigured } = get();
+        const { activeProfileId, isConfigured } = get();
         
         if (!isConfigured) {
           set({ connectionError: 'WebDAV not configured' });
@@ -151,8 +253,8 @@ export const useWebDAVStore = create<WebDAVState>()(
         });
 
         try {
-          webdavService.setConfig(config);
-          const plan = await webdavService.computeSyncPlan(vaultPath);
+          webdavService.use(vaultPath, activeProfileId);
+          const plan = await webdavService.computeSyncPlan();
           
           set({
             pendingSyncPlan: plan,
@@ -172,7 +274,7 @@ export const useWebDAVStore = create<WebDAVState>()(
 
       // 执行同步
       executeSync: async (vaultPath, plan) => {
-        const { config, isConfigured, pendingSyncPlan } = get();
+        const { activeProfileId, isConfigured, pendingSyncPlan } = get();
         const syncPlan = plan || pendingSyncPlan;
         
         if (!isConfigured) {
@@ -196,19 +298,19 @@ export const useWebDAVStore = create<WebDAVState>()(
         });
 
         try {
-          webdavService.setConfig(config);
-          const result = await webdavService.executeSync(vaultPath, syncPlan);
+          webdavService.use(vaultPath, activeProfileId);
+          const result = await webdavService.executeSync(syncPlan);
           
           set({
             lastSyncResult: result,
             lastSyncTime: Date.now(),
             pendingSyncPlan: null,
             syncProgress: {
-              stage: result.success ? 'Completed' : 'Error',
+              stage: result.cancelled ? 'Cancelled' : result.success ? 'Completed' : 'Error',
               total: syncPlan.items.length,
               processed: syncPlan.items.length,
               current_file: null,
-              error: result.success ? null : 'Sync completed with errors',
+              error: result.success || result.cancelled ? null : 'Sync completed with errors',
             },
           });
           
@@ -225,7 +327,7 @@ export const useWebDAVStore = create<WebDAVState>()(
 
       // 快速同步
       quickSync: async (vaultPath) => {
-        const { config, isConfigured } = get();
+        const { activeProfileId, isConfigured } = get();
         
         if (!isConfigured) {
           set({ connectionError: 'WebDAV not configured' });
@@ -243,18 +345,18 @@ export const useWebDAVStore = create<WebDAVState>()(
         });
 
         try {
-          webdavService.setConfig(config);
-          const result = await webdavService.quickSync(vaultPath);
+          webdavService.use(vaultPath, activeProfileId);
+          const result = await webdavService.quickSync();
           
           set({
             lastSyncResult: result,
             lastSyncTime: Date.now(),
             syncProgress: {
-              stage: result.success ? 'Completed' : 'Error',
+              stage: result.cancelled ? 'Cancelled' : result.success ? 'Completed' : 'Error',
               total: result.uploaded + result.downloaded + result.deleted,
               processed: result.uploaded + result.downloaded + result.deleted,
               current_file: null,
-              error: result.success ? null : 'Sync completed with errors',
+              error: result.success || result.cancelled ? null : 'Sync completed with errors',
             },
           });
           
@@ -271,6 +373,9 @@ export const useWebDAVStore = create<WebDAVState>()(
 
       // 取消同步
       cancelSync: () => {
+        cancelWebDAVSync().catch((error) => {
+          console.error('[WebDAV] Failed to cancel sync:', error);
+        });
         set({
           syncProgress: {
             stage: 'Idle',
@@ -283,6 +388,68 @@ export const useWebDAVStore = create<WebDAVState>()(
         });
       },
 
+      // 检查上次被中断的同步（开启 auto_sync 时由后台调度器自动恢复，无需提示）
+      checkInterruptedSync: async (vaultPath) => {
+        const { isConfigured, config } = get();
+        if (!isConfigured || config.auto_sync) {
+          set({ interruptedPlan: null });
+          return;
+        }
+        try {
+          const plan = await getInterruptedWebDAVSync(vaultPath);
+          set({ interruptedPlan: plan && plan.items.length > 0 ? plan : null });
+        } catch (error) {
+          console.error('[WebDAV] Failed to check interrupted sync:', error);
+        }
+      },
+
+      // 恢复被中断的同步（已完成的条目不会重复执行）
+      resumeSync: async (vaultPath) => {
+        const { activeProfileId, interruptedPlan } = get();
+        set({
+          interruptedPlan: null,
+          syncProgress: {
+            stage: 'Syncing',
+            total: interruptedPlan?.items.length ?? 0,
+            processed: 0,
+            current_file: null,
+            error: null,
+          },
+        });
+
+        try {
+          webdavService.use(vaultPath, activeProfileId);
+          const result = await webdavService.resumeSync();
+          if (!result) {
+            set({ syncProgress: { ...get().syncProgress, stage: 'Idle' } });
+            return null;
+          }
+          set({
+            lastSyncResult: result,
+            lastSyncTime: Date.now(),
+            syncProgress: {
+              stage: result.cancelled ? 'Cancelled' : result.success ? 'Completed' : 'Error',
+              total: result.uploaded + result.downloaded + result.deleted,
+              processed: result.uploaded + result.downloaded + result.deleted,
+              current_file: null,
+              error: result.success || result.cancelled ? null : 'Sync completed with errors',
+            },
+          });
+          return result;
+        } catch (error) {
+          const message = error instanceof Error ? error.message : String(error);
+          set({
+            syncProgress: { ...get().syncProgress, stage: 'Error', error: message },
+            connectionError: message,
+          });
+          return null;
+        }
+      },
+
+      dismissInterruptedSync: () => {
+        set({ interruptedPlan: null });
+      },
+
       // 更新同步进度
       setSyncProgress: (progress) => {
         set((state) => ({
@@ -301,11 +468,7 @@ export const useWebDAVStore = create<WebDAVState>()(
     {
       name: 'lumina-webdav-config',
       partialize: (state) => ({
-        // 只持久化配置，不存储密码
-        config: {
-          ...state.config,
-          password: '', // 不保存密码到 localStorage
-        },
+        // 配置由后端保存，这里不再持久化（避免密码写入 localStorage）
         lastSyncTime: state.lastSyncTime,
       }),
     }
@@ -317,7 +480,8 @@ export function useSyncStatusText(): string {
   const { syncProgress, isConnected, isConfigured } = useWebDAVStore();
 
   if (!isConfigured) return 'Not configured';
-  if (!isConnected) return 'Disconnected';
+  // 后台自动同步运行时即使未测试连接也显示其进度
+  if (!isConnected && syncProgress.stage === 'Idle') return 'Disconnected';
 
   switch (syncProgress.stage) {
     case 'Idle':
@@ -334,6 +498,8 @@ export function useSyncStatusText(): string {
       return `Syncing ${syncProgress.processed}/${syncProgress.total}`;
     case 'Completed':
       return 'Sync complete';
+    case 'Cancelled':
+      return 'Sync cancelled';
     case 'Error':
       return `Error: ${syncProgress.error || 'Unknown error'}`;
     default: